@@ -25,6 +25,47 @@
  */
 #define Color_NUM 2
 
+/**
+ * Returned by the C functions when the result does not fit in the given buffer.
+ *
+ * A buffer of [`MAX_SINGLE_MOVE_BYTES`] bytes is always large enough for a single move.
+ */
+#define KIFU_ERR_BUFFER_TOO_SMALL -2
+
+/**
+ * Returned by the C functions when a move cannot be rendered or made in the given position.
+ *
+ * The values of the `KIFU_ERR_*` constants are stable and never reused for another meaning.
+ */
+#define KIFU_ERR_ILLEGAL_MOVE -1
+
+/**
+ * Returned by the C functions when a record cannot be parsed.
+ */
+#define KIFU_ERR_INVALID_RECORD -4
+
+/**
+ * Returned by the C functions when an index is out of range.
+ */
+#define KIFU_ERR_OUT_OF_RANGE -3
+
+/**
+ * The maximum length in bytes of a string that [`display_single_move_write`] and [`display_single_move_write_kansuji`] write.
+ *
+ * The longest ones look like `▲２２銀左上不成`: a side, two digits, a piece, two characters for disambiguation and `不成`, all of which take 3 bytes per character in UTF-8.
+ * Promoted pieces that take two characters (such as `成銀`) cannot be followed by `不成`, so they never make a longer string.
+ * The bound also holds for every [`Style`] except those in [`Notation::Yomi`] or with [`Style::hand_changes`] set:
+ * the other options only change characters into others of the same length, or add `打` to moves that have no other suffixes.
+ * [`required_len`] finds the exact length of a move.
+ *
+ * Examples:
+ * ```
+ * # use shogi_official_kifu::MAX_SINGLE_MOVE_BYTES;
+ * assert_eq!(MAX_SINGLE_MOVE_BYTES, "▲２２銀左上不成".len());
+ * ```
+ */
+#define MAX_SINGLE_MOVE_BYTES 24
+
 #define PieceKind_OPTION_NUM 15
 
 /**
@@ -49,6 +90,13 @@ enum Color {
 };
 typedef uint8_t Color;
 
+/**
+ * A record parsed by [`kifu_record_parse_csa`], together with its moves rendered in the official notation.
+ *
+ * C code only sees pointers to it and frees it with [`kifu_record_free`].
+ */
+typedef struct KifuRecord KifuRecord;
+
 /**
  * A hand of a single player. A hand is a multiset of unpromoted pieces (except a king).
  *
@@ -141,30 +189,153 @@ typedef struct PartialPosition {
  */
 typedef uint16_t CompactMove;
 
+/**
+ * A function that receives one rendered move from [`display_compactmoves_streaming`].
+ *
+ * `ptr` points to `len` bytes of UTF-8 with no terminating NUL, which are valid only during the call.
+ * `user_data` is passed through as it is.
+ */
+typedef void (*KifuMoveCallback)(void *user_data, const uint8_t *ptr, size_t len);
+
+
+
+
+
+/**
+ * Finds the string representations of `len` moves made one after another from `position`,
+ * and passes each of them to `callback` as soon as it is found.
+ *
+ * Each move is written into a buffer of [`MAX_SINGLE_MOVE_BYTES`] bytes on the stack and passed to `callback`
+ * before the next one is found, so a whole game can be converted without a buffer that holds all of it.
+ * Finding the pieces that can move to a square still allocates a short-lived list for every move.
+ * Returns 0 on success, or [`KIFU_ERR_ILLEGAL_MOVE`] at the first move that cannot be rendered or made;
+ * the moves before it have already been passed to `callback`.
+ *
+ * # Safety
+ * `moves` must point to `len` valid [`CompactMove`]s, and `callback` must be safe to call with `user_data`.
+ *
+ * Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
+ */
+int32_t display_compactmoves_streaming(const struct PartialPosition *position,
+                                       const CompactMove *moves,
+                                       size_t len,
+                                       KifuMoveCallback callback,
+                                       void *user_data);
+
 /**
  * Finds the string representation of a [`Move`] and write it to a [`u8`] pointer.
  *
+ * Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+ * On [`KIFU_ERR_BUFFER_TOO_SMALL`], a prefix of the result may have been written.
+ *
  * # Safety
- * `ptr` must have enough space for the result.
+ * `ptr` must be valid for writes of `len` bytes.
+ * [`MAX_SINGLE_MOVE_BYTES`] bytes are always enough.
+ * No terminating NUL is written.
+ * [`display_single_move_to_slice`] does the same without `unsafe`.
  *
  * Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
  */
-bool display_single_compactmove(const struct PartialPosition *position,
-                                CompactMove mv,
-                                uint8_t *ptr);
+int32_t display_single_compactmove(const struct PartialPosition *position,
+                                   CompactMove mv,
+                                   uint8_t *ptr,
+                                   size_t len);
 
 #if defined(DEFINE_KANSUJI)
 /**
  * Finds the string representation of a [`Move`] and write it to a [`u8`] pointer.
  *
+ * Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+ * On [`KIFU_ERR_BUFFER_TOO_SMALL`], a prefix of the result may have been written.
+ *
  * # Safety
- * `ptr` must have enough space for the result.
+ * `ptr` must be valid for writes of `len` bytes.
+ * [`MAX_SINGLE_MOVE_BYTES`] bytes are always enough.
+ * No terminating NUL is written.
  *
  * Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
  */
-bool display_single_compactmove_kansuji(const struct PartialPosition *position,
-                                        CompactMove mv,
-                                        uint8_t *ptr);
+int32_t display_single_compactmove_kansuji(const struct PartialPosition *position,
+                                           CompactMove mv,
+                                           uint8_t *ptr,
+                                           size_t len);
 #endif
 
+/**
+ * Writes the name of Black into `ptr`.
+ *
+ * Returns the number of bytes written, which is 0 if the name is not given, or a negative `KIFU_ERR_*` code on failure.
+ *
+ * # Safety
+ * `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+ */
+int32_t kifu_record_black_name(const struct KifuRecord *record,
+                               uint8_t *ptr,
+                               size_t len);
+
+/**
+ * Frees a record returned by [`kifu_record_parse_csa`]. Does nothing if `record` is null.
+ *
+ * # Safety
+ * `record` must be null or a pointer returned by [`kifu_record_parse_csa`] that is not freed yet.
+ */
+void kifu_record_free(struct KifuRecord *record);
+
+/**
+ * Writes the move at `index`, counting from 0, in the official notation into `ptr`.
+ *
+ * Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+ * [`KIFU_ERR_OUT_OF_RANGE`] is returned if there is no move at `index`.
+ *
+ * # Safety
+ * `ptr` must be valid for writes of `len` bytes.
+ * [`MAX_SINGLE_MOVE_BYTES`](crate::MAX_SINGLE_MOVE_BYTES) bytes are always enough.
+ * No terminating NUL is written.
+ */
+int32_t kifu_record_move(const struct KifuRecord *record, size_t index, uint8_t *ptr, size_t len);
+
+/**
+ * Returns the number of moves in `record`.
+ */
+size_t kifu_record_move_count(const struct KifuRecord *record);
+
+/**
+ * Parses a record in the CSA format and stores a pointer to it in `out`.
+ *
+ * Returns 0 on success, or [`KIFU_ERR_INVALID_RECORD`] if `ptr` is not valid UTF-8, not a valid record
+ * or larger than [`ReadLimits::DEFAULT`](crate::ReadLimits::DEFAULT) allows, in which case `out` is left untouched.
+ * [`csa::parse`] does not panic on any input, so malformed records never unwind into C.
+ * The record must be freed with [`kifu_record_free`].
+ *
+ * # Safety
+ * `ptr` must be valid for reads of `len` bytes, and `out` must be valid for a write.
+ */
+int32_t kifu_record_parse_csa(const uint8_t *ptr,
+                              size_t len,
+                              struct KifuRecord **out);
+
+/**
+ * Writes the special move that ended the game, such as `TORYO`, into `ptr`.
+ *
+ * Returns the number of bytes written, which is 0 if the record has no result, or a negative `KIFU_ERR_*` code on failure.
+ *
+ * # Safety
+ * `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+ */
+int32_t kifu_record_result(const struct KifuRecord *record,
+                           uint8_t *ptr,
+                           size_t len);
+
+/**
+ * Writes the name of White into `ptr`.
+ *
+ * Returns the number of bytes written, which is 0 if the name is not given, or a negative `KIFU_ERR_*` code on failure.
+ *
+ * # Safety
+ * `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+ */
+int32_t kifu_record_white_name(const struct KifuRecord *record,
+                               uint8_t *ptr,
+                               size_t len);
+
 #endif /* shogi_official_kifu_bindings_h */
@@ -0,0 +1,6 @@
+fn main() {
+    // The benchmarks are built on nightly with `RUSTFLAGS="--cfg bench"`.
+    // `[lints]` in Cargo.toml would need a newer Cargo than `rust-version`, and older ones ignore this line.
+    println!("cargo:rustc-check-cfg=cfg(bench)");
+    println!("cargo:rerun-if-changed=build.rs");
+}
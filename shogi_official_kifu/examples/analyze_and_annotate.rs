@@ -0,0 +1,80 @@
+//! Annotates every move of a game with its properties.
+//!
+//! ```text
+//! echo "startpos moves 7g7f 3c3d 8h2b+ 3a2b" | cargo run --example analyze_and_annotate
+//! ```
+//!
+//! Reads one game in USI format from the standard input, and prints each move in the official notation
+//! followed by whether it captures, promotes, drops or gives check.
+
+use std::io::Read;
+
+use shogi_core::Position;
+use shogi_official_kifu::{classify, display_single_move, GameRecord, Replay};
+use shogi_usi_parser::FromUsi;
+
+fn main() {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .expect("failed to read the standard input");
+    match annotate(&input) {
+        Some(lines) => {
+            for line in lines {
+                println!("{}", line);
+            }
+        }
+        None => eprintln!("invalid game"),
+    }
+}
+
+/// Renders every move of a game in USI format with its annotations.
+fn annotate(usi: &str) -> Option<Vec<String>> {
+    let position = Position::from_usi(usi.trim()).ok()?;
+    let record = GameRecord::with_moves(
+        position.initial_position().clone(),
+        position.moves().iter().copied(),
+    );
+    let mut replay = Replay::new(&record);
+    let mut lines = Vec::new();
+    for mv in record.moves() {
+        let mut line = display_single_move(replay.position(), mv)?;
+        let class = classify(replay.position(), mv)?;
+        let notes: Vec<&str> = [
+            (class.capture, "capture"),
+            (class.promotion, "promotion"),
+            (class.drop, "drop"),
+            (class.check, "check"),
+        ]
+        .iter()
+        .filter(|&&(applies, _)| applies)
+        .map(|&(_, note)| note)
+        .collect();
+        if !notes.is_empty() {
+            line.push_str(&format!(" ({})", notes.join(", ")));
+        }
+        lines.push(line);
+        replay.forward()?;
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotate_works() {
+        assert_eq!(
+            annotate("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap(),
+            [
+                "▲７６歩",
+                "△３４歩",
+                "▲２２角成 (capture, promotion)",
+                "△同銀 (capture)",
+                "▲４５角 (drop)",
+            ],
+        );
+        assert_eq!(annotate("startpos moves 7g7g"), None);
+    }
+}
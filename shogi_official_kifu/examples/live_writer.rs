@@ -0,0 +1,81 @@
+//! Writes moves in the official notation as they are played.
+//!
+//! ```text
+//! cargo run --example live_writer
+//! ```
+//!
+//! Reads one move in USI format (such as `7g7f`) per line from the standard input,
+//! starting from the initial position, and prints each move as soon as it is read.
+//! Illegal moves are reported and ignored.
+
+use std::io::BufRead;
+
+use shogi_core::{LegalityChecker, Move, PartialPosition};
+use shogi_legality_lite::LiteLegalityChecker;
+use shogi_official_kifu::{caption, display_single_move};
+use shogi_usi_parser::FromUsi;
+
+fn main() {
+    let mut writer = LiveWriter::new(PartialPosition::startpos());
+    for line in std::io::stdin().lock().lines() {
+        let line = line.expect("failed to read the standard input");
+        match writer.play(line.trim()) {
+            Ok(rendered) => println!("{}", rendered),
+            Err(message) => eprintln!("{}: {}", line, message),
+        }
+    }
+    if let Some(caption) = writer.caption() {
+        println!("{}", caption);
+    }
+}
+
+/// A game in progress.
+struct LiveWriter {
+    position: PartialPosition,
+    // The position before the last move and the last move, for the caption.
+    last: Option<(PartialPosition, Move)>,
+}
+
+impl LiveWriter {
+    fn new(position: PartialPosition) -> Self {
+        Self {
+            position,
+            last: None,
+        }
+    }
+
+    /// Makes a move given in USI format, and returns it in the official notation.
+    fn play(&mut self, usi: &str) -> Result<String, &'static str> {
+        let mv = Move::from_usi(usi).map_err(|_| "not a move")?;
+        if !LiteLegalityChecker.is_legal_partial_lite(&self.position, mv) {
+            return Err("illegal move");
+        }
+        let rendered = display_single_move(&self.position, mv).ok_or("cannot be written")?;
+        let previous = self.position.clone();
+        self.position.make_move(mv).ok_or("illegal move")?;
+        self.last = Some((previous, mv));
+        Ok(rendered)
+    }
+
+    /// Returns the caption of the current position, such as `図は▲７六歩まで`.
+    fn caption(&self) -> Option<String> {
+        let (position, mv) = self.last.as_ref()?;
+        caption(position, *mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_works() {
+        let mut writer = LiveWriter::new(PartialPosition::startpos());
+        assert_eq!(writer.caption(), None);
+        assert_eq!(writer.play("7g7f").as_deref(), Ok("▲７６歩"));
+        assert_eq!(writer.play("7g7f"), Err("illegal move"));
+        assert_eq!(writer.play("hello"), Err("not a move"));
+        assert_eq!(writer.play("3c3d").as_deref(), Ok("△３４歩"));
+        assert_eq!(writer.caption().as_deref(), Some("図は△３四歩まで"));
+    }
+}
@@ -0,0 +1,58 @@
+//! Converts games in USI format into KIF documents.
+//!
+//! ```text
+//! echo "startpos moves 7g7f 3c3d" | cargo run --example usi_to_kif
+//! ```
+//!
+//! Every non-empty line of the standard input is one game (`startpos moves ...` or `sfen ... moves ...`).
+
+use std::io::Read;
+
+use shogi_core::Position;
+use shogi_official_kifu::{GameRecord, KifFile};
+use shogi_usi_parser::FromUsi;
+
+fn main() {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .expect("failed to read the standard input");
+    for line in input.lines().filter(|line| !line.trim().is_empty()) {
+        match convert(line) {
+            Some(kif) => print!("{}", kif),
+            None => eprintln!("invalid game: {}", line),
+        }
+    }
+}
+
+/// Converts one game in USI format into a KIF document.
+fn convert(usi: &str) -> Option<String> {
+    let position = Position::from_usi(usi.trim()).ok()?;
+    let record = GameRecord::with_moves(
+        position.initial_position().clone(),
+        position.moves().iter().copied(),
+    );
+    KifFile::new(&record).render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_works() {
+        assert_eq!(
+            convert("startpos moves 7g7f 3c3d 8h2b+ 3a2b").as_deref(),
+            Some(
+                "手合割：平手\n\
+                 手数----指手---------消費時間--\n   \
+                 1 ７六歩(77)\n   \
+                 2 ３四歩(33)\n   \
+                 3 ２二角成(88)\n   \
+                 4 同　銀(31)\n\
+                 まで4手\n",
+            ),
+        );
+        assert_eq!(convert("startpos moves 7g7g"), None);
+    }
+}
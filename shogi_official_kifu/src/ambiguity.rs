@@ -0,0 +1,119 @@
+use shogi_core::{Move, PartialPosition};
+
+use crate::{kifu_move, GameRecord};
+
+/// A move whose official notation needs suffixes to tell it from other moves, as found by [`AmbiguousMoves`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct AmbiguousMove {
+    /// The index of the move in the record, counting from 0.
+    pub ply: usize,
+    /// The move.
+    pub mv: Move,
+    /// `左`, `右` or `直`, if needed.
+    pub relative: Option<char>,
+    /// `上`, `引` or `寄`, if needed.
+    pub movement: Option<char>,
+    /// Whether `打` is needed, because a piece on the board can also move to the destination.
+    pub drop: bool,
+}
+
+/// An iterator over the moves of a [`GameRecord`] whose official notation needs suffixes
+/// (`左`, `右`, `直`, `上`, `引`, `寄` or `打`).
+///
+/// Useful for studying how often the notation is ambiguous on a collection of games,
+/// and for testing the disambiguation on randomly generated ones.
+/// The iteration stops at the first move that cannot be made.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{AmbiguousMoves, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 6i5h 4a3b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let found: Vec<_> = AmbiguousMoves::new(&record)
+///     .map(|found| (found.ply, found.relative, found.movement))
+///     .collect();
+/// // ▲５８金左 and △３二金
+/// assert_eq!(found, [(2, Some('左'), None)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct AmbiguousMoves<'a> {
+    record: &'a GameRecord,
+    position: PartialPosition,
+    ply: usize,
+}
+
+impl<'a> AmbiguousMoves<'a> {
+    /// Creates an [`AmbiguousMoves`] that scans `record` from the first move.
+    pub fn new(record: &'a GameRecord) -> Self {
+        Self {
+            record,
+            position: record.initial().clone(),
+            ply: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for AmbiguousMoves<'a> {
+    type Item = AmbiguousMove;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mv = self.record.move_at(self.ply)?;
+            let analyzed = match kifu_move::analyze(&self.position, mv) {
+                Ok(analyzed) if self.position.make_move(mv).is_some() => analyzed,
+                _ => {
+                    // Stop for good.
+                    self.ply = self.record.len();
+                    return None;
+                }
+            };
+            let ply = self.ply;
+            self.ply += 1;
+            if analyzed.relative.is_some() || analyzed.movement.is_some() || analyzed.drop {
+                return Some(AmbiguousMove {
+                    ply,
+                    mv,
+                    relative: analyzed.relative,
+                    movement: analyzed.movement,
+                    drop: analyzed.drop,
+                });
+            }
+        }
+    }
+}
+
+impl<'a> core::iter::FusedIterator for AmbiguousMoves<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn ambiguous_moves_works() {
+        let pos =
+            Position::from_usi("sfen 4k4/9/9/9/9/9/9/4G4/4K4 b G 1 moves 5i6i 5a4a G*4g").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let found: alloc::vec::Vec<_> = AmbiguousMoves::new(&record).collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].ply, 2);
+        assert!(found[0].drop);
+
+        // The scan stops at an invalid move.
+        let mut record = record;
+        record.push(Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        });
+        record.push(pos.moves()[0]);
+        let mut iter = AmbiguousMoves::new(&record);
+        assert_eq!(iter.next().map(|found| found.ply), Some(2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+}
@@ -0,0 +1,155 @@
+use shogi_core::{Color, Move, PartialPosition, Piece, PieceKind, Square};
+
+/// Returns whether `mv` made in `position` attacks the opponent's king.
+///
+/// Only the squares around the king are looked at, so this is much cheaper than enumerating the moves of the position.
+/// Moves that leave the mover's own king in check still count, as they do in the rules.
+/// Returns `false` if `mv` cannot be made in `position` or the opponent has no king.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::gives_check;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b N 1").unwrap();
+/// let check = Move::Drop {
+///     piece: Piece::B_N,
+///     to: Square::SQ_4C,
+/// };
+/// assert!(gives_check(&pos, check));
+/// let quiet = Move::Drop {
+///     piece: Piece::B_N,
+///     to: Square::SQ_5C,
+/// };
+/// assert!(!gives_check(&pos, quiet));
+/// ```
+pub fn gives_check(position: &PartialPosition, mv: Move) -> bool {
+    let mut next = position.clone();
+    if next.make_move(mv).is_none() {
+        return false;
+    }
+    let side = next.side_to_move();
+    match next.king_position(side) {
+        Some(king) => is_attacked(&next, king, side.flip()),
+        None => false,
+    }
+}
+
+/// Returns whether a piece of `attacker` can move to `square`, ignoring the safety of `attacker`'s king.
+pub(crate) fn is_attacked(position: &PartialPosition, square: Square, attacker: Color) -> bool {
+    // Directions are seen from `attacker`, for which a negative rank delta points forward.
+    // `sign` turns them into deltas on the board.
+    let sign: i8 = if attacker == Color::Black { 1 } else { -1 };
+    for direction in [
+        (-1, -1),
+        (0, -1),
+        (1, -1),
+        (-1, 0),
+        (1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 1),
+    ] {
+        // Walk from `square` towards the attacking piece, that is, against the direction it moves in.
+        let mut distance = 1;
+        while let Some(from) = offset(
+            square,
+            -direction.0 * distance,
+            -direction.1 * sign * distance,
+        ) {
+            if let Some(piece) = position.piece_at(from) {
+                if piece.color() == attacker
+                    && moves_in(piece.piece_kind(), direction, distance == 1)
+                {
+                    return true;
+                }
+                break;
+            }
+            distance += 1;
+        }
+    }
+    // A knight moves by (±1, -2).
+    for file_delta in [-1, 1] {
+        if let Some(from) = offset(square, file_delta, 2 * sign) {
+            if position.piece_at(from) == Some(Piece::new(PieceKind::Knight, attacker)) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Returns whether a piece of `piece_kind` moves in `direction`, seen from its owner, by one square if `adjacent` or by more squares otherwise.
+fn moves_in(piece_kind: PieceKind, direction: (i8, i8), adjacent: bool) -> bool {
+    let diagonal = direction.0 != 0 && direction.1 != 0;
+    let slides = match piece_kind {
+        PieceKind::Lance => direction == (0, -1),
+        PieceKind::Bishop | PieceKind::ProBishop => diagonal,
+        PieceKind::Rook | PieceKind::ProRook => !diagonal,
+        _ => false,
+    };
+    if slides {
+        return true;
+    }
+    if !adjacent {
+        return false;
+    }
+    match piece_kind {
+        PieceKind::Pawn => direction == (0, -1),
+        PieceKind::Silver => direction.1 != 0 && direction != (0, 1),
+        PieceKind::Gold
+        | PieceKind::ProPawn
+        | PieceKind::ProLance
+        | PieceKind::ProKnight
+        | PieceKind::ProSilver => direction.1 <= 0 || direction == (0, 1),
+        PieceKind::King | PieceKind::ProBishop | PieceKind::ProRook => true,
+        PieceKind::Lance | PieceKind::Knight | PieceKind::Bishop | PieceKind::Rook => false,
+    }
+}
+
+fn offset(square: Square, file_delta: i8, rank_delta: i8) -> Option<Square> {
+    let file = square.file() as i8 + file_delta;
+    let rank = square.rank() as i8 + rank_delta;
+    if (1..=9).contains(&file) && (1..=9).contains(&rank) {
+        Square::new(file as u8, rank as u8)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{LegalityChecker, Position};
+    use shogi_legality_lite::LiteLegalityChecker;
+    use shogi_usi_parser::FromUsi;
+
+    // Compares with the attacks found by enumerating every move of the attacker.
+    #[test]
+    fn gives_check_matches_enumeration() {
+        let sfens = [
+            "startpos",
+            "sfen 4k4/9/9/9/9/9/9/9/4K4 b RBGSNLP2rbgsnl2p 1",
+            "sfen 4k4/9/9/9/9/9/9/9/4K4 w RBGSNLP2rbgsnl2p 2",
+            "sfen 9/9/3+P1+L3/2+BSkN3/3G1+R3/9/9/9/4K4 b - 1",
+            "sfen 4k4/9/4P4/9/9/9/1+p4s1s/+p8/+p+p+p1K1ss1 w L 2",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+",
+        ];
+        for sfen in sfens {
+            let pos = Position::from_usi(sfen).unwrap();
+            let pos = pos.inner();
+            for mv in LiteLegalityChecker.all_legal_moves_partial(pos) {
+                let mut next = pos.clone();
+                next.make_move(mv).unwrap();
+                let side = next.side_to_move();
+                let king = Piece::new(PieceKind::King, side);
+                let mut attacker = next.clone();
+                attacker.side_to_move_set(side.flip());
+                let expected = shogi_legality_lite::prelegality::all_valid_moves(&attacker)
+                    .into_iter()
+                    .any(|mv| matches!(mv, Move::Normal { to, .. } if next.piece_at(to) == Some(king)));
+                assert_eq!(gives_check(pos, mv), expected, "{} {:?}", sfen, mv);
+            }
+        }
+    }
+}
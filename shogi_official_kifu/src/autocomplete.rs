@@ -0,0 +1,95 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use shogi_core::{LegalityChecker, Move, PartialPosition};
+use shogi_legality_lite::LiteLegalityChecker;
+
+use crate::{RenderedMoves, KANSUJI, SANYOU_SUJI};
+
+/// Finds every legal move whose official notation starts with `prefix`, for autocompletion in text boxes.
+///
+/// Returns pairs of the move in the official notation and the move, sorted by the notation.
+/// `prefix` is matched loosely so that it can be typed easily:
+/// - the side (`▲`/`△`) may be omitted,
+/// - digits may be in ASCII (`56`), full width (`５６`) or kansuji (`５六`),
+/// - `王` and `龍` are read as `玉` and `竜`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_official_kifu::autocomplete;
+/// let pos = PartialPosition::startpos();
+/// let completions: Vec<_> = autocomplete(&pos, "▲５八")
+///     .into_iter()
+///     .map(|(rendered, _)| rendered)
+///     .collect();
+/// assert_eq!(completions, ["▲５８玉", "▲５８金右", "▲５８金左", "▲５８飛"]);
+/// assert_eq!(autocomplete(&pos, "76").len(), 1);
+/// assert!(autocomplete(&pos, "△").is_empty());
+/// ```
+pub fn autocomplete(position: &PartialPosition, prefix: &str) -> Vec<(String, Move)> {
+    let prefix: String = prefix.chars().map(normalize).collect();
+    let with_side = prefix.starts_with(&['▲', '△'][..]);
+    let moves = LiteLegalityChecker.all_legal_moves_partial(position);
+    let mut rendered = RenderedMoves::new();
+    let mut found = Vec::new();
+    for mv in moves {
+        if rendered.push_move(position, mv).is_none() {
+            continue;
+        }
+        let s = rendered
+            .get(rendered.len() - 1)
+            .expect("a move was just pushed");
+        let s_without_side = s.trim_start_matches(&['▲', '△'][..]);
+        if (if with_side { s } else { s_without_side }).starts_with(&prefix) {
+            found.push((rendered.len() - 1, mv));
+        }
+    }
+    let mut ret: Vec<_> = found
+        .into_iter()
+        .map(|(index, mv)| {
+            let s = rendered.get(index).expect("index is in range");
+            (String::from(s), mv)
+        })
+        .collect();
+    ret.sort_by(|(a, _), (b, _)| a.cmp(b));
+    ret
+}
+
+fn normalize(c: char) -> char {
+    if let Some(index) = KANSUJI.iter().position(|&d| d == c) {
+        return SANYOU_SUJI[index];
+    }
+    match c {
+        '1'..='9' => SANYOU_SUJI[(c as u8 - b'1') as usize],
+        '王' => '玉',
+        '龍' => '竜',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn autocomplete_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let pos = pos.inner();
+        let completions: Vec<_> = autocomplete(pos, "２二")
+            .into_iter()
+            .map(|(rendered, _)| rendered)
+            .collect();
+        assert_eq!(completions, ["▲２２角不成", "▲２２角成"]);
+        // Every legal move completes the empty prefix.
+        assert_eq!(
+            autocomplete(pos, "").len(),
+            LiteLegalityChecker.all_legal_moves_partial(pos).len(),
+        );
+        for (rendered, mv) in autocomplete(pos, "▲") {
+            assert_eq!(crate::parse_single_move(pos, &rendered), Ok(mv));
+        }
+        assert!(autocomplete(pos, "▲５一").is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use shogi_core::{Move, PartialPosition};
+
+use crate::{display_single_move_write, GameRecord};
+
+/// Rendered moves stored in one contiguous buffer.
+///
+/// Rendering a large corpus into a `Vec<String>` allocates once per move.
+/// [`RenderedMoves`] appends every move to a single [`String`] and only remembers where each one ends,
+/// so the number of allocations grows with the logarithm of the total length instead.
+/// Calling [`RenderedMoves::clear`] between batches keeps the buffers for reuse.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, RenderedMoves};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut rendered = RenderedMoves::new();
+/// rendered.push_record(&record).unwrap();
+/// assert_eq!(rendered.len(), 4);
+/// assert_eq!(rendered.get(3), Some("△同銀"));
+/// assert_eq!(
+///     rendered.iter().collect::<Vec<_>>(),
+///     ["▲７６歩", "△３４歩", "▲２２角成", "△同銀"],
+/// );
+/// assert_eq!(rendered.as_str(), "▲７６歩△３４歩▲２２角成△同銀");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RenderedMoves {
+    buffer: String,
+    // ends[i] is the end of the i-th move in buffer, which is also the start of the next one.
+    ends: Vec<usize>,
+}
+
+impl RenderedMoves {
+    /// Creates an empty [`RenderedMoves`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of moves.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns whether there are no moves.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the `index`-th move, counting from 0.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let end = *self.ends.get(index)?;
+        Some(&self.buffer[self.start(index)..end])
+    }
+
+    /// Returns the moves in the order they were added.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator + '_ {
+        (0..self.ends.len()).map(move |index| &self.buffer[self.start(index)..self.ends[index]])
+    }
+
+    /// Returns all moves concatenated without separators.
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Removes every move, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.ends.clear();
+    }
+
+    /// Renders `mv` made in `position` and appends it.
+    ///
+    /// Returns [`None`] without appending anything if `mv` cannot be rendered.
+    pub fn push_move(&mut self, position: &PartialPosition, mv: Move) -> Option<()> {
+        let start = self.buffer.len();
+        if display_single_move_write(position, mv, &mut self.buffer)
+            .expect("fmt::Write for String cannot return an error")
+            .is_none()
+        {
+            self.buffer.truncate(start);
+            return None;
+        }
+        self.ends.push(self.buffer.len());
+        Some(())
+    }
+
+    /// Renders every move in `record` and appends them.
+    ///
+    /// Returns [`None`] without appending anything if a move cannot be rendered or made.
+    pub fn push_record(&mut self, record: &GameRecord) -> Option<()> {
+        let (buffer_len, ends_len) = (self.buffer.len(), self.ends.len());
+        let mut position = record.initial().clone();
+        for mv in record.moves() {
+            if self.push_move(&position, mv).is_none() || position.make_move(mv).is_none() {
+                self.buffer.truncate(buffer_len);
+                self.ends.truncate(ends_len);
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn start(&self, index: usize) -> usize {
+        match index.checked_sub(1) {
+            Some(previous) => self.ends[previous],
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_single_move;
+    use shogi_core::{Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn push_record_matches_display_single_move() {
+        let pos = Position::from_usi(
+            "startpos moves 2g2f 8c8d 2f2e 8d8e 2e2d 2c2d 2h2d 8e8f 8g8f 8b8f 2d2c+ 3a3b",
+        )
+        .unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut expected = Vec::new();
+        let mut position = record.initial().clone();
+        for mv in record.moves() {
+            expected.push(display_single_move(&position, mv).unwrap());
+            position.make_move(mv).unwrap();
+        }
+        let mut rendered = RenderedMoves::new();
+        rendered.push_record(&record).unwrap();
+        rendered.push_record(&record).unwrap();
+        assert_eq!(rendered.len(), expected.len() * 2);
+        for (actual, expected) in rendered.iter().zip(expected.iter().chain(expected.iter())) {
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(
+            rendered.iter().next_back(),
+            expected.last().map(|s| s.as_str())
+        );
+        assert_eq!(rendered.get(rendered.len()), None);
+
+        rendered.clear();
+        assert!(rendered.is_empty());
+        assert_eq!(rendered.as_str(), "");
+    }
+
+    #[test]
+    fn push_record_is_all_or_nothing() {
+        let mut rendered = RenderedMoves::new();
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        });
+        rendered.push_record(&record).unwrap();
+        // No piece at the origin
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        assert_eq!(rendered.push_record(&record), None);
+        assert_eq!(rendered.iter().collect::<Vec<_>>(), ["▲７６歩"]);
+    }
+}
@@ -0,0 +1,149 @@
+//! Command-line tools around `shogi_official_kifu`.
+//!
+//! ```text
+//! shogi-kifu snapshot <dir>
+//! shogi-kifu verify <dir>
+//! ```
+//!
+//! Every `*.sfen` file in `<dir>` holds one game per line in USI format
+//! (`startpos moves ...` or `sfen ... moves ...`). Empty lines and lines starting with `#` are kept as they are.
+//! `snapshot` renders every game into `*.expected` next to it, one line per game with the moves in the official notation,
+//! and `verify` checks that the existing `*.expected` files match what would be rendered now.
+//! Committing the `*.expected` files makes changes to the notation reviewable as diffs.
+
+use std::path::{Path, PathBuf};
+
+use shogi_core::{Position, ToUsi};
+use shogi_official_kifu::display_single_move;
+use shogi_usi_parser::FromUsi;
+
+const USAGE: &str = "usage: shogi-kifu (snapshot | verify) <dir>";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.as_slice() {
+        [command, dir] if command == "snapshot" => snapshot(Path::new(dir)),
+        [command, dir] if command == "verify" => verify(Path::new(dir)),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Writes `*.expected` for every fixture in `dir`.
+fn snapshot(dir: &Path) -> std::io::Result<bool> {
+    for fixture in fixtures(dir)? {
+        let rendered = render(&std::fs::read_to_string(&fixture)?);
+        std::fs::write(fixture.with_extension("expected"), rendered)?;
+    }
+    Ok(true)
+}
+
+/// Returns whether every `*.expected` in `dir` matches the rendered fixture, reporting the ones that do not.
+fn verify(dir: &Path) -> std::io::Result<bool> {
+    let mut ok = true;
+    for fixture in fixtures(dir)? {
+        let rendered = render(&std::fs::read_to_string(&fixture)?);
+        let expected_path = fixture.with_extension("expected");
+        let expected = match std::fs::read_to_string(&expected_path) {
+            Ok(expected) => expected,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                println!("{}: missing", expected_path.display());
+                ok = false;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for (index, (actual, expected)) in rendered.lines().zip(expected.lines()).enumerate() {
+            if actual != expected {
+                println!("{}:{}", expected_path.display(), index + 1);
+                println!("- {}", expected);
+                println!("+ {}", actual);
+                ok = false;
+            }
+        }
+        if rendered.lines().count() != expected.lines().count() {
+            println!("{}: the number of lines differs", expected_path.display());
+            ok = false;
+        }
+    }
+    Ok(ok)
+}
+
+/// Lists `*.sfen` files in `dir` in a stable order.
+fn fixtures(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |extension| extension == "sfen")
+        {
+            fixtures.push(path);
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// Renders every game in a fixture.
+///
+/// A move that cannot be rendered is written as `-` followed by its USI representation,
+/// and an invalid line is written as `!` followed by the line itself, so that they show up in diffs.
+fn render(fixture: &str) -> String {
+    let mut ret = String::new();
+    for line in fixture.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            ret.push_str(line);
+            ret.push('\n');
+            continue;
+        }
+        let position = match Position::from_usi(line) {
+            Ok(position) => position,
+            Err(_) => {
+                ret.push('!');
+                ret.push_str(line);
+                ret.push('\n');
+                continue;
+            }
+        };
+        let mut current = position.initial_position().clone();
+        let mut moves = Vec::new();
+        for &mv in position.moves() {
+            moves.push(
+                display_single_move(&current, mv)
+                    .unwrap_or_else(|| format!("-{}", mv.to_usi_owned())),
+            );
+            current
+                .make_move(mv)
+                .expect("moves in a Position can be made");
+        }
+        ret.push_str(&moves.join(" "));
+        ret.push('\n');
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_works() {
+        let fixture = "# comment\n\nstartpos moves 7g7f 3c3d 8h2b+ 3a2b\ninvalid\n";
+        assert_eq!(
+            render(fixture),
+            "# comment\n\n▲７６歩 △３４歩 ▲２２角成 △同銀\n!invalid\n",
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use core::fmt::Write;
+use shogi_core::{Color, Hand, Move, PartialPosition, Piece, PieceKind, Square};
+
+use crate::{KingGlyphs, KANSUJI, SANYOU_SUJI};
+
+/// Writes `position` as a board diagram (BOD), the format KIF uses for positions other than the standard ones.
+///
+/// Every line ends with `\n`, and every piece in hand is followed by a full-width space as Kifu for Windows does.
+/// A line saying `後手番` is added if White is to move. Kings are written as `kings` says.
+pub(crate) fn write_bod<W: Write>(
+    position: &PartialPosition,
+    kings: KingGlyphs,
+    w: &mut W,
+) -> core::fmt::Result {
+    write_hand("後手の持駒：", position.hand_of_a_player(Color::White), w)?;
+    w.write_char(' ')?;
+    for &file in SANYOU_SUJI.iter().rev() {
+        w.write_char(' ')?;
+        w.write_char(file)?;
+    }
+    w.write_str("\n+---------------------------+\n")?;
+    for rank in 1..=9 {
+        w.write_char('|')?;
+        for file in (1..=9).rev() {
+            // Safety: 1 <= file, rank <= 9
+            let square = unsafe { Square::new(file, rank).unwrap_unchecked() };
+            match position.piece_at(square) {
+                Some(piece) => {
+                    w.write_char(if piece.color() == Color::Black {
+                        ' '
+                    } else {
+                        'v'
+                    })?;
+                    match piece.piece_kind() {
+                        PieceKind::King => {
+                            w.write_str(kings.glyph(piece.color(), position.side_to_move()))?
+                        }
+                        piece_kind => w.write_char(piece_kind_to_bod(piece_kind))?,
+                    }
+                }
+                None => w.write_str(" ・")?,
+            }
+        }
+        w.write_char('|')?;
+        w.write_char(*unsafe { KANSUJI.get_unchecked(rank as usize - 1) })?;
+        w.write_char('\n')?;
+    }
+    w.write_str("+---------------------------+\n")?;
+    write_hand("先手の持駒：", position.hand_of_a_player(Color::Black), w)?;
+    if position.side_to_move() == Color::White {
+        w.write_str("後手番\n")?;
+    }
+    Ok(())
+}
+
+fn write_hand<W: Write>(label: &str, hand: Hand, w: &mut W) -> core::fmt::Result {
+    w.write_str(label)?;
+    if hand_pieces(hand).next().is_none() {
+        w.write_str("なし")?;
+    }
+    for (piece_kind, count) in hand_pieces(hand) {
+        w.write_char(piece_kind_to_bod(piece_kind))?;
+        write_kansuji_count(count, w)?;
+        w.write_char('　')?;
+    }
+    w.write_char('\n')
+}
+
+/// Writes the pieces that the side to move has in hand after `mv`, such as ` (持駒: 角　歩二)`, if `mv` changes them,
+/// as [`Style::hand_changes`](crate::Style::hand_changes) does.
+///
+/// Nothing is written for a move that neither captures nor drops a piece.
+pub(crate) fn write_hand_change<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> core::fmt::Result {
+    let hand = position.hand_of_a_player(position.side_to_move());
+    let hand = match mv {
+        Move::Normal { to, .. } => position.piece_at(to).and_then(|captured| {
+            let piece_kind = captured.piece_kind();
+            hand.added(piece_kind.unpromote().unwrap_or(piece_kind))
+        }),
+        Move::Drop { piece, .. } => hand.removed(piece.piece_kind()),
+    };
+    let hand = match hand {
+        Some(hand) => hand,
+        None => return Ok(()),
+    };
+    w.write_str(" (持駒: ")?;
+    if hand_pieces(hand).next().is_none() {
+        w.write_str("なし")?;
+    }
+    for (index, (piece_kind, count)) in hand_pieces(hand).enumerate() {
+        if index > 0 {
+            w.write_char('　')?;
+        }
+        w.write_char(piece_kind_to_bod(piece_kind))?;
+        write_kansuji_count(count, w)?;
+    }
+    w.write_char(')')
+}
+
+/// Returns the kinds of the pieces in `hand` with their counts, stronger pieces first.
+fn hand_pieces(hand: Hand) -> impl Iterator<Item = (PieceKind, u8)> {
+    [
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Gold,
+        PieceKind::Silver,
+        PieceKind::Knight,
+        PieceKind::Lance,
+        PieceKind::Pawn,
+    ]
+    .into_iter()
+    .map(move |piece_kind| (piece_kind, hand.count(piece_kind).unwrap_or(0)))
+    .filter(|&(_, count)| count > 0)
+}
+
+/// Writes `count` in kansuji, omitting it if it is 1.
+pub(crate) fn write_kansuji_count<W: Write>(count: u8, w: &mut W) -> core::fmt::Result {
+    if count >= 10 {
+        w.write_char('十')?;
+    }
+    match count % 10 {
+        0 => Ok(()),
+        1 if count < 10 => Ok(()),
+        ones => w.write_char(*unsafe { KANSUJI.get_unchecked(ones as usize - 1) }),
+    }
+}
+
+/// Reads a board diagram line by line, the inverse of [`write_bod`].
+///
+/// Lines that [`write_bod`] writes for labels and borders are skipped, and so are `上手` and `下手` in place of `後手` and `先手`.
+pub(crate) struct BodReader {
+    position: PartialPosition,
+    // The number of ranks read so far.
+    ranks: u8,
+    // Whether a line of a diagram has been read.
+    seen: bool,
+}
+
+impl BodReader {
+    pub(crate) fn new() -> Self {
+        Self {
+            position: PartialPosition::empty(),
+            ranks: 0,
+            seen: false,
+        }
+    }
+
+    /// Reads `line`, returning `Some(false)` if it is not part of a board diagram and [`None`] if it is malformed.
+    pub(crate) fn read_line(&mut self, line: &str) -> Option<bool> {
+        if let Some((label, hand)) = line.split_once('：') {
+            let color = match label {
+                "先手の持駒" | "下手の持駒" => Color::Black,
+                "後手の持駒" | "上手の持駒" => Color::White,
+                _ => return Some(false),
+            };
+            *self.position.hand_of_a_player_mut(color) = read_hand(hand)?;
+        } else if let Some(cells) = line.strip_prefix('|') {
+            self.ranks += 1;
+            read_rank(&mut self.position, self.ranks, cells)?;
+        } else {
+            match line.trim() {
+                "先手番" | "下手番" => self.position.side_to_move_set(Color::Black),
+                "後手番" | "上手番" => self.position.side_to_move_set(Color::White),
+                line if line.starts_with("+---") || line.starts_with('９') => {}
+                _ => return Some(false),
+            }
+        }
+        self.seen = true;
+        Some(true)
+    }
+
+    /// Returns the position in the diagram, or [`None`] if no line of a diagram has been read.
+    pub(crate) fn position(&self) -> Option<&PartialPosition> {
+        if self.seen {
+            Some(&self.position)
+        } else {
+            None
+        }
+    }
+}
+
+fn read_rank(position: &mut PartialPosition, rank: u8, cells: &str) -> Option<()> {
+    let mut chars = cells.chars();
+    for file in (1..=9).rev() {
+        let square = Square::new(file, rank)?;
+        let color = match chars.next()? {
+            ' ' => Color::Black,
+            'v' => Color::White,
+            _ => return None,
+        };
+        let piece = match chars.next()? {
+            '・' if color == Color::Black => None,
+            c => Some(Piece::new(piece_kind_from_bod(c)?, color)),
+        };
+        position.piece_set(square, piece);
+    }
+    if chars.next()? != '|' {
+        return None;
+    }
+    Some(())
+}
+
+fn read_hand(s: &str) -> Option<Hand> {
+    let mut hand = Hand::new();
+    if s.trim() == "なし" {
+        return Some(hand);
+    }
+    for token in s.split(&[' ', '　'][..]).filter(|token| !token.is_empty()) {
+        let mut chars = token.chars();
+        let piece_kind = piece_kind_from_bod(chars.next()?)?;
+        let count = match chars.as_str() {
+            "" => 1,
+            count => read_kansuji_count(count)?,
+        };
+        for _ in 0..count {
+            hand = hand.added(piece_kind)?;
+        }
+    }
+    Some(hand)
+}
+
+/// Reads a count written by [`write_kansuji_count`], which is at most 18.
+fn read_kansuji_count(s: &str) -> Option<u8> {
+    let digit = |c: char| KANSUJI.iter().position(|&k| k == c).map(|i| i as u8 + 1);
+    let mut chars = s.chars();
+    match (chars.next()?, chars.next(), chars.next()) {
+        ('十', None, None) => Some(10),
+        ('十', Some(ones), None) => Some(10 + digit(ones)?),
+        (ones, None, None) => digit(ones),
+        _ => None,
+    }
+}
+
+fn piece_kind_from_bod(c: char) -> Option<PieceKind> {
+    Some(match c {
+        '王' => PieceKind::King,
+        '竜' => PieceKind::ProRook,
+        c => *ALL_PIECE_KINDS
+            .iter()
+            .find(|&&piece_kind| piece_kind_to_bod(piece_kind) == c)?,
+    })
+}
+
+static ALL_PIECE_KINDS: [PieceKind; 14] = [
+    PieceKind::Pawn,
+    PieceKind::Lance,
+    PieceKind::Knight,
+    PieceKind::Silver,
+    PieceKind::Gold,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+    PieceKind::King,
+    PieceKind::ProPawn,
+    PieceKind::ProLance,
+    PieceKind::ProKnight,
+    PieceKind::ProSilver,
+    PieceKind::ProBishop,
+    PieceKind::ProRook,
+];
+
+pub(crate) fn piece_kind_to_bod(piece_kind: PieceKind) -> char {
+    match piece_kind {
+        PieceKind::King => '玉',
+        PieceKind::Rook => '飛',
+        PieceKind::Bishop => '角',
+        PieceKind::Gold => '金',
+        PieceKind::Silver => '銀',
+        PieceKind::Knight => '桂',
+        PieceKind::Lance => '香',
+        PieceKind::Pawn => '歩',
+        PieceKind::ProRook => '龍',
+        PieceKind::ProBishop => '馬',
+        PieceKind::ProSilver => '全',
+        PieceKind::ProKnight => '圭',
+        PieceKind::ProLance => '杏',
+        PieceKind::ProPawn => 'と',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn write_bod_works() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4+S4/4K4 w 2Gp12P 1").unwrap();
+        let mut result = alloc::string::String::new();
+        write_bod(&pos, KingGlyphs::Gyoku, &mut result).unwrap();
+        let expected = "\
+後手の持駒：歩　
+  ９ ８ ７ ６ ５ ４ ３ ２ １
++---------------------------+
+| ・ ・ ・ ・v玉 ・ ・ ・ ・|一
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|二
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|三
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|四
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|七
+| ・ ・ ・ ・ 全 ・ ・ ・ ・|八
+| ・ ・ ・ ・ 玉 ・ ・ ・ ・|九
++---------------------------+
+先手の持駒：金二　歩十二　
+後手番
+";
+        assert_eq!(result, expected);
+
+        let mut result = alloc::string::String::new();
+        write_bod(&pos, KingGlyphs::WhiteOu, &mut result).unwrap();
+        assert!(result.contains("\n| ・ ・ ・ ・v王 ・ ・ ・ ・|一\n"));
+        assert!(result.contains("\n| ・ ・ ・ ・ 玉 ・ ・ ・ ・|九\n"));
+    }
+
+    #[test]
+    fn read_bod_round_trip() {
+        let sfens = [
+            "sfen 4k4/9/9/9/9/9/9/4+S4/4K4 w 2Gp12P 1",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "sfen 4k4/9/9/9/9/9/9/9/+R3K+B1+P b 2B18P 1",
+        ];
+        for sfen in sfens {
+            let pos = PartialPosition::from_usi(sfen).unwrap();
+            let mut written = alloc::string::String::new();
+            write_bod(&pos, KingGlyphs::BlackOu, &mut written).unwrap();
+            let mut reader = BodReader::new();
+            for line in written.lines() {
+                assert_eq!(reader.read_line(line), Some(true), "{}", line);
+            }
+            assert_eq!(reader.position(), Some(&pos));
+        }
+
+        let mut reader = BodReader::new();
+        assert_eq!(reader.read_line("先手：羽生善治"), Some(false));
+        assert_eq!(reader.read_line("| ・ ・|一"), None);
+        assert_eq!(reader.read_line("先手の持駒：歩百"), None);
+        assert_eq!(BodReader::new().position(), None);
+    }
+}
@@ -0,0 +1,113 @@
+use shogi_core::{IllegalMoveKind, LegalityChecker, Move, PartialPosition, PieceKind};
+use shogi_legality_lite::LiteLegalityChecker;
+
+/// A limit on the work that an operation may do, so that services checking records from untrusted input can bound latency.
+///
+/// Work is counted in steps, each of which takes about as long as making a move:
+/// - Making a move and counting the position after it for repetition takes 1 step, as in [`GameRecord::repetition_counts_within`](crate::GameRecord::repetition_counts_within).
+/// - Checking that a move is legal takes 1 step, and [`Budget::DROP_PAWN_MATE_STEPS`] more for a pawn drop that gives check,
+///   which needs a search for a reply to tell 打ち歩詰め (a mate by a dropped pawn), as in [`GameRecord::check_legality`](crate::GameRecord::check_legality).
+///
+/// An operation stops with [`Inconclusive`] as soon as the next step would go over the budget.
+/// Steps spent by an operation are not given back, so a budget can be shared by several operations on one request.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{Budget, GameRecord, Inconclusive};
+/// let pos = Position::from_usi("startpos moves 2h3h 8b7b 3h2h 7b8b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut budget = Budget::new(3);
+/// let result = record.repetition_counts_within(&mut budget);
+/// assert!(matches!(result, Err(Inconclusive { completed: 3, .. })));
+/// let mut budget = Budget::new(100);
+/// assert_eq!(record.repetition_counts_within(&mut budget), Ok(Some(vec![1, 1, 1, 1, 2])));
+/// assert_eq!(budget.remaining(), 96);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Budget {
+    remaining: u64,
+}
+
+/// cbindgen:ignore
+impl Budget {
+    /// The steps that checking a pawn drop for 打ち歩詰め takes on top of the step of the check itself.
+    ///
+    /// It is about the number of replies that the search may try.
+    pub const DROP_PAWN_MATE_STEPS: u64 = 64;
+
+    /// A budget that never runs out, which the operations without a budget use.
+    pub const UNLIMITED: Self = Self {
+        remaining: u64::MAX,
+    };
+
+    /// Creates a [`Budget`] of `steps` steps.
+    pub fn new(steps: u64) -> Self {
+        Self { remaining: steps }
+    }
+
+    /// Returns the number of steps left.
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Spends `steps` steps, or returns [`Inconclusive`] without spending any if fewer are left.
+    ///
+    /// `completed` is the number of units of work, such as moves, that the operation has finished.
+    pub(crate) fn spend(&mut self, steps: u64, completed: usize) -> Result<(), Inconclusive> {
+        if self.remaining == u64::MAX {
+            return Ok(());
+        }
+        self.remaining = self
+            .remaining
+            .checked_sub(steps)
+            .ok_or(Inconclusive { completed })?;
+        Ok(())
+    }
+
+    /// Checks that `mv` is legal in `position` as [`LiteLegalityChecker`] does, spending the steps it takes.
+    pub(crate) fn check_legal(
+        &mut self,
+        position: &PartialPosition,
+        mv: Move,
+        completed: usize,
+    ) -> Result<Result<(), IllegalMoveKind>, Inconclusive> {
+        let steps = match mv {
+            Move::Drop { piece, .. }
+                if piece.piece_kind() == PieceKind::Pawn && crate::gives_check(position, mv) =>
+            {
+                1 + Self::DROP_PAWN_MATE_STEPS
+            }
+            _ => 1,
+        };
+        self.spend(steps, completed)?;
+        Ok(LiteLegalityChecker.is_legal_partial(position, mv))
+    }
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// The result of an operation that ran out of its [`Budget`] before it could find the answer.
+///
+/// The answer is unknown, not negative: the record may still be valid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Inconclusive {
+    /// How many units of work, such as moves, the operation finished before the budget ran out.
+    pub completed: usize,
+}
+
+impl core::fmt::Display for Inconclusive {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "the budget ran out after {} moves", self.completed)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for Inconclusive {}
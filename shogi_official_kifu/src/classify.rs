@@ -0,0 +1,217 @@
+use shogi_core::{Move, PartialPosition};
+
+use crate::attack;
+
+/// Properties of a move, as found by [`classify`].
+///
+/// A move may have several properties at once; for example, a capture can also promote and give check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MoveClass {
+    /// Whether the move captures a piece.
+    pub capture: bool,
+    /// Whether the move promotes a piece.
+    pub promotion: bool,
+    /// Whether the move drops a piece from a hand.
+    pub drop: bool,
+    /// Whether the move attacks the opponent's king.
+    pub check: bool,
+}
+
+impl MoveClass {
+    /// Returns whether the move is a plain move of a piece on the board that neither captures, promotes nor gives check.
+    ///
+    /// Drops are never quiet, because they change the material on the board.
+    pub fn is_quiet(&self) -> bool {
+        !self.capture && !self.promotion && !self.drop && !self.check
+    }
+}
+
+/// Finds the properties of `mv` made in `position`.
+///
+/// Returns [`None`] if `mv` cannot be made in `position`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::classify;
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_8H,
+///     to: Square::SQ_2B,
+///     promote: true,
+/// };
+/// let class = classify(pos.inner(), mv).unwrap();
+/// assert!(class.capture && class.promotion && !class.drop && !class.check);
+/// assert!(!class.is_quiet());
+/// ```
+pub fn classify(position: &PartialPosition, mv: Move) -> Option<MoveClass> {
+    let mut next = position.clone();
+    next.make_move(mv)?;
+    let (capture, promotion, drop) = match mv {
+        Move::Normal { to, promote, .. } => (position.piece_at(to).is_some(), promote, false),
+        Move::Drop { .. } => (false, false, true),
+    };
+    let side = next.side_to_move();
+    let check = next
+        .king_position(side)
+        .map_or(false, |king| attack::is_attacked(&next, king, side.flip()));
+    Some(MoveClass {
+        capture,
+        promotion,
+        drop,
+        check,
+    })
+}
+
+/// Writes a sentence comparing the move the player made with a better one, for feedback in training tools.
+///
+/// Both moves are written in the official notation, each followed by its properties as found by [`classify`].
+/// Returns [`None`] if either move cannot be made in `position`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::compare_moves;
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let played = Move::Normal {
+///     from: Square::SQ_2G,
+///     to: Square::SQ_2F,
+///     promote: false,
+/// };
+/// let better = Move::Normal {
+///     from: Square::SQ_8H,
+///     to: Square::SQ_2B,
+///     promote: true,
+/// };
+/// assert_eq!(
+///     compare_moves(pos.inner(), played, better).as_deref(),
+///     Some("You played ▲２６歩, a quiet move; ▲２２角成, a capture with promotion, was better."),
+/// );
+/// assert_eq!(
+///     compare_moves(pos.inner(), better, better).as_deref(),
+///     Some("You played ▲２２角成, a capture with promotion, which was the best move."),
+/// );
+/// ```
+pub fn compare_moves(
+    position: &PartialPosition,
+    played: Move,
+    better: Move,
+) -> Option<alloc::string::String> {
+    let played_class = classify(position, played)?;
+    let better_class = classify(position, better)?;
+    let mut ret = alloc::string::String::from("You played ");
+    crate::display_single_move_write(position, played, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    ret.push_str(", ");
+    played_class.write_description(&mut ret);
+    if played == better {
+        ret.push_str(", which was the best move.");
+        return Some(ret);
+    }
+    ret.push_str("; ");
+    crate::display_single_move_write(position, better, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    ret.push_str(", ");
+    better_class.write_description(&mut ret);
+    ret.push_str(", was better.");
+    Some(ret)
+}
+
+impl MoveClass {
+    /// Appends a description such as `a capture with promotion and check` to `s`.
+    fn write_description(&self, s: &mut alloc::string::String) {
+        s.push_str(if self.drop {
+            "a drop"
+        } else if self.capture {
+            "a capture"
+        } else if self.is_quiet() {
+            "a quiet move"
+        } else {
+            "a move"
+        });
+        let extras = [(self.promotion, "promotion"), (self.check, "check")];
+        for (index, (_, extra)) in extras.iter().filter(|&&(has, _)| has).enumerate() {
+            s.push_str(if index == 0 { " with " } else { " and " });
+            s.push_str(extra);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Piece, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn classify_works() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 1").unwrap();
+        let mv = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_5B,
+        };
+        let class = classify(&pos, mv).unwrap();
+        assert!(class.drop && class.check && !class.capture && !class.promotion);
+        assert!(!class.is_quiet());
+
+        let mv = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_5E,
+        };
+        let class = classify(&pos, mv).unwrap();
+        assert_eq!(
+            class,
+            MoveClass {
+                drop: true,
+                ..MoveClass::default()
+            },
+        );
+        assert!(!class.is_quiet());
+
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/3GK4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_6I,
+            to: Square::SQ_6H,
+            promote: false,
+        };
+        let class = classify(&pos, mv).unwrap();
+        assert_eq!(class, MoveClass::default());
+        assert!(class.is_quiet());
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(classify(&pos, mv), None);
+    }
+
+    #[test]
+    fn compare_moves_works() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/4p4/9/9/9/9/9/4K4 b G 1").unwrap();
+        let played = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_1A,
+        };
+        let better = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_4B,
+        };
+        assert_eq!(
+            compare_moves(&pos, played, better).unwrap(),
+            "You played ▲１１金, a drop; ▲４２金, a drop with check, was better.",
+        );
+
+        let invalid = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(compare_moves(&pos, invalid, better), None);
+        assert_eq!(compare_moves(&pos, played, invalid), None);
+    }
+}
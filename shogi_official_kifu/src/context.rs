@@ -0,0 +1,175 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+use shogi_core::{Bitboard, Move, PartialPosition, Piece, PieceKind, Square};
+
+use crate::{bod, kifu_move, KifuError, KifuMove, Notation, Style};
+
+/// A position with the candidates of every destination found in advance, for writing many moves made in it.
+///
+/// [`display_single_move`](crate::display_single_move) enumerates the moves of the position on every call
+/// to find which pieces can move to the destination. [`NotationContext::new`] enumerates them once,
+/// so writing every move of a position, or the first moves of many principal variations, costs one enumeration.
+/// The result is the same as [`display_single_move_with_style`](crate::display_single_move_with_style).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::NotationContext;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/3S1S2K b - 1").unwrap();
+/// let context = NotationContext::new(&pos);
+/// assert_eq!(context.candidates(Piece::B_S, Square::SQ_5H).count(), 2);
+/// let mv = Move::Normal {
+///     from: Square::SQ_6I,
+///     to: Square::SQ_5H,
+///     promote: false,
+/// };
+/// assert_eq!(context.render(mv), Some("▲５８銀左".to_string()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct NotationContext<'a> {
+    position: &'a PartialPosition,
+    style: Style,
+    // candidates[piece_kind * 81 + to] are the origins of the pieces of the side to move.
+    candidates: Vec<Bitboard>,
+}
+
+impl<'a> NotationContext<'a> {
+    /// Creates a [`NotationContext`] that writes moves made in `position` in [`Style::OFFICIAL`].
+    pub fn new(position: &'a PartialPosition) -> Self {
+        let mut candidates = alloc::vec![Bitboard::empty(); PieceKind::all().len() * 81];
+        for mv in shogi_legality_lite::prelegality::all_valid_moves(position) {
+            if let Move::Normal { from, to, .. } = mv {
+                if let Some(piece) = position.piece_at(from) {
+                    candidates[index(piece.piece_kind(), to)] |= from;
+                }
+            }
+        }
+        Self {
+            position,
+            style: Style::OFFICIAL,
+            candidates,
+        }
+    }
+
+    /// Sets the [`Style`] that moves are written in.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Returns the position.
+    pub fn position(&self) -> &'a PartialPosition {
+        self.position
+    }
+
+    /// Returns the squares from which `piece` can move to `to`.
+    ///
+    /// Same as [`normal_candidates`](crate::normal_candidates), looked up instead of computed.
+    pub fn candidates(&self, piece: Piece, to: Square) -> Bitboard {
+        if piece.color() != self.position.side_to_move() {
+            return Bitboard::empty();
+        }
+        self.candidates[index(piece.piece_kind(), to)]
+    }
+
+    /// Writes `mv` into a [`String`].
+    ///
+    /// Returns [`None`] if `mv` cannot be written.
+    pub fn render(&self, mv: Move) -> Option<String> {
+        self.try_render(mv).ok()
+    }
+
+    /// Writes `mv` into a [`String`], or finds why it cannot be written.
+    pub fn try_render(&self, mv: Move) -> Result<String, KifuError> {
+        let kifu_move = self.analyze(mv)?;
+        let mut ret = String::new();
+        self.write_analyzed(&kifu_move, mv, &mut ret)
+            .expect("fmt::Write for String cannot return an error");
+        Ok(ret)
+    }
+
+    /// Writes `mv` to a [`Write`].
+    ///
+    /// Returns `Ok(None)` without writing anything if `mv` cannot be written.
+    ///
+    /// # Errors
+    /// Same as [`display_single_move_write`](crate::display_single_move_write).
+    pub fn write<W: Write>(&self, mv: Move, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+        let kifu_move = match self.analyze(mv) {
+            Ok(kifu_move) => kifu_move,
+            Err(_) => return Ok(None),
+        };
+        self.write_analyzed(&kifu_move, mv, w)?;
+        Ok(Some(()))
+    }
+
+    fn write_analyzed<W: Write>(
+        &self,
+        kifu_move: &KifuMove,
+        mv: Move,
+        w: &mut W,
+    ) -> core::fmt::Result {
+        kifu_move.write_notation(&self.style, w)?;
+        if self.style.hand_changes && self.style.notation == Notation::Japanese {
+            bod::write_hand_change(self.position, mv, w)?;
+        }
+        Ok(())
+    }
+
+    fn analyze(&self, mv: Move) -> Result<KifuMove, KifuError> {
+        kifu_move::analyze_with(self.position, mv, |piece, to| self.candidates(piece, to))
+    }
+}
+
+fn index(piece_kind: PieceKind, to: Square) -> usize {
+    piece_kind.array_index() * 81 + to.array_index()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{display_single_move_with_style, normal_candidates};
+    use shogi_core::{Color, LegalityChecker};
+    use shogi_legality_lite::LiteLegalityChecker;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn notation_context_agrees_with_display_single_move() {
+        let sfens = [
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "sfen 4k4/9/9/9/9/9/9/9/3S1S2K b - 1",
+            "sfen 4k4/9/9/9/9/9/9/1B7/4K4 b B 1",
+            "sfen 4k4/3G1G3/2G3G2/9/9/9/9/9/4K4 b - 1",
+            "sfen lnsgkgsnl/1r5+B1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL w B 4",
+        ];
+        for sfen in sfens {
+            let pos = PartialPosition::from_usi(sfen).unwrap();
+            let hand_changes = Style {
+                hand_changes: true,
+                ..Style::OFFICIAL
+            };
+            for style in [Style::OFFICIAL, Style::TRADITIONAL, hand_changes] {
+                let context = NotationContext::new(&pos).style(style);
+                for mv in LiteLegalityChecker.all_legal_moves_partial(&pos) {
+                    assert_eq!(
+                        context.render(mv),
+                        display_single_move_with_style(&pos, mv, &style)
+                    );
+                }
+            }
+            let context = NotationContext::new(&pos);
+            for piece_kind in PieceKind::all() {
+                for color in Color::all() {
+                    let piece = Piece::new(piece_kind, color);
+                    for to in Square::all() {
+                        assert_eq!(
+                            context.candidates(piece, to),
+                            normal_candidates(&pos, piece, to)
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1004 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use shogi_core::{Color, Hand, Move, PartialPosition, Piece, PieceKind, Position, Square};
+
+use crate::{record::is_startpos, sanitize, ReadLimits, SpecialMove};
+
+/// The attributes defined by the CSA format.
+const STANDARD_ATTRIBUTES: [&str; 6] = [
+    "EVENT",
+    "SITE",
+    "START_TIME",
+    "END_TIME",
+    "TIME_LIMIT",
+    "OPENING",
+];
+
+/// Finds the CSA representation of a [`Move`], such as `+7776FU`.
+///
+/// The side to move is followed by the origin (`00` for drops), the destination,
+/// and the kind of the piece after the move.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_single_move_csa;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(display_single_move_csa(&pos, mv), Some("+7776FU".to_string()));
+///
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w b 1").unwrap();
+/// let mv = Move::Drop {
+///     piece: Piece::W_B,
+///     to: Square::SQ_4E,
+/// };
+/// assert_eq!(display_single_move_csa(&pos, mv), Some("-0045KA".to_string()));
+/// ```
+/// Ref: <http://www2.computer-shogi.org/protocol/record_v22.html>
+pub fn display_single_move_csa(
+    position: &PartialPosition,
+    mv: Move,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_csa(position, mv, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the CSA representation of a [`Move`] and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`](crate::display_single_move_write).
+///
+/// Ref: <http://www2.computer-shogi.org/protocol/record_v22.html>
+pub fn display_single_move_write_csa<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let (from, to, piece_kind) = match mv {
+        Move::Normal { from, to, promote } => {
+            let piece_kind = if let Some(p) = position.piece_at(from) {
+                p.piece_kind()
+            } else {
+                return Ok(None);
+            };
+            let piece_kind = if promote {
+                if let Some(promoted) = piece_kind.promote() {
+                    promoted
+                } else {
+                    return Ok(None);
+                }
+            } else {
+                piece_kind
+            };
+            (Some(from), to, piece_kind)
+        }
+        Move::Drop { to, piece } => (None, to, piece.piece_kind()),
+    };
+    w.write_char(if position.side_to_move() == Color::Black {
+        '+'
+    } else {
+        '-'
+    })?;
+    match from {
+        Some(from) => write!(w, "{}{}", from.file(), from.rank())?,
+        None => w.write_str("00")?,
+    }
+    write!(w, "{}{}", to.file(), to.rank())?;
+    w.write_str(piece_kind_to_csa(piece_kind))?;
+    Ok(Some(()))
+}
+
+/// Finds the message that a client sends to a CSA server to make a move, such as `+7776FU,T12`.
+///
+/// `seconds` is the time spent on the move, which the server ignores but records.
+/// The message does not end with the line feed that the protocol requires after it.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::csa;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(csa::protocol_move(&pos, mv, 12), Some("+7776FU,T12".to_string()));
+/// ```
+/// Ref: <http://www2.computer-shogi.org/protocol/tcp_ip_server_121.html>
+pub fn protocol_move(position: &PartialPosition, mv: Move, seconds: u32) -> Option<String> {
+    let mut ret = String::new();
+    protocol_move_write(position, mv, seconds, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the message that a client sends to a CSA server to make a move and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`](crate::display_single_move_write).
+///
+/// Ref: <http://www2.computer-shogi.org/protocol/tcp_ip_server_121.html>
+pub fn protocol_move_write<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    seconds: u32,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    if display_single_move_write_csa(position, mv, w)?.is_none() {
+        return Ok(None);
+    }
+    write!(w, ",T{}", seconds)?;
+    Ok(Some(()))
+}
+
+/// Finds the message that a client sends to a CSA server for a special move, such as `%TORYO`.
+///
+/// Servers accept only [`SpecialMove::Resign`] and [`SpecialMove::Declaration`] from clients,
+/// but every special move is written the same way.
+/// The message does not end with the line feed that the protocol requires after it.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::{csa, SpecialMove};
+/// assert_eq!(csa::protocol_special_move(SpecialMove::Resign), "%TORYO");
+/// ```
+/// Ref: <http://www2.computer-shogi.org/protocol/tcp_ip_server_121.html>
+pub fn protocol_special_move(special_move: SpecialMove) -> String {
+    alloc::format!("%{}", special_move.to_csa())
+}
+
+pub(crate) fn piece_kind_to_csa(piece_kind: PieceKind) -> &'static str {
+    match piece_kind {
+        PieceKind::King => "OU",
+        PieceKind::Rook => "HI",
+        PieceKind::Bishop => "KA",
+        PieceKind::Gold => "KI",
+        PieceKind::Silver => "GI",
+        PieceKind::Knight => "KE",
+        PieceKind::Lance => "KY",
+        PieceKind::Pawn => "FU",
+        PieceKind::ProRook => "RY",
+        PieceKind::ProBishop => "UM",
+        PieceKind::ProSilver => "NG",
+        PieceKind::ProKnight => "NK",
+        PieceKind::ProLance => "NY",
+        PieceKind::ProPawn => "TO",
+    }
+}
+
+pub(crate) fn piece_kind_from_csa(s: &str) -> Option<PieceKind> {
+    Some(match s {
+        "OU" => PieceKind::King,
+        "HI" => PieceKind::Rook,
+        "KA" => PieceKind::Bishop,
+        "KI" => PieceKind::Gold,
+        "GI" => PieceKind::Silver,
+        "KE" => PieceKind::Knight,
+        "KY" => PieceKind::Lance,
+        "FU" => PieceKind::Pawn,
+        "RY" => PieceKind::ProRook,
+        "UM" => PieceKind::ProBishop,
+        "NG" => PieceKind::ProSilver,
+        "NK" => PieceKind::ProKnight,
+        "NY" => PieceKind::ProLance,
+        "TO" => PieceKind::ProPawn,
+        _ => return None,
+    })
+}
+
+/// Pieces in hands in the order they are written, with the number of them in a set.
+const HAND_PIECES: [(PieceKind, u8); 7] = [
+    (PieceKind::Rook, 2),
+    (PieceKind::Bishop, 2),
+    (PieceKind::Gold, 4),
+    (PieceKind::Silver, 4),
+    (PieceKind::Knight, 4),
+    (PieceKind::Lance, 4),
+    (PieceKind::Pawn, 18),
+];
+
+/// A game record in the CSA format.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::csa::{self, CsaRecord};
+/// let text = "\
+/// V2.2
+/// N+Sente
+/// N-Gote
+/// PI
+/// +
+/// +7776FU
+/// T3
+/// -3334FU
+/// T1
+/// %TORYO
+/// ";
+/// let record = csa::parse(text).unwrap();
+/// assert_eq!(record.black_name.as_deref(), Some("Sente"));
+/// assert_eq!(record.position.moves().len(), 2);
+/// assert_eq!(record.times, [Some(3), Some(1)]);
+/// assert_eq!(record.end.as_deref(), Some("TORYO"));
+/// assert_eq!(record.render().as_deref(), Some(text));
+/// ```
+/// Ref: <http://www2.computer-shogi.org/protocol/record_v22.html>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CsaRecord {
+    /// The name of Black (`N+`).
+    pub black_name: Option<String>,
+    /// The name of White (`N-`).
+    pub white_name: Option<String>,
+    /// Other information such as `$EVENT`, as pairs of a key without `$` and a value, in the order they appear.
+    pub attributes: Vec<(String, String)>,
+    /// The initial position and the moves.
+    pub position: Position,
+    /// The time spent on each move in seconds (`T`), if given.
+    pub times: Vec<Option<u32>>,
+    /// The special move that ended the game without `%`, such as `TORYO`.
+    pub end: Option<String>,
+}
+
+impl CsaRecord {
+    /// Creates a [`CsaRecord`] with the moves in `position` and no other information.
+    pub fn new(position: Position) -> Self {
+        let times = alloc::vec![None; position.moves().len()];
+        Self {
+            black_name: None,
+            white_name: None,
+            attributes: Vec::new(),
+            position,
+            times,
+            end: None,
+        }
+    }
+
+    /// Removes personal data, for publishing records anonymously.
+    ///
+    /// Given names are replaced with `先手` and `後手`.
+    /// Attributes not defined by the CSA format, such as IDs used by a site, are removed,
+    /// and so are attributes whose values contain an email address.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_official_kifu::csa;
+    /// let mut record = csa::parse("N+taro\n$EVENT:練習\n$GAME_ID:12345\nPI\n+\n").unwrap();
+    /// record.anonymize();
+    /// assert_eq!(record.black_name.as_deref(), Some("先手"));
+    /// assert_eq!(record.white_name, None);
+    /// assert_eq!(record.attributes, [("EVENT".to_string(), "練習".to_string())]);
+    /// ```
+    pub fn anonymize(&mut self) {
+        if self.black_name.is_some() {
+            self.black_name = Some("先手".into());
+        }
+        if self.white_name.is_some() {
+            self.white_name = Some("後手".into());
+        }
+        self.attributes.retain(|(key, value)| {
+            STANDARD_ATTRIBUTES.contains(&key.as_str()) && !sanitize::contains_email(value)
+        });
+    }
+
+    /// Returns [`CsaRecord::end`] as a [`SpecialMove`], or [`None`] if it is not given or not known.
+    pub fn special_move(&self) -> Option<SpecialMove> {
+        self.end.as_deref().and_then(SpecialMove::from_csa)
+    }
+
+    /// Sets [`CsaRecord::end`] to `special_move`.
+    pub fn set_special_move(&mut self, special_move: SpecialMove) {
+        self.end = Some(special_move.to_csa().into());
+    }
+
+    /// Writes the record into a [`String`].
+    ///
+    /// Returns [`None`] if a move cannot be written.
+    pub fn render(&self) -> Option<String> {
+        let mut ret = String::new();
+        self.write(&mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Writes the record to a [`std::io::Write`], such as a file.
+    ///
+    /// Same as [`CsaRecord::write`].
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn write_io<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<Option<()>> {
+        crate::io::write_io(w, |w| self.write(w))
+    }
+
+    /// Writes the record to a [`Write`].
+    ///
+    /// The initial position is written as `PI` if it is the standard one, and line by line otherwise.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+        w.write_str("V2.2\n")?;
+        if let Some(name) = &self.black_name {
+            writeln!(w, "N+{}", name)?;
+        }
+        if let Some(name) = &self.white_name {
+            writeln!(w, "N-{}", name)?;
+        }
+        for (key, value) in &self.attributes {
+            writeln!(w, "${}:{}", key, value)?;
+        }
+        let initial = self.position.initial_position();
+        if is_startpos(initial) {
+            w.write_str("PI\n")?;
+        } else {
+            write_board(initial, w)?;
+        }
+        w.write_str(if initial.side_to_move() == Color::Black {
+            "+\n"
+        } else {
+            "-\n"
+        })?;
+        let mut position = initial.clone();
+        for (index, &mv) in self.position.moves().iter().enumerate() {
+            if display_single_move_write_csa(&position, mv, w)?.is_none()
+                || position.make_move(mv).is_none()
+            {
+                return Ok(None);
+            }
+            w.write_char('\n')?;
+            if let Some(time) = self.times.get(index).copied().flatten() {
+                writeln!(w, "T{}", time)?;
+            }
+        }
+        if let Some(end) = &self.end {
+            writeln!(w, "%{}", end)?;
+        }
+        Ok(Some(()))
+    }
+}
+
+impl From<Position> for CsaRecord {
+    fn from(position: Position) -> Self {
+        Self::new(position)
+    }
+}
+
+fn write_board<W: Write>(position: &PartialPosition, w: &mut W) -> core::fmt::Result {
+    for rank in 1..=9 {
+        write!(w, "P{}", rank)?;
+        for file in (1..=9).rev() {
+            // Safety: 1 <= file, rank <= 9
+            let square = unsafe { Square::new(file, rank).unwrap_unchecked() };
+            match position.piece_at(square) {
+                Some(piece) => {
+                    w.write_char(if piece.color() == Color::Black {
+                        '+'
+                    } else {
+                        '-'
+                    })?;
+                    w.write_str(piece_kind_to_csa(piece.piece_kind()))?;
+                }
+                None => w.write_str(" * ")?,
+            }
+        }
+        w.write_char('\n')?;
+    }
+    for (color, sign) in [(Color::Black, '+'), (Color::White, '-')] {
+        let hand = position.hand_of_a_player(color);
+        if hand == Hand::new() {
+            continue;
+        }
+        write!(w, "P{}", sign)?;
+        for (piece_kind, _) in HAND_PIECES {
+            for _ in 0..hand.count(piece_kind).unwrap_or(0) {
+                write!(w, "00{}", piece_kind_to_csa(piece_kind))?;
+            }
+        }
+        w.write_char('\n')?;
+    }
+    Ok(())
+}
+
+/// The reason why a CSA record could not be read.
+///
+/// Line numbers start from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CsaError {
+    /// The line is not a valid statement.
+    InvalidLine(usize),
+    /// The move on the line cannot be made.
+    IllegalMove(usize),
+    /// The record has no initial position.
+    MissingPosition,
+    /// The record has more moves or longer headers than the [`ReadLimits`] allow.
+    TooLarge,
+}
+
+impl core::fmt::Display for CsaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CsaError::InvalidLine(line) => write!(f, "invalid statement at line {}", line),
+            CsaError::IllegalMove(line) => write!(f, "illegal move at line {}", line),
+            CsaError::MissingPosition => f.write_str("no initial position"),
+            CsaError::TooLarge => f.write_str("the record is too large"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for CsaError {}
+
+/// Reads a game record in the CSA format.
+///
+/// Statements separated by `,` on one line are understood, and comments (`'`) are skipped.
+/// Only the first game is read if the input has several games separated by `/`.
+/// Moves are checked only as far as [`PartialPosition::make_move`] does.
+///
+/// The input is read once from the start to the end, and records over [`ReadLimits::DEFAULT`] are rejected with [`CsaError::TooLarge`],
+/// so it is safe to pass untrusted input directly.
+///
+/// Ref: <http://www2.computer-shogi.org/protocol/record_v22.html>
+pub fn parse(s: &str) -> Result<CsaRecord, CsaError> {
+    parse_with_limits(s, &ReadLimits::DEFAULT)
+}
+
+/// Same as [`parse`], but rejects records over `limits` instead of [`ReadLimits::DEFAULT`].
+///
+/// The names of the players (`N+`, `N-`) and the attributes (`$`) count as headers.
+pub fn parse_with_limits(s: &str, limits: &ReadLimits) -> Result<CsaRecord, CsaError> {
+    let mut header_bytes = 0;
+    let mut black_name = None;
+    let mut white_name = None;
+    let mut attributes = Vec::new();
+    let mut board = PartialPosition::empty();
+    let mut position: Option<Position> = None;
+    let mut times = Vec::new();
+    let mut end = None;
+    'lines: for (index, line) in s.lines().enumerate() {
+        let line_number = index + 1;
+        let invalid = CsaError::InvalidLine(line_number);
+        if line.starts_with('\'') {
+            continue;
+        }
+        for statement in line.split(',') {
+            let statement = statement.trim_end_matches('\r');
+            if statement.is_empty() || statement.starts_with('V') {
+                continue;
+            }
+            if statement == "/" {
+                break 'lines;
+            }
+            if end.is_some() {
+                return Err(invalid);
+            }
+            if let Some(position) = &mut position {
+                if let Some(time) = statement.strip_prefix('T') {
+                    let time = time.parse().map_err(|_| invalid)?;
+                    *times.last_mut().ok_or(invalid)? = Some(time);
+                } else if let Some(special) = statement.strip_prefix('%') {
+                    end = Some(String::from(special));
+                } else {
+                    let sign = if position.side_to_move() == Color::Black {
+                        '+'
+                    } else {
+                        '-'
+                    };
+                    if !statement.starts_with(sign) {
+                        return Err(if statement.starts_with(['+', '-']) {
+                            CsaError::IllegalMove(line_number)
+                        } else {
+                            invalid
+                        });
+                    }
+                    if times.len() >= limits.max_moves {
+                        return Err(CsaError::TooLarge);
+                    }
+                    let mv = parse_move(position.inner(), statement).ok_or(invalid)?;
+                    position
+                        .make_move(mv)
+                        .ok_or(CsaError::IllegalMove(line_number))?;
+                    times.push(None);
+                }
+                continue;
+            }
+            if statement.starts_with(['N', '$']) {
+                header_bytes += statement.len();
+                if header_bytes > limits.max_header_bytes {
+                    return Err(CsaError::TooLarge);
+                }
+            }
+            if let Some(name) = statement.strip_prefix("N+") {
+                black_name = Some(String::from(name));
+            } else if let Some(name) = statement.strip_prefix("N-") {
+                white_name = Some(String::from(name));
+            } else if let Some(attribute) = statement.strip_prefix('$') {
+                let (key, value) = attribute.split_once(':').ok_or(invalid)?;
+                attributes.push((String::from(key), String::from(value)));
+            } else if let Some(removed) = statement.strip_prefix("PI") {
+                board = PartialPosition::startpos();
+                parse_removed(&mut board, removed).ok_or(invalid)?;
+            } else if let Some(pieces) = statement.strip_prefix("P+") {
+                parse_pieces(&mut board, Color::Black, pieces).ok_or(invalid)?;
+            } else if let Some(pieces) = statement.strip_prefix("P-") {
+                parse_pieces(&mut board, Color::White, pieces).ok_or(invalid)?;
+            } else if let Some(rank_line) = statement.strip_prefix('P') {
+                parse_rank(&mut board, rank_line).ok_or(invalid)?;
+            } else if statement == "+" || statement == "-" {
+                board.side_to_move_set(if statement == "+" {
+                    Color::Black
+                } else {
+                    Color::White
+                });
+                position = Some(Position::arbitrary_position(board.clone()));
+            } else {
+                return Err(invalid);
+            }
+        }
+    }
+    let position = position.ok_or(CsaError::MissingPosition)?;
+    Ok(CsaRecord {
+        black_name,
+        white_name,
+        attributes,
+        position,
+        times,
+        end,
+    })
+}
+
+fn parse_square(s: &str) -> Option<Option<Square>> {
+    let bytes = s.as_bytes();
+    let file = bytes.first()?.checked_sub(b'0')?;
+    let rank = bytes.get(1)?.checked_sub(b'0')?;
+    if file == 0 && rank == 0 {
+        return Some(None);
+    }
+    Square::new(file, rank).map(Some)
+}
+
+/// Splits `s` into 4-byte pieces such as `82HI`.
+fn chunks4(s: &str) -> Option<impl Iterator<Item = (&str, &str)>> {
+    if !s.is_ascii() || s.len() % 4 != 0 {
+        return None;
+    }
+    Some((0..s.len() / 4).map(move |i| (&s[4 * i..4 * i + 2], &s[4 * i + 2..4 * i + 4])))
+}
+
+fn parse_removed(board: &mut PartialPosition, s: &str) -> Option<()> {
+    for (square, piece_kind) in chunks4(s)? {
+        let square = parse_square(square)??;
+        let piece_kind = piece_kind_from_csa(piece_kind)?;
+        if board.piece_at(square)?.piece_kind() != piece_kind {
+            return None;
+        }
+        board.piece_set(square, None);
+    }
+    Some(())
+}
+
+fn parse_pieces(board: &mut PartialPosition, color: Color, s: &str) -> Option<()> {
+    for (square, piece_kind) in chunks4(s)? {
+        let square = parse_square(square)?;
+        if piece_kind == "AL" {
+            if square.is_some() {
+                return None;
+            }
+            for (piece_kind, total) in HAND_PIECES {
+                let mut rest = total;
+                for c in Color::all() {
+                    rest = rest.checked_sub(board.hand_of_a_player(c).count(piece_kind)?)?;
+                }
+                for square in Square::all() {
+                    if let Some(piece) = board.piece_at(square) {
+                        let kind = piece.piece_kind();
+                        if kind.unpromote().unwrap_or(kind) == piece_kind {
+                            rest = rest.checked_sub(1)?;
+                        }
+                    }
+                }
+                let hand = board.hand_of_a_player_mut(color);
+                for _ in 0..rest {
+                    *hand = hand.added(piece_kind)?;
+                }
+            }
+            continue;
+        }
+        let piece_kind = piece_kind_from_csa(piece_kind)?;
+        match square {
+            Some(square) => board.piece_set(square, Some(Piece::new(piece_kind, color))),
+            None => {
+                let hand = board.hand_of_a_player_mut(color);
+                *hand = hand.added(piece_kind)?;
+            }
+        }
+    }
+    Some(())
+}
+
+/// Reads a line such as `1-KY-KE-GI-KI-OU-KI-GI-KE-KY`. Trailing empty squares may be omitted.
+fn parse_rank(board: &mut PartialPosition, s: &str) -> Option<()> {
+    // Checked before slicing, because `s[1..]` is not a char boundary if `s` starts with a non-ASCII character.
+    if !s.is_ascii() {
+        return None;
+    }
+    let rank = s.as_bytes().first()?.checked_sub(b'0')?;
+    let cells = &s[1..];
+    if cells.len() > 27 {
+        return None;
+    }
+    for (i, file) in (1..=9).rev().enumerate() {
+        let square = Square::new(file, rank)?;
+        let cell = cells.get(3 * i..(3 * i + 3).min(cells.len())).unwrap_or("");
+        let piece = match cell.trim_end() {
+            "" | " *" => None,
+            cell if cell.len() == 3 => {
+                let color = match cell.as_bytes()[0] {
+                    b'+' => Color::Black,
+                    b'-' => Color::White,
+                    _ => return None,
+                };
+                Some(Piece::new(piece_kind_from_csa(&cell[1..])?, color))
+            }
+            _ => return None,
+        };
+        board.piece_set(square, piece);
+    }
+    Some(())
+}
+
+/// Reads a move such as `+7776FU`, whose sign has been checked to be the side to move.
+fn parse_move(position: &PartialPosition, s: &str) -> Option<Move> {
+    if !s.is_ascii() || s.len() != 7 {
+        return None;
+    }
+    let side = position.side_to_move();
+    let from = parse_square(&s[1..3])?;
+    let to = parse_square(&s[3..5])??;
+    let piece_kind = piece_kind_from_csa(&s[5..7])?;
+    Some(match from {
+        None => Move::Drop {
+            piece: Piece::new(piece_kind, side),
+            to,
+        },
+        Some(from) => {
+            let current = position.piece_at(from)?.piece_kind();
+            let promote = if current == piece_kind {
+                false
+            } else if current.promote() == Some(piece_kind) {
+                true
+            } else {
+                return None;
+            };
+            Move::Normal { from, to, promote }
+        }
+    })
+}
+
+/// What [`merge_annotations`] does when both records have different values for the same item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MergePolicy {
+    /// Keeps the value in the base record.
+    KeepBase,
+    /// Takes the value in the other record.
+    TakeOther,
+    /// Fails with [`MergeError::Conflict`].
+    Reject,
+}
+
+/// The reason why [`merge_annotations`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MergeError {
+    /// The records have different initial positions or moves.
+    DifferentGames,
+    /// The records have different values for the same item under [`MergePolicy::Reject`].
+    Conflict,
+}
+
+impl core::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MergeError::DifferentGames => f.write_str("the records are different games"),
+            MergeError::Conflict => f.write_str("the records have conflicting annotations"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for MergeError {}
+
+/// Merges the information of two records of the same game.
+///
+/// The records must have the same initial position and moves.
+/// The names, the attributes, the times and the end are taken from whichever record has them,
+/// and `policy` decides what happens when both have different values.
+/// Attributes only in `other` are appended after those of `base`.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::csa::{self, MergePolicy};
+/// let base = csa::parse("N+Sente\nPI\n+\n+7776FU\nT3\n-3334FU\n").unwrap();
+/// let other = csa::parse("N-Gote\nPI\n+\n+7776FU\n-3334FU\nT1\n%TORYO\n").unwrap();
+/// let merged = csa::merge_annotations(&base, &other, MergePolicy::Reject).unwrap();
+/// assert_eq!(merged.black_name.as_deref(), Some("Sente"));
+/// assert_eq!(merged.white_name.as_deref(), Some("Gote"));
+/// assert_eq!(merged.times, [Some(3), Some(1)]);
+/// assert_eq!(merged.end.as_deref(), Some("TORYO"));
+/// ```
+pub fn merge_annotations(
+    base: &CsaRecord,
+    other: &CsaRecord,
+    policy: MergePolicy,
+) -> Result<CsaRecord, MergeError> {
+    if base.position.initial_position() != other.position.initial_position()
+        || base.position.moves() != other.position.moves()
+    {
+        return Err(MergeError::DifferentGames);
+    }
+    let mut merged = base.clone();
+    merge_option(&mut merged.black_name, &other.black_name, policy)?;
+    merge_option(&mut merged.white_name, &other.white_name, policy)?;
+    merge_option(&mut merged.end, &other.end, policy)?;
+    for (time, other_time) in merged.times.iter_mut().zip(&other.times) {
+        merge_option(time, other_time, policy)?;
+    }
+    for (key, value) in &other.attributes {
+        match merged.attributes.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => {
+                let mut existing_value = Some(core::mem::take(existing));
+                merge_option(&mut existing_value, &Some(value.clone()), policy)?;
+                *existing = existing_value.unwrap_or_default();
+            }
+            None => merged.attributes.push((key.clone(), value.clone())),
+        }
+    }
+    Ok(merged)
+}
+
+fn merge_option<T: Clone + PartialEq>(
+    base: &mut Option<T>,
+    other: &Option<T>,
+    policy: MergePolicy,
+) -> Result<(), MergeError> {
+    match (base.as_ref(), other) {
+        (_, None) => {}
+        (None, Some(_)) => *base = other.clone(),
+        (Some(a), Some(b)) if a == b => {}
+        (Some(_), Some(_)) => match policy {
+            MergePolicy::KeepBase => {}
+            MergePolicy::TakeOther => *base = other.clone(),
+            MergePolicy::Reject => return Err(MergeError::Conflict),
+        },
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn display_single_move_csa_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_8H,
+            to: Square::SQ_2B,
+            promote: true,
+        };
+        assert_eq!(
+            display_single_move_csa(pos.inner(), mv),
+            Some("+8822UM".to_string())
+        );
+
+        // A promoted piece keeps its kind.
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K2+P1 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_2I,
+            to: Square::SQ_2H,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_csa(&pos, mv),
+            Some("+2928TO".to_string())
+        );
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_csa(&pos, mv), None);
+        // A gold cannot promote.
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/8G/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_1D,
+            to: Square::SQ_1C,
+            promote: true,
+        };
+        assert_eq!(display_single_move_csa(&pos, mv), None);
+    }
+
+    #[test]
+    fn protocol_move_works() {
+        let pos = Position::from_usi("startpos moves 7g7f").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3C,
+            to: Square::SQ_3D,
+            promote: false,
+        };
+        assert_eq!(
+            protocol_move(pos.inner(), mv, 0),
+            Some("-3334FU,T0".to_string())
+        );
+        let mut w = crate::test_support::FailingWriter::new(7);
+        assert!(protocol_move_write(pos.inner(), mv, 0, &mut w).is_err());
+        assert_eq!(w.as_str(), "-3334FU");
+        assert_eq!(protocol_special_move(SpecialMove::Declaration), "%KACHI");
+    }
+
+    #[test]
+    fn parse_board_and_write_back() {
+        let text = "\
+V2.2\n\
+$EVENT:test\n\
+P1 *  *  *  * -OU *  *  *  * \n\
+P2 *  *  *  *  *  *  *  *  * \n\
+P3 *  *  *  *  *  *  *  *  * \n\
+P4 *  *  *  *  *  *  *  *  * \n\
+P5 *  *  *  *  *  *  *  *  * \n\
+P6 *  *  *  *  *  *  *  *  * \n\
+P7 *  *  *  *  *  *  *  *  * \n\
+P8 *  *  *  * +NG *  *  *  * \n\
+P9 *  *  *  * +OU *  *  *  * \n\
+P+00KI00KI\n\
+P-00FU\n\
+-\n\
+-0052FU\n\
+T10\n\
++5948OU\n\
+%CHUDAN\n";
+        let record = parse(text).unwrap();
+        assert_eq!(
+            record.attributes,
+            [(String::from("EVENT"), String::from("test"))]
+        );
+        let expected =
+            Position::from_usi("sfen 4k4/9/9/9/9/9/9/4+S4/4K4 w 2Gp 1 moves P*5b 5i4h").unwrap();
+        assert_eq!(record.position, expected);
+        assert_eq!(record.times, [Some(10), None]);
+        assert_eq!(record.special_move(), Some(SpecialMove::Interrupt));
+        assert_eq!(record.render().as_deref(), Some(text));
+
+        let mut record = record;
+        record.set_special_move(SpecialMove::Sennichite);
+        assert!(record.render().unwrap().ends_with("\n%SENNICHITE\n"));
+    }
+
+    #[test]
+    fn parse_handicap_and_remaining_pieces() {
+        // 角落ち
+        let record = parse("PI22KA\n-\n-3334FU,T5\n").unwrap();
+        let expected = Position::from_usi(
+            "sfen lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1 moves 3c3d",
+        )
+        .unwrap();
+        assert_eq!(record.position, expected);
+        assert_eq!(record.times, [Some(5)]);
+
+        // Every piece not on the board goes to White.
+        let record = parse("P5 *  *  *  * +OU\nP-00AL\n+\n").unwrap();
+        let initial = record.position.initial_position();
+        assert_eq!(
+            initial
+                .hand_of_a_player(Color::White)
+                .count(PieceKind::Pawn),
+            Some(18)
+        );
+        assert_eq!(
+            initial
+                .hand_of_a_player(Color::White)
+                .count(PieceKind::Rook),
+            Some(2)
+        );
+        assert_eq!(initial.hand_of_a_player(Color::Black), Hand::new());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), Err(CsaError::MissingPosition));
+        assert_eq!(parse("PI\n+\n+7776FX\n"), Err(CsaError::InvalidLine(3)));
+        assert_eq!(parse("PI\n+\n-3334FU\n"), Err(CsaError::IllegalMove(3)));
+        assert_eq!(parse("PI\n+\nT5\n"), Err(CsaError::InvalidLine(3)));
+        assert_eq!(parse("PI99HI\n+\n"), Err(CsaError::InvalidLine(1)));
+        assert_eq!(
+            parse("PI\n+\n%TORYO\n+7776FU\n"),
+            Err(CsaError::InvalidLine(4))
+        );
+        assert_eq!(
+            parse("P1+OU+OU+OU+OU+OU+OU+OU+OU+OU+OU\n+\n"),
+            Err(CsaError::InvalidLine(1))
+        );
+        assert_eq!(parse("P+99\n+\n"), Err(CsaError::InvalidLine(1)));
+        assert_eq!(parse("Pあ\n+\n"), Err(CsaError::InvalidLine(1)));
+        assert_eq!(parse("P1あ\n+\n"), Err(CsaError::InvalidLine(1)));
+    }
+
+    #[test]
+    fn parse_with_limits_works() {
+        let text = "N+Sente\nN-Gote\n$EVENT:Test\nPI\n+\n+7776FU\n-3334FU\n%TORYO\n";
+        let mut limits = ReadLimits::DEFAULT;
+        limits.max_moves = 2;
+        limits.max_header_bytes = "N+SenteN-Gote$EVENT:Test".len();
+        assert_eq!(parse_with_limits(text, &limits), parse(text));
+        limits.max_moves = 1;
+        assert_eq!(parse_with_limits(text, &limits), Err(CsaError::TooLarge));
+        limits.max_moves = 2;
+        limits.max_header_bytes -= 1;
+        assert_eq!(parse_with_limits(text, &limits), Err(CsaError::TooLarge));
+
+        // A long game is stopped at the limit, without reading the rest.
+        let mut long = String::from("PI\n+\n");
+        for _ in 0..ReadLimits::DEFAULT.max_moves / 4 + 1 {
+            long.push_str("+5958OU\n-5152OU\n+5859OU\n-5251OU\n");
+        }
+        assert_eq!(parse(&long), Err(CsaError::TooLarge));
+    }
+
+    #[test]
+    fn parse_never_panics_on_non_ascii() {
+        let text =
+            "N+a\n$EVENT:b\nP1 *  *  *  * -OU\nP9 *  *  *  * +OU\nP+00KI\n+\n+5958OU\nT1\n%TORYO\n";
+        for (index, _) in text.char_indices() {
+            for inserted in ["あ", "é", "\u{0}"] {
+                let mut mangled = String::from(text);
+                mangled.insert_str(index, inserted);
+                let _ = parse(&mangled);
+            }
+        }
+    }
+
+    #[test]
+    fn merge_annotations_policies() {
+        let base = parse("$EVENT:A\nPI\n+\n+7776FU\nT3\n%TORYO\n").unwrap();
+        let other = parse("$EVENT:B\n$SITE:C\nPI\n+\n+7776FU\nT5\n%TORYO\n").unwrap();
+        assert_eq!(
+            merge_annotations(&base, &other, MergePolicy::Reject),
+            Err(MergeError::Conflict),
+        );
+        let kept = merge_annotations(&base, &other, MergePolicy::KeepBase).unwrap();
+        assert_eq!(kept.times, [Some(3)]);
+        assert_eq!(
+            kept.attributes,
+            [
+                ("EVENT".to_string(), "A".to_string()),
+                ("SITE".to_string(), "C".to_string()),
+            ],
+        );
+        let taken = merge_annotations(&base, &other, MergePolicy::TakeOther).unwrap();
+        assert_eq!(taken.times, [Some(5)]);
+        assert_eq!(taken.attributes[0], ("EVENT".to_string(), "B".to_string()));
+
+        let different = parse("PI\n+\n+2726FU\n").unwrap();
+        assert_eq!(
+            merge_annotations(&base, &different, MergePolicy::KeepBase),
+            Err(MergeError::DifferentGames),
+        );
+    }
+}
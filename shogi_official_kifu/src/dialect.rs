@@ -0,0 +1,405 @@
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition, Position};
+
+use crate::{
+    csa, display_single_move_write_kif, display_single_move_write_with_style, parse_kif,
+    parse_single_move, GameRecord, KifFile, Style,
+};
+
+/// A format of game records that [`Converter`] reads and writes.
+///
+/// Only [`name`](KifuDialect::name) and [`render_move`](KifuDialect::render_move) are required.
+/// By default a record is written as the header followed by one move on a line, and cannot be read.
+/// Formats that lay out whole records differently, such as KIF, override [`render_record`](KifuDialect::render_record).
+///
+/// Writers take `&mut dyn Write` so that dialects can be kept as trait objects in a [`Converter`].
+///
+/// Examples:
+/// ```
+/// # use core::fmt::Write;
+/// # use shogi_core::{Move, PartialPosition, Position, ToUsi};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{Converter, GameRecord, KifuDialect};
+/// /// Moves in USI, as a club exports them.
+/// struct Usi;
+///
+/// impl KifuDialect for Usi {
+///     fn name(&self) -> &str {
+///         "usi"
+///     }
+///
+///     fn render_header(&self, _record: &GameRecord, w: &mut dyn Write) -> core::fmt::Result {
+///         w.write_str("# club\n")
+///     }
+///
+///     fn render_move(
+///         &self,
+///         _position: &PartialPosition,
+///         mv: Move,
+///         w: &mut dyn Write,
+///     ) -> Result<Option<()>, core::fmt::Error> {
+///         w.write_str(&mv.to_usi_owned())?;
+///         Ok(Some(()))
+///     }
+///
+///     fn parse(&self, s: &str) -> Option<GameRecord> {
+///         let moves: Vec<&str> = s.lines().filter(|line| !line.starts_with('#')).collect();
+///         let pos = Position::from_usi(&format!("startpos moves {}", moves.join(" "))).ok()?;
+///         Some(GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec()))
+///     }
+/// }
+///
+/// let converter = Converter::new().register(Usi);
+/// let usi = converter.convert("▲７六歩 △３四歩", "official", "usi").unwrap();
+/// assert_eq!(usi, "# club\n7g7f\n3c3d\n");
+/// assert_eq!(
+///     converter.convert(&usi, "usi", "traditional").unwrap(),
+///     "▲７六歩\n△３四歩\n",
+/// );
+/// ```
+pub trait KifuDialect {
+    /// Returns the name that the dialect is registered under in a [`Converter`].
+    fn name(&self) -> &str;
+
+    /// Writes what comes before the moves of `record`. Nothing is written by default.
+    ///
+    /// # Errors
+    /// Errors from `w` should be propagated as they are.
+    fn render_header(&self, record: &GameRecord, w: &mut dyn Write) -> core::fmt::Result {
+        let _ = (record, w);
+        Ok(())
+    }
+
+    /// Writes `mv` made in `position`.
+    ///
+    /// Returns `Ok(None)` if `mv` cannot be written.
+    ///
+    /// # Errors
+    /// Errors from `w` should be propagated as they are.
+    fn render_move(
+        &self,
+        position: &PartialPosition,
+        mv: Move,
+        w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error>;
+
+    /// Writes the whole `record`.
+    ///
+    /// By default this writes [`render_header`](KifuDialect::render_header)
+    /// followed by [`render_move`](KifuDialect::render_move) for every move, each on a line of its own.
+    ///
+    /// Returns `Ok(None)` if a move in `record` cannot be written or made.
+    ///
+    /// # Errors
+    /// Errors from `w` should be propagated as they are.
+    fn render_record(
+        &self,
+        record: &GameRecord,
+        w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        self.render_header(record, w)?;
+        let mut position = record.initial().clone();
+        for mv in record.moves() {
+            if self.render_move(&position, mv, w)?.is_none() || position.make_move(mv).is_none() {
+                return Ok(None);
+            }
+            w.write_char('\n')?;
+        }
+        Ok(Some(()))
+    }
+
+    /// Reads a record written in the dialect.
+    ///
+    /// Returns [`None`] if `s` cannot be read. By default nothing can be read.
+    fn parse(&self, s: &str) -> Option<GameRecord> {
+        let _ = s;
+        None
+    }
+}
+
+/// Converts game records between [`KifuDialect`]s, looking them up by name.
+///
+/// [`Converter::new`] knows these dialects:
+/// - `official` and `traditional`: moves in [`Style::OFFICIAL`] and [`Style::TRADITIONAL`], one on a line.
+///   They are read as moves from the initial position separated by ASCII whitespace, skipping ply numbers.
+/// - `kif`: documents written by [`KifFile`] and read by [`parse_kif`].
+/// - `csa`: records written by [`CsaRecord`](csa::CsaRecord) and read by [`csa::parse`].
+///
+/// Only the initial position and the moves are carried from one dialect to another.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::Converter;
+/// let converter = Converter::new();
+/// let csa = converter.convert("７六歩 ３四歩", "official", "csa").unwrap();
+/// assert!(csa.ends_with("+7776FU\n-3334FU\n"));
+/// assert_eq!(converter.convert(&csa, "csa", "official").unwrap(), "▲７６歩\n△３４歩\n");
+/// ```
+pub struct Converter {
+    dialects: Vec<Box<dyn KifuDialect>>,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self {
+            dialects: Vec::new(),
+        }
+        .register(StyleDialect {
+            name: "official",
+            style: Style::OFFICIAL,
+        })
+        .register(StyleDialect {
+            name: "traditional",
+            style: Style::TRADITIONAL,
+        })
+        .register(KifDialect)
+        .register(CsaDialect)
+    }
+}
+
+impl core::fmt::Debug for Converter {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Converter")
+            .field("dialects", &self.names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Converter {
+    /// Creates a [`Converter`] that knows the dialects of this crate.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `dialect`, replacing the one with the same name if any.
+    pub fn register<D: KifuDialect + 'static>(mut self, dialect: D) -> Self {
+        self.dialects
+            .retain(|registered| registered.name() != dialect.name());
+        self.dialects.push(Box::new(dialect));
+        self
+    }
+
+    /// Returns the dialect registered under `name`.
+    pub fn dialect(&self, name: &str) -> Option<&dyn KifuDialect> {
+        self.dialects
+            .iter()
+            .find(|dialect| dialect.name() == name)
+            .map(|dialect| &**dialect)
+    }
+
+    /// Returns the names of the registered dialects, in the order they were registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.dialects.iter().map(|dialect| dialect.name())
+    }
+
+    /// Reads `s` written in the dialect `from` and writes it in the dialect `to`.
+    ///
+    /// # Errors
+    /// Returns [`ConvertError`] if either dialect is not registered, `s` cannot be read or the record cannot be written.
+    pub fn convert(&self, s: &str, from: &str, to: &str) -> Result<String, ConvertError> {
+        let from = self.dialect(from).ok_or(ConvertError::UnknownDialect)?;
+        let to = self.dialect(to).ok_or(ConvertError::UnknownDialect)?;
+        let record = from.parse(s).ok_or(ConvertError::Unreadable)?;
+        let mut ret = String::new();
+        to.render_record(&record, &mut ret)
+            .expect("fmt::Write for String cannot return an error")
+            .ok_or(ConvertError::Unwritable)?;
+        Ok(ret)
+    }
+}
+
+/// The reason why [`Converter::convert`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConvertError {
+    /// No dialect is registered under the name.
+    UnknownDialect,
+    /// The input cannot be read in the dialect.
+    Unreadable,
+    /// The record cannot be written in the dialect.
+    Unwritable,
+}
+
+impl core::fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            ConvertError::UnknownDialect => "unknown dialect",
+            ConvertError::Unreadable => "the input cannot be read",
+            ConvertError::Unwritable => "the record cannot be written",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for ConvertError {}
+
+struct StyleDialect {
+    name: &'static str,
+    style: Style,
+}
+
+impl KifuDialect for StyleDialect {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn render_move(
+        &self,
+        position: &PartialPosition,
+        mv: Move,
+        mut w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        display_single_move_write_with_style(position, mv, &self.style, &mut w)
+    }
+
+    fn parse(&self, s: &str) -> Option<GameRecord> {
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        let mut position = PartialPosition::startpos();
+        for word in s
+            .split(|c: char| c.is_ascii_whitespace())
+            .filter(|word| !word.is_empty() && !word.bytes().all(|b| b.is_ascii_digit()))
+        {
+            let mv = parse_single_move(&position, word).ok()?;
+            position.make_move(mv)?;
+            record.push(mv);
+        }
+        Some(record)
+    }
+}
+
+struct KifDialect;
+
+impl KifuDialect for KifDialect {
+    fn name(&self) -> &str {
+        "kif"
+    }
+
+    fn render_move(
+        &self,
+        position: &PartialPosition,
+        mv: Move,
+        mut w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        display_single_move_write_kif(position, mv, &mut w)
+    }
+
+    fn render_record(
+        &self,
+        record: &GameRecord,
+        mut w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        KifFile::new(record).write(&mut w)
+    }
+
+    fn parse(&self, s: &str) -> Option<GameRecord> {
+        parse_kif(s).ok().map(|parsed| parsed.record)
+    }
+}
+
+struct CsaDialect;
+
+impl KifuDialect for CsaDialect {
+    fn name(&self) -> &str {
+        "csa"
+    }
+
+    fn render_move(
+        &self,
+        position: &PartialPosition,
+        mv: Move,
+        mut w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        csa::display_single_move_write_csa(position, mv, &mut w)
+    }
+
+    fn render_record(
+        &self,
+        record: &GameRecord,
+        mut w: &mut dyn Write,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        let mut position = Position::arbitrary_position(record.initial().clone());
+        for mv in record.moves() {
+            if position.make_move(mv).is_none() {
+                return Ok(None);
+            }
+        }
+        csa::CsaRecord::new(position).write(&mut w)
+    }
+
+    fn parse(&self, s: &str) -> Option<GameRecord> {
+        let parsed = csa::parse(s).ok()?;
+        Some(GameRecord::with_moves(
+            parsed.position.initial_position().clone(),
+            parsed.position.moves().iter().copied(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Upper;
+
+    impl KifuDialect for Upper {
+        fn name(&self) -> &str {
+            "official"
+        }
+
+        fn render_move(
+            &self,
+            _position: &PartialPosition,
+            _mv: Move,
+            w: &mut dyn Write,
+        ) -> Result<Option<()>, core::fmt::Error> {
+            w.write_str("MOVE")?;
+            Ok(Some(()))
+        }
+    }
+
+    #[test]
+    fn builtin_dialects_convert() {
+        let converter = Converter::new();
+        assert_eq!(
+            converter.names().collect::<Vec<_>>(),
+            ["official", "traditional", "kif", "csa"]
+        );
+        let official = "1 ▲７６歩 2 △３４歩 3 ▲２２角成 4 △２２銀\n";
+        let kif = converter.convert(official, "official", "kif").unwrap();
+        assert!(kif.contains("   4 同　銀(31)"), "{}", kif);
+        let csa = converter.convert(&kif, "kif", "csa").unwrap();
+        assert_eq!(
+            converter.convert(&csa, "csa", "traditional").unwrap(),
+            "▲７六歩\n△３四歩\n▲２二角成\n△同銀\n"
+        );
+        assert_eq!(
+            converter.convert(official, "official", "usi"),
+            Err(ConvertError::UnknownDialect)
+        );
+        assert_eq!(
+            converter.convert("▲５五歩", "official", "csa"),
+            Err(ConvertError::Unreadable)
+        );
+    }
+
+    #[test]
+    fn register_replaces_same_name() {
+        let converter = Converter::new().register(Upper);
+        assert_eq!(converter.names().count(), 4);
+        assert_eq!(
+            converter.convert("▲７六歩", "csa", "official"),
+            Err(ConvertError::Unreadable)
+        );
+        assert_eq!(
+            converter.convert("７六歩", "traditional", "official"),
+            Ok("MOVE\n".into())
+        );
+        assert!(converter
+            .dialect("official")
+            .unwrap()
+            .parse("７六歩")
+            .is_none());
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::{string::String, vec::Vec};
+
+/// How a move differs between two texts compared by [`diff_kifu_text`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DiffKind {
+    /// The moves are the same but are spelled with different glyphs,
+    /// such as `７六` and `76`, `竜` and `龍`, `☗` and `▲`, or `同` and the square it stands for.
+    Glyph,
+    /// The moves differ only in the suffixes that distinguish pieces (`左`, `右`, `上`, `引`, `寄`, `直` and `打`)
+    /// or in the origin written by KIF (`(77)`), apart from glyphs.
+    Suffix,
+    /// The moves are different.
+    Move,
+    /// Only one of the texts has a move at the ply.
+    Missing,
+}
+
+impl DiffKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DiffKind::Glyph => "glyph",
+            DiffKind::Suffix => "suffix",
+            DiffKind::Move => "move",
+            DiffKind::Missing => "missing",
+        }
+    }
+}
+
+/// A move that differs between two texts, as found by [`diff_kifu_text`].
+///
+/// It is displayed as `   3 ▲２二角成 -> ▲２二角 (move)`, with `-` for a missing move.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MoveDiff {
+    /// The ply of the move, counting from 1.
+    pub ply: usize,
+    /// The move in the first text, as it is written there.
+    pub left: Option<String>,
+    /// The move in the second text, as it is written there.
+    pub right: Option<String>,
+    /// How the moves differ.
+    pub kind: DiffKind,
+}
+
+impl core::fmt::Display for MoveDiff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{:>4} {} -> {} ({})",
+            self.ply,
+            self.left.as_deref().unwrap_or("-"),
+            self.right.as_deref().unwrap_or("-"),
+            self.kind.as_str()
+        )
+    }
+}
+
+/// Compares two texts of moves ply by ply, for reviewing the output of converters.
+///
+/// Moves are separated by ASCII whitespace, so a full-width space inside a move such as `▲同　歩` is kept,
+/// and words made only of ASCII digits, such as the ply numbers written by [`GameLayout`](crate::GameLayout), are skipped.
+/// This reads KI2, the output of [`GameLayout`](crate::GameLayout) and one move per line alike.
+/// The moves are aligned by ply rather than by line, and only the plies whose moves differ are returned,
+/// each classified by [`DiffKind`] so that differences in spelling stand out from differences in moves.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::{diff_kifu_text, DiffKind};
+/// let old = "▲７六歩 △３四歩 ▲２二角成 △同　銀 ▲４五角打";
+/// let new = "1 ▲７６歩 2 △３４歩 3 ▲２２角成 4 △２２銀 5 ▲４５角";
+/// let diffs = diff_kifu_text(old, new);
+/// assert_eq!(diffs.len(), 5);
+/// assert!(diffs[..4].iter().all(|diff| diff.kind == DiffKind::Glyph));
+/// assert_eq!(diffs[4].kind, DiffKind::Suffix);
+/// assert_eq!(diffs[4].to_string(), "   5 ▲４五角打 -> ▲４５角 (suffix)");
+///
+/// let diffs = diff_kifu_text("▲７六歩 △３四歩", "▲２六歩");
+/// assert_eq!(diffs[0].kind, DiffKind::Move);
+/// assert_eq!(diffs[1].kind, DiffKind::Missing);
+/// assert_eq!(diffs[1].to_string(), "   2 △３四歩 -> - (missing)");
+/// ```
+pub fn diff_kifu_text(a: &str, b: &str) -> Vec<MoveDiff> {
+    let left = moves(a);
+    let right = moves(b);
+    let mut diffs = Vec::new();
+    for ply in 0..left.len().max(right.len()) {
+        let kind = match (left.get(ply), right.get(ply)) {
+            (Some(l), Some(r)) if l.text == r.text => continue,
+            (Some(l), Some(r)) if l.normalized == r.normalized => DiffKind::Glyph,
+            (Some(l), Some(r))
+                if strip_suffixes(&l.normalized) == strip_suffixes(&r.normalized) =>
+            {
+                DiffKind::Suffix
+            }
+            (Some(_), Some(_)) => DiffKind::Move,
+            _ => DiffKind::Missing,
+        };
+        diffs.push(MoveDiff {
+            ply: ply + 1,
+            left: left.get(ply).map(|m| String::from(m.text)),
+            right: right.get(ply).map(|m| String::from(m.text)),
+            kind,
+        });
+    }
+    diffs
+}
+
+struct TextMove<'a> {
+    text: &'a str,
+    normalized: String,
+}
+
+fn moves(s: &str) -> Vec<TextMove<'_>> {
+    let mut ret = Vec::new();
+    let mut last_to: Option<(char, char)> = None;
+    for text in s
+        .split(|c: char| c.is_ascii_whitespace())
+        .filter(|word| !word.is_empty() && !word.bytes().all(|b| b.is_ascii_digit()))
+    {
+        let (normalized, to) = normalize(text, last_to);
+        last_to = to.or(last_to);
+        ret.push(TextMove { text, normalized });
+    }
+    ret
+}
+
+/// Spells `text` in one way, replacing `同` with `last_to`, and returns it with the destination of the move.
+fn normalize(text: &str, last_to: Option<(char, char)>) -> (String, Option<(char, char)>) {
+    let mut normalized = String::with_capacity(text.len());
+    for c in text.chars() {
+        let c = match c {
+            '☗' => '▲',
+            '☖' | '▽' => '△',
+            '１'..='９' => char::from_u32(c as u32 - '１' as u32 + '1' as u32).unwrap_or(c),
+            '竜' => '龍',
+            '王' => '玉',
+            '　' => continue,
+            c => match crate::KANSUJI.iter().position(|&k| k == c) {
+                Some(index) => (b'1' + index as u8) as char,
+                None => c,
+            },
+        };
+        normalized.push(c);
+    }
+    let normalized = normalized
+        .replace("成銀", "全")
+        .replace("成桂", "圭")
+        .replace("成香", "杏");
+    let marker = normalized
+        .chars()
+        .next()
+        .filter(|&c| c == '▲' || c == '△')
+        .map_or(0, char::len_utf8);
+    let (side, rest) = normalized.split_at(marker);
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some('同'), _) => match last_to {
+            Some((file, rank)) => {
+                let mut replaced = String::from(side);
+                replaced.push(file);
+                replaced.push(rank);
+                replaced.push_str(&rest['同'.len_utf8()..]);
+                (replaced, last_to)
+            }
+            None => (normalized, None),
+        },
+        (Some(file), Some(rank)) if file.is_ascii_digit() && rank.is_ascii_digit() => {
+            (normalized, Some((file, rank)))
+        }
+        _ => (normalized, None),
+    }
+}
+
+fn strip_suffixes(normalized: &str) -> String {
+    let without_origin = match normalized.find('(') {
+        Some(index) if normalized.ends_with(')') => &normalized[..index],
+        _ => normalized,
+    };
+    without_origin
+        .chars()
+        .filter(|c| !"左右上引寄直打".contains(*c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ki2, GameLayout, GameRecord, KifuFormatter, Style};
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    // Two styles of the same game differ in glyphs and suffixes, never in moves.
+    #[test]
+    fn styles_differ_only_in_spelling() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e 6a5b 4i5h").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let traditional = ki2::to_string(&record).unwrap();
+        let formatter = KifuFormatter::new().full_disambiguation(true);
+        let layout = GameLayout::new().style(*formatter.style());
+        let official = layout.render(&record).unwrap();
+        let diffs = diff_kifu_text(&traditional, &official);
+        assert!(!diffs.is_empty());
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff.kind, DiffKind::Glyph | DiffKind::Suffix)));
+        assert!(diff_kifu_text(&official, &official).is_empty());
+
+        let layout = GameLayout::new().style(Style::TRADITIONAL);
+        assert!(diff_kifu_text(&traditional, &layout.render(&record).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn same_is_resolved_in_each_text() {
+        let diffs = diff_kifu_text("▲２四歩 △同歩 ▲同飛", "▲２四歩 △２四歩 ▲２四角");
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].kind, DiffKind::Glyph);
+        assert_eq!(diffs[1].kind, DiffKind::Move);
+        assert_eq!(diffs[1].ply, 3);
+    }
+}
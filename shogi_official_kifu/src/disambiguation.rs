@@ -1,47 +1,240 @@
-use shogi_core::{Bitboard, Color, PartialPosition, PieceKind, Square};
+use shogi_core::{Bitboard, Color, Move, PartialPosition, PieceKind, Square};
 
 use core::cmp::Ordering;
-use core::fmt::Write;
 
-pub fn run<W: Write>(
+use crate::{kifu_move, KifuError};
+
+pub use crate::normal_candidates;
+
+/// A suffix that the notation adds to tell a move from the other moves to the same destination.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::Modifier;
+/// assert_eq!(Modifier::Up.as_char(), Some('上'));
+/// assert_eq!(Modifier::from_char('直'), Some(Modifier::Straight));
+/// assert_eq!(Modifier::None.as_char(), None);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Modifier {
+    /// `上`: the piece moves forward.
+    Up,
+    /// `引`: the piece moves backward.
+    Down,
+    /// `寄`: the piece moves sideways.
+    Sideways,
+    /// `左`: the piece is the one on the left.
+    Left,
+    /// `右`: the piece is the one on the right.
+    Right,
+    /// `直`: the piece moves straight forward.
+    Straight,
+    /// `打`: the piece is dropped.
+    Drop,
+    /// No suffix.
+    None,
+}
+
+impl Modifier {
+    /// Returns the character written for `self`, or [`None`] for [`Modifier::None`].
+    pub fn as_char(self) -> Option<char> {
+        match self {
+            Modifier::Up => Some('上'),
+            Modifier::Down => Some('引'),
+            Modifier::Sideways => Some('寄'),
+            Modifier::Left => Some('左'),
+            Modifier::Right => Some('右'),
+            Modifier::Straight => Some('直'),
+            Modifier::Drop => Some('打'),
+            Modifier::None => None,
+        }
+    }
+
+    /// Returns the [`Modifier`] written as `c`, or [`None`] if `c` is not a suffix.
+    pub fn from_char(c: char) -> Option<Self> {
+        Some(match c {
+            '上' => Modifier::Up,
+            '引' => Modifier::Down,
+            '寄' => Modifier::Sideways,
+            '左' => Modifier::Left,
+            '右' => Modifier::Right,
+            '直' => Modifier::Straight,
+            '打' => Modifier::Drop,
+            _ => return None,
+        })
+    }
+}
+
+/// Finds the suffixes that the official notation needs for `mv` in `position`.
+///
+/// Returns `(relative, movement)` in the order they are written, as in `左上`.
+/// `relative` is [`Modifier::Left`], [`Modifier::Right`], [`Modifier::Straight`] or [`Modifier::Drop`],
+/// `movement` is [`Modifier::Up`], [`Modifier::Down`] or [`Modifier::Sideways`],
+/// and either is [`Modifier::None`] if it is not needed.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{required_modifier, Modifier};
+/// // ▲５８金左: golds on 6I, 5I and 4I can move to 5H.
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/3GGG2K b G 1").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_6I,
+///     to: Square::SQ_5H,
+///     promote: false,
+/// };
+/// assert_eq!(required_modifier(&pos, mv), Ok((Modifier::Left, Modifier::None)));
+/// let mv = Move::Drop {
+///     piece: Piece::B_G,
+///     to: Square::SQ_5H,
+/// };
+/// assert_eq!(required_modifier(&pos, mv), Ok((Modifier::Drop, Modifier::None)));
+/// ```
+pub fn required_modifier(
+    position: &PartialPosition,
+    mv: Move,
+) -> Result<(Modifier, Modifier), KifuError> {
+    let kifu_move = kifu_move::analyze(position, mv)?;
+    let relative = if kifu_move.drop {
+        Modifier::Drop
+    } else {
+        kifu_move
+            .relative
+            .and_then(Modifier::from_char)
+            .unwrap_or(Modifier::None)
+    };
+    let movement = kifu_move
+        .movement
+        .and_then(Modifier::from_char)
+        .unwrap_or(Modifier::None);
+    Ok((relative, movement))
+}
+
+/// Finds the suffixes that distinguish the move from `from` among `candidates`.
+///
+/// Returns `(relative, movement)`, where `relative` is one of `左`, `右` and `直`
+/// and `movement` is one of `上`, `引` and `寄`, each [`None`] if it is not needed
+/// ([`Modifier::from_char`] converts them).
+/// Returns [`None`] if `candidates` is empty or no suffixes can tell the move from the others.
+///
+/// `candidates` are usually found by [`normal_candidates`], and must include `from`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::disambiguation::{normal_candidates, run};
+/// // Silvers on 6I and 4I can move to 5H: ５８銀左 and ５８銀右
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/3S1S2K b - 1").unwrap();
+/// let candidates = normal_candidates(&pos, Piece::B_S, Square::SQ_5H);
+/// assert_eq!(run(&pos, Square::SQ_6I, Square::SQ_5H, candidates), Some((Some('左'), None)));
+/// assert_eq!(run(&pos, Square::SQ_4I, Square::SQ_5H, candidates), Some((Some('右'), None)));
+/// ```
+pub fn run(
     position: &PartialPosition,
     from: Square,
     to: Square,
     candidates: Bitboard,
-    w: &mut W,
-) -> Result<Option<()>, core::fmt::Error> {
+) -> Option<(Option<char>, Option<char>)> {
     if candidates.is_empty() {
-        return Ok(None);
+        return None;
     }
     // Needs nothing
     if candidates.count() == 1 {
-        return Ok(Some(()));
+        return Some((None, None));
     }
-    let (subset2, char2) = if let Some(result) = run_move(position, from, to, candidates) {
-        result
-    } else {
-        return Ok(None);
-    };
-    let (subset1, char1) = if let Some(result) = run_file(position, from, to, candidates) {
-        result
-    } else {
-        return Ok(None);
-    };
+    let (subset2, char2) = run_move(position, from, to, candidates)?;
+    let (subset1, char1) = run_file(position, from, to, candidates)?;
     // Preference: nothing > 2 > 1 > 1 + 2
     if subset2.count() == 1 {
-        w.write_char(char2)?;
-        return Ok(Some(()));
+        return Some((None, Some(char2)));
     }
     if subset1.count() == 1 {
-        w.write_char(char1)?;
-        return Ok(Some(()));
+        return Some((Some(char1), None));
     }
     if (subset1 & subset2).count() == 1 {
-        w.write_char(char1)?;
-        w.write_char(char2)?;
-        return Ok(Some(()));
+        return Some((Some(char1), Some(char2)));
+    }
+    None
+}
+
+/// Returns `(file_diff, rank_diff)`, how far `to` is from `from` as seen by `side`.
+///
+/// `file_diff` is positive if `to` is on the right of `from` and `rank_diff` is positive if `to` is ahead of `from`,
+/// both from the view of `side`. So a move with a positive `file_diff` comes from the left (`左`),
+/// and one with a positive `rank_diff` goes up (`上`).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Color, Square};
+/// # use shogi_official_kifu::relative_direction;
+/// // ▲７六歩
+/// assert_eq!(relative_direction(Color::Black, Square::SQ_7G, Square::SQ_7F), (0, 1));
+/// // △２二銀 from 3一, forward and to the left of White
+/// assert_eq!(relative_direction(Color::White, Square::SQ_3A, Square::SQ_2B), (-1, 1));
+/// // ▲２八飛 from 5八
+/// assert_eq!(relative_direction(Color::Black, Square::SQ_5H, Square::SQ_2H), (3, 0));
+/// ```
+pub fn relative_direction(side: Color, from: Square, to: Square) -> (i8, i8) {
+    let file_diff = from.file() as i8 - to.file() as i8;
+    let rank_diff = from.relative_rank(side) as i8 - to.relative_rank(side) as i8;
+    if side == Color::Black {
+        (file_diff, rank_diff)
+    } else {
+        (-file_diff, rank_diff)
     }
-    Ok(None)
+}
+
+/// Adds the suffixes that [`run`] omits because they are not needed, so that every normal move is written with them.
+///
+/// `relative` and `movement` are what [`run`] returned, and are kept if given.
+/// `左` or `右` is added if the move changes the file, and `上`, `引` or `寄` is always added,
+/// except that a gold-like piece moving straight forward gets `直` alone, as in the official notation.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Color, PieceKind, Square};
+/// # use shogi_official_kifu::disambiguation::complete;
+/// // ▲５８金左 becomes ▲５８金左上.
+/// let result = complete(Color::Black, PieceKind::Gold, Square::SQ_6I, Square::SQ_5H, (Some('左'), None));
+/// assert_eq!(result, (Some('左'), Some('上')));
+/// // ▲５８金 from 5I becomes ▲５８金直.
+/// let result = complete(Color::Black, PieceKind::Gold, Square::SQ_5I, Square::SQ_5H, (None, None));
+/// assert_eq!(result, (Some('直'), None));
+/// ```
+pub fn complete(
+    side: Color,
+    piece_kind: PieceKind,
+    from: Square,
+    to: Square,
+    (relative, movement): (Option<char>, Option<char>),
+) -> (Option<char>, Option<char>) {
+    if relative == Some('直') {
+        return (relative, movement);
+    }
+    let (file_delta, rank_delta) = relative_direction(side, from, to);
+    if relative.is_none()
+        && movement.is_none()
+        && file_delta == 0
+        && rank_delta > 0
+        && is_gold_like(piece_kind)
+    {
+        return (Some('直'), None);
+    }
+    let relative = relative.or(match file_delta.cmp(&0) {
+        Ordering::Greater => Some('左'),
+        Ordering::Less => Some('右'),
+        Ordering::Equal => None,
+    });
+    let movement = movement.or(Some(match rank_delta.cmp(&0) {
+        Ordering::Greater => '上',
+        Ordering::Less => '引',
+        Ordering::Equal => '寄',
+    }));
+    (relative, movement)
 }
 
 fn run_move(
@@ -82,11 +275,11 @@ fn run_file(
     if is_gold_like(piece_kind) {
         // Use |from.file() - to.file()| to disambiguate.
         let file_diff = from.file() as i8 - to.file() as i8;
-        if file_diff == 0 && from.relative_rank(side) as i8 - to.relative_rank(side) as i8 > 0 {
+        let (file_diff_relative, rank_diff_relative) = relative_direction(side, from, to);
+        if file_diff == 0 && rank_diff_relative > 0 {
             // We should use '直' for this particular case.
             return Some((Bitboard::single(from), '直'));
         }
-        let file_diff_relative = file_diff * if side == Color::Black { 1 } else { -1 };
         let horizontal = match file_diff_relative.cmp(&0) {
             Ordering::Less => '右',
             Ordering::Greater => '左',
@@ -0,0 +1,105 @@
+use core::fmt::{Display, Formatter};
+use shogi_core::{Move, PartialPosition, ToUsi};
+
+use crate::{try_display_single_move_write_with_style, Style};
+
+/// A [`Move`] made in a position, written in the official notation with [`Display`].
+///
+/// Nothing is allocated, so moves can be formatted or logged directly.
+/// If the move cannot be written, it is written in USI instead, such as `5e5d`,
+/// so that formatting never fails because of the move.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::KifuDisplay;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(format!("{}", KifuDisplay(&pos, mv)), "▲７６歩");
+/// let mv = Move::Normal {
+///     from: Square::SQ_5E,
+///     to: Square::SQ_5D,
+///     promote: false,
+/// };
+/// assert_eq!(KifuDisplay(&pos, mv).to_string(), "5e5d");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct KifuDisplay<'a>(pub &'a PartialPosition, pub Move);
+
+impl Display for KifuDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write_or_usi(self.0, self.1, &Style::OFFICIAL, f)
+    }
+}
+
+/// A [`Move`] made in a position, written in the traditional notation with [`Display`].
+///
+/// Same as [`KifuDisplay`], except that ranks are written in kansuji.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::KifuDisplayKansuji;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(format!("{}", KifuDisplayKansuji(&pos, mv)), "▲７六歩");
+/// ```
+#[cfg(feature = "kansuji")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kansuji")))]
+#[derive(Clone, Copy, Debug)]
+pub struct KifuDisplayKansuji<'a>(pub &'a PartialPosition, pub Move);
+
+#[cfg(feature = "kansuji")]
+impl Display for KifuDisplayKansuji<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write_or_usi(self.0, self.1, &Style::TRADITIONAL, f)
+    }
+}
+
+fn write_or_usi(
+    position: &PartialPosition,
+    mv: Move,
+    style: &Style,
+    f: &mut Formatter<'_>,
+) -> core::fmt::Result {
+    // Nothing is written if the move cannot be written.
+    if try_display_single_move_write_with_style(position, mv, style, f)?.is_err() {
+        mv.to_usi(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_single_move;
+    use shogi_core::{Piece, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn kifu_display_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        assert_eq!(
+            KifuDisplay(pos.inner(), mv).to_string(),
+            display_single_move(pos.inner(), mv).unwrap()
+        );
+        let mv = Move::Drop {
+            piece: Piece::B_K,
+            to: Square::SQ_5E,
+        };
+        assert_eq!(KifuDisplay(pos.inner(), mv).to_string(), "K*5e");
+    }
+}
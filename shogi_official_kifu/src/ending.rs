@@ -0,0 +1,257 @@
+use core::fmt::Write;
+use shogi_core::Color;
+
+use crate::{detect_handicap, GameRecord};
+
+/// The sentence that ends a KIF or KI2 record, such as `まで123手で先手の勝ち`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Color;
+/// # use shogi_official_kifu::{Ending, Outcome};
+/// let ending = Ending::parse("まで123手で先手の勝ち").unwrap();
+/// assert_eq!(ending.plies, 123);
+/// assert_eq!(ending.outcome, Some(Outcome::Win(Color::Black)));
+/// assert_eq!(ending.winner(), Some(Color::Black));
+/// assert_eq!(ending.to_string(), "まで123手で先手の勝ち");
+///
+/// let ending = Ending {
+///     plies: 64,
+///     outcome: Some(Outcome::Win(Color::White)),
+///     handicap: true,
+/// };
+/// assert_eq!(ending.to_string(), "まで64手で上手の勝ち");
+/// ```
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ending {
+    /// The number of moves in the game.
+    pub plies: usize,
+    /// How the game ended, or [`None`] if the sentence only gives the number of moves (`まで123手`).
+    pub outcome: Option<Outcome>,
+    /// Whether the sides are called `下手` and `上手` as in handicap games, instead of `先手` and `後手`.
+    pub handicap: bool,
+}
+
+/// How a game ended, as written after `で` in an [`Ending`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// `先手の勝ち`: the side won, usually by resignation or checkmate.
+    Win(Color),
+    /// `時間切れにより先手の勝ち`: the side won because the opponent ran out of time.
+    TimeUp(Color),
+    /// `先手の反則勝ち`: the side won because the opponent made an illegal move.
+    Foul(Color),
+    /// `千日手`: a draw by repetition.
+    Sennichite,
+    /// `持将棋`: a draw by impasse.
+    Jishogi,
+    /// `中断`: the game was suspended.
+    Interrupted,
+}
+
+impl Outcome {
+    /// Returns the side that won, or [`None`] if the game was not won.
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            Outcome::Win(color) | Outcome::TimeUp(color) | Outcome::Foul(color) => Some(color),
+            Outcome::Sennichite | Outcome::Jishogi | Outcome::Interrupted => None,
+        }
+    }
+}
+
+impl Ending {
+    /// Creates the [`Ending`] of `record`, which ended as `outcome`.
+    ///
+    /// The moves in `record` are counted, whatever the ply of the initial position is,
+    /// so a handicap game where White moves first counts from 1 too.
+    /// Sides are called `下手` and `上手` if the game starts from a standard handicap (see [`detect_handicap`]).
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::{Color, Position};
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{Ending, GameRecord, Outcome};
+    /// let pos = Position::from_usi(
+    ///     "sfen lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1 moves 3c3d 7g7f",
+    /// )
+    /// .unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// let ending = Ending::of_record(&record, Some(Outcome::Win(Color::Black)));
+    /// assert_eq!(ending.to_string(), "まで2手で下手の勝ち");
+    /// assert_eq!(format!("{:#}", ending), "まで２手で下手の勝ち");
+    /// ```
+    pub fn of_record(record: &GameRecord, outcome: Option<Outcome>) -> Self {
+        Ending {
+            plies: record.len(),
+            outcome,
+            handicap: detect_handicap(record.initial()).is_some(),
+        }
+    }
+
+    /// Parses a sentence such as `まで123手で先手の勝ち`.
+    ///
+    /// The number of moves may be written in ASCII or in full-width digits.
+    /// Leading and trailing whitespace is ignored.
+    /// Returns [`None`] if `s` is not a sentence that [`Ending`] represents.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().strip_prefix("まで")?;
+        let mut plies: usize = 0;
+        let mut digits = 0;
+        for c in s.chars() {
+            let digit = match c {
+                '0'..='9' => c as u32 - '0' as u32,
+                '０'..='９' => c as u32 - '０' as u32,
+                _ => break,
+            };
+            plies = plies.checked_mul(10)?.checked_add(digit as usize)?;
+            digits += c.len_utf8();
+        }
+        if digits == 0 {
+            return None;
+        }
+        let s = s[digits..].strip_prefix('手')?;
+        if s.is_empty() {
+            return Some(Ending {
+                plies,
+                outcome: None,
+                handicap: false,
+            });
+        }
+        let s = s.strip_prefix('で')?;
+        let simple = match s {
+            "千日手" => Some(Outcome::Sennichite),
+            "持将棋" => Some(Outcome::Jishogi),
+            "中断" => Some(Outcome::Interrupted),
+            _ => None,
+        };
+        if let Some(outcome) = simple {
+            return Some(Ending {
+                plies,
+                outcome: Some(outcome),
+                handicap: false,
+            });
+        }
+        let (s, time_up) = match s.strip_prefix("時間切れにより") {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (color, handicap, s) = [
+            ("先手", Color::Black, false),
+            ("後手", Color::White, false),
+            ("下手", Color::Black, true),
+            ("上手", Color::White, true),
+        ]
+        .iter()
+        .find_map(|&(name, color, handicap)| Some((color, handicap, s.strip_prefix(name)?)))?;
+        let outcome = match (s, time_up) {
+            ("の勝ち", false) => Outcome::Win(color),
+            ("の勝ち", true) => Outcome::TimeUp(color),
+            ("の反則勝ち", false) => Outcome::Foul(color),
+            _ => return None,
+        };
+        Some(Ending {
+            plies,
+            outcome: Some(outcome),
+            handicap,
+        })
+    }
+
+    /// Returns the side that won, or [`None`] if the game was not won or the outcome is not given.
+    pub fn winner(&self) -> Option<Color> {
+        self.outcome.and_then(Outcome::winner)
+    }
+
+    fn side_name(&self, color: Color) -> &'static str {
+        match (color, self.handicap) {
+            (Color::Black, false) => "先手",
+            (Color::White, false) => "後手",
+            (Color::Black, true) => "下手",
+            (Color::White, true) => "上手",
+        }
+    }
+}
+
+impl core::fmt::Display for Ending {
+    /// Writes the sentence, with the number of moves in full-width digits if the alternate flag (`{:#}`) is given.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("まで")?;
+        if f.alternate() {
+            let mut digits = alloc::string::String::new();
+            write!(digits, "{}", self.plies)?;
+            for c in digits.chars() {
+                f.write_char(
+                    char::from_u32('０' as u32 + (c as u32 - '0' as u32))
+                        .expect("full-width digits are characters"),
+                )?;
+            }
+        } else {
+            write!(f, "{}", self.plies)?;
+        }
+        f.write_char('手')?;
+        let outcome = if let Some(outcome) = self.outcome {
+            outcome
+        } else {
+            return Ok(());
+        };
+        f.write_char('で')?;
+        match outcome {
+            Outcome::Win(color) => write!(f, "{}の勝ち", self.side_name(color)),
+            Outcome::TimeUp(color) => write!(f, "時間切れにより{}の勝ち", self.side_name(color)),
+            Outcome::Foul(color) => write!(f, "{}の反則勝ち", self.side_name(color)),
+            Outcome::Sennichite => f.write_str("千日手"),
+            Outcome::Jishogi => f.write_str("持将棋"),
+            Outcome::Interrupted => f.write_str("中断"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn round_trip() {
+        let sentences = [
+            "まで0手",
+            "まで123手で先手の勝ち",
+            "まで64手で上手の勝ち",
+            "まで81手で時間切れにより後手の勝ち",
+            "まで50手で下手の反則勝ち",
+            "まで120手で千日手",
+            "まで300手で持将棋",
+            "まで12手で中断",
+        ];
+        for sentence in sentences {
+            let ending = Ending::parse(sentence).unwrap();
+            assert_eq!(ending.to_string(), sentence);
+        }
+        assert_eq!(
+            Ending::parse(" まで2手で後手の勝ち\n").unwrap().winner(),
+            Some(Color::White),
+        );
+        assert_eq!(Ending::parse("まで120手で千日手").unwrap().winner(), None);
+        let ending = Ending::parse("まで１２４手で千日手").unwrap();
+        assert_eq!(ending.plies, 124);
+        assert_eq!(alloc::format!("{:#}", ending), "まで１２４手で千日手");
+    }
+
+    #[test]
+    fn parse_errors() {
+        for s in [
+            "",
+            "まで",
+            "まで手",
+            "123手で先手の勝ち",
+            "まで123手で",
+            "まで123手で先手",
+            "まで123手で先手の負け",
+            "まで123手で時間切れにより千日手",
+            "まで123手で時間切れにより先手の反則勝ち",
+        ] {
+            assert_eq!(Ending::parse(s), None, "{}", s);
+        }
+    }
+}
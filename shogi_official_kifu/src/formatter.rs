@@ -0,0 +1,154 @@
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition};
+
+use crate::{
+    display_single_move_write_with_style, Coordinates, KingGlyphs, Notation, SideMarkers, Style,
+};
+
+/// A builder of a [`Style`] that writes moves in it.
+///
+/// Every option of [`Style`] has a method of the same name, so options can be chained
+/// instead of setting fields one by one. The defaults are those of [`Style::OFFICIAL`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{Coordinates, KifuFormatter, SideMarkers};
+/// let formatter = KifuFormatter::new()
+///     .side_markers(SideMarkers::ShogiPieces)
+///     .coordinates(Coordinates::KansujiRank)
+///     .always_mark_drops(true);
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(formatter.render(&pos, mv), Some("☗７六歩".to_string()));
+///
+/// let mut s = String::new();
+/// formatter.format(&pos, mv, &mut s).unwrap();
+/// assert_eq!(s, "☗７六歩");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct KifuFormatter {
+    style: Style,
+}
+
+impl KifuFormatter {
+    /// Creates a [`KifuFormatter`] that writes the official notation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a [`KifuFormatter`] that writes in `style`.
+    pub fn with_style(style: Style) -> Self {
+        Self { style }
+    }
+
+    /// Returns the [`Style`] built so far.
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    /// Sets [`Style::write_forced_promotion`].
+    pub fn write_forced_promotion(mut self, write_forced_promotion: bool) -> Self {
+        self.style.write_forced_promotion = write_forced_promotion;
+        self
+    }
+
+    /// Sets [`Style::notation`].
+    pub fn notation(mut self, notation: Notation) -> Self {
+        self.style.notation = notation;
+        self
+    }
+
+    /// Sets [`Style::coordinates`].
+    pub fn coordinates(mut self, coordinates: Coordinates) -> Self {
+        self.style.coordinates = coordinates;
+        self
+    }
+
+    /// Sets [`Style::side_markers`].
+    pub fn side_markers(mut self, side_markers: SideMarkers) -> Self {
+        self.style.side_markers = side_markers;
+        self
+    }
+
+    /// Sets [`Style::write_same`].
+    pub fn write_same(mut self, write_same: bool) -> Self {
+        self.style.write_same = write_same;
+        self
+    }
+
+    /// Sets [`Style::pad_same`].
+    pub fn pad_same(mut self, pad_same: bool) -> Self {
+        self.style.pad_same = pad_same;
+        self
+    }
+
+    /// Sets [`Style::always_mark_drops`].
+    pub fn always_mark_drops(mut self, always_mark_drops: bool) -> Self {
+        self.style.always_mark_drops = always_mark_drops;
+        self
+    }
+
+    /// Sets [`Style::full_disambiguation`].
+    pub fn full_disambiguation(mut self, full_disambiguation: bool) -> Self {
+        self.style.full_disambiguation = full_disambiguation;
+        self
+    }
+
+    /// Sets [`Style::old_form_dragon`].
+    pub fn old_form_dragon(mut self, old_form_dragon: bool) -> Self {
+        self.style.old_form_dragon = old_form_dragon;
+        self
+    }
+
+    /// Sets [`Style::single_char_promoted`].
+    pub fn single_char_promoted(mut self, single_char_promoted: bool) -> Self {
+        self.style.single_char_promoted = single_char_promoted;
+        self
+    }
+
+    /// Sets [`Style::kings`].
+    pub fn kings(mut self, kings: KingGlyphs) -> Self {
+        self.style.kings = kings;
+        self
+    }
+
+    /// Sets [`Style::hand_changes`].
+    pub fn hand_changes(mut self, hand_changes: bool) -> Self {
+        self.style.hand_changes = hand_changes;
+        self
+    }
+
+    /// Writes `mv` made in `position` into a [`String`](alloc::string::String).
+    ///
+    /// Returns [`None`] if `mv` cannot be written.
+    pub fn render(&self, position: &PartialPosition, mv: Move) -> Option<alloc::string::String> {
+        let mut ret = alloc::string::String::new();
+        self.format(position, mv, &mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Writes `mv` made in `position` to a [`Write`].
+    ///
+    /// # Errors
+    /// Same as [`display_single_move_write`](crate::display_single_move_write).
+    pub fn format<W: Write>(
+        &self,
+        position: &PartialPosition,
+        mv: Move,
+        w: &mut W,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        display_single_move_write_with_style(position, mv, &self.style, w)
+    }
+}
+
+impl From<Style> for KifuFormatter {
+    fn from(style: Style) -> Self {
+        Self::with_style(style)
+    }
+}
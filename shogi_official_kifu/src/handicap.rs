@@ -0,0 +1,163 @@
+use shogi_core::{Color, Hand, PartialPosition, Square};
+
+/// The standard handicaps, with the squares of White's pieces removed from the initial position.
+const HANDICAPS: [(&str, &[Square]); 10] = [
+    ("香落ち", &[Square::SQ_1A]),
+    ("右香落ち", &[Square::SQ_9A]),
+    ("角落ち", &[Square::SQ_2B]),
+    ("飛車落ち", &[Square::SQ_8B]),
+    ("飛香落ち", &[Square::SQ_8B, Square::SQ_1A]),
+    ("二枚落ち", &[Square::SQ_8B, Square::SQ_2B]),
+    (
+        "四枚落ち",
+        &[Square::SQ_8B, Square::SQ_2B, Square::SQ_1A, Square::SQ_9A],
+    ),
+    (
+        "六枚落ち",
+        &[
+            Square::SQ_8B,
+            Square::SQ_2B,
+            Square::SQ_1A,
+            Square::SQ_9A,
+            Square::SQ_2A,
+            Square::SQ_8A,
+        ],
+    ),
+    (
+        "八枚落ち",
+        &[
+            Square::SQ_8B,
+            Square::SQ_2B,
+            Square::SQ_1A,
+            Square::SQ_9A,
+            Square::SQ_2A,
+            Square::SQ_8A,
+            Square::SQ_3A,
+            Square::SQ_7A,
+        ],
+    ),
+    (
+        "十枚落ち",
+        &[
+            Square::SQ_8B,
+            Square::SQ_2B,
+            Square::SQ_1A,
+            Square::SQ_9A,
+            Square::SQ_2A,
+            Square::SQ_8A,
+            Square::SQ_3A,
+            Square::SQ_7A,
+            Square::SQ_4A,
+            Square::SQ_6A,
+        ],
+    ),
+];
+
+/// Finds the name of the handicap, such as `香落ち`, that gives `position`, as written in the `手合割` header of KIF.
+///
+/// In a handicap game, White (`上手`) removes some pieces from the initial position and moves first.
+/// Returns [`None`] if `position` is not the initial position of a standard handicap, including when it is the even one (`平手`).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::detect_handicap;
+/// let pos = PartialPosition::from_usi("sfen lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1").unwrap();
+/// assert_eq!(detect_handicap(&pos), Some("角落ち"));
+/// assert_eq!(detect_handicap(&PartialPosition::startpos()), None);
+/// ```
+pub fn detect_handicap(position: &PartialPosition) -> Option<&'static str> {
+    if position.side_to_move() != Color::White
+        || Color::all()
+            .iter()
+            .any(|&color| position.hand_of_a_player(color) != Hand::new())
+    {
+        return None;
+    }
+    let startpos = PartialPosition::startpos();
+    HANDICAPS
+        .iter()
+        .find(|(_, removed)| {
+            Square::all().all(|square| {
+                let expected = if removed.contains(&square) {
+                    None
+                } else {
+                    startpos.piece_at(square)
+                };
+                position.piece_at(square) == expected
+            })
+        })
+        .map(|&(name, _)| name)
+}
+
+/// Returns the initial position of the standard handicap named `name`, the inverse of [`detect_handicap`].
+pub(crate) fn handicap_position(name: &str) -> Option<PartialPosition> {
+    let &(_, removed) = HANDICAPS.iter().find(|&&(n, _)| n == name)?;
+    let mut position = PartialPosition::startpos();
+    for &square in removed {
+        position.piece_set(square, None);
+    }
+    position.side_to_move_set(Color::White);
+    Some(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn handicap_position_round_trip() {
+        for &(name, _) in &HANDICAPS {
+            let position = handicap_position(name).unwrap();
+            assert_eq!(detect_handicap(&position), Some(name));
+        }
+        assert_eq!(handicap_position("平手"), None);
+    }
+
+    #[test]
+    fn detect_handicap_works() {
+        let cases = [
+            (
+                "lnsgkgsn1/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+                Some("香落ち"),
+            ),
+            (
+                "1nsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+                Some("右香落ち"),
+            ),
+            (
+                "lnsgkgsn1/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+                Some("飛香落ち"),
+            ),
+            (
+                "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+                Some("二枚落ち"),
+            ),
+            (
+                "4k4/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1",
+                Some("十枚落ち"),
+            ),
+            // Black to move
+            (
+                "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+                None,
+            ),
+            // Pieces in hand
+            (
+                "lnsgkgsnl/9/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w R 1",
+                None,
+            ),
+            // Black's piece removed
+            (
+                "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B7/LNSGKGSNL w - 1",
+                None,
+            ),
+        ];
+        for (sfen, expected) in cases {
+            let pos = PartialPosition::from_usi(&alloc::format!("sfen {}", sfen)).unwrap();
+            assert_eq!(detect_handicap(&pos), expected, "{}", sfen);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use alloc::vec::Vec;
+use shogi_core::{Color, Hand, PartialPosition};
+
+use crate::GameRecord;
+
+/// Returns the pieces in hand of both sides at every position in `record`, indexed by [`Color::array_index`].
+///
+/// As in [`GameRecord::position_hashes`], the hands at `ply` are those after `ply` moves,
+/// so the first entry is of the initial position and the one at `index + 1` is of the position after
+/// the move that [`RenderedMoves::get(index)`](crate::RenderedMoves::get) returns.
+/// The moves are made once from the start to the end, and the timeline stops at the first move that cannot be made,
+/// so it is shorter than `record.len() + 1` if there is one.
+/// [`KifuMoves::hands`](crate::KifuMoves::hands) gives the same hands while the moves are being written.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Color, PieceKind, Position};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{hand_timeline, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let timeline = hand_timeline(&record);
+/// assert_eq!(timeline.len(), 5);
+/// let black = Color::Black.array_index();
+/// assert_eq!(timeline[2][black].count(PieceKind::Bishop), Some(0));
+/// assert_eq!(timeline[3][black].count(PieceKind::Bishop), Some(1));
+/// assert_eq!(timeline[4][Color::White.array_index()], timeline[4][black]);
+/// ```
+pub fn hand_timeline(record: &GameRecord) -> Vec<[Hand; 2]> {
+    let mut position = record.initial().clone();
+    let mut timeline = Vec::with_capacity(record.len() + 1);
+    timeline.push(hands(&position));
+    for mv in record.moves() {
+        if position.make_move(mv).is_none() {
+            break;
+        }
+        timeline.push(hands(&position));
+    }
+    timeline
+}
+
+/// Returns the pieces in hand of both sides in `position`, indexed by [`Color::array_index`].
+pub(crate) fn hands(position: &PartialPosition) -> [Hand; 2] {
+    [
+        position.hand_of_a_player(Color::Black),
+        position.hand_of_a_player(Color::White),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, PieceKind, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn hand_timeline_follows_captures_and_drops() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let mut record =
+            GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let bishops =
+            |hands: &[Hand; 2]| hands.map(|hand| hand.count(PieceKind::Bishop).unwrap_or(0));
+        let timeline = hand_timeline(&record);
+        assert_eq!(
+            timeline.iter().map(bishops).collect::<Vec<_>>(),
+            [[0, 0], [0, 0], [0, 0], [1, 0], [1, 1], [0, 1]]
+        );
+
+        // The timeline stops before a move that cannot be made.
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        assert_eq!(hand_timeline(&record), timeline);
+
+        let mut moves = crate::kifu_moves(record.initial().clone(), record.moves());
+        let mut hands = alloc::vec![moves.hands()];
+        while let Some(Ok(_)) = moves.next() {
+            hands.push(moves.hands());
+        }
+        assert_eq!(hands, timeline);
+    }
+}
@@ -0,0 +1,102 @@
+use shogi_core::{Color, Hand, PartialPosition, Square};
+
+/// The number of keys in [`KEYS`]: one for every piece on every square,
+/// one for every count of every piece in every hand, and one for White to move.
+const NUM_KEYS: usize = 28 * 81 + 2 * 7 * 19 + 1;
+
+/// Pseudo-random keys generated by SplitMix64 at compile time, so that hashes do not depend on the platform.
+static KEYS: [u64; NUM_KEYS] = {
+    let mut keys = [0; NUM_KEYS];
+    let mut state: u64 = 0x5348_4f47_4b49_4655; // "SHOGKIFU"
+    let mut index = 0;
+    while index < NUM_KEYS {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[index] = z ^ (z >> 31);
+        index += 1;
+    }
+    keys
+};
+
+const HAND_OFFSET: usize = 28 * 81;
+
+/// Computes a Zobrist hash of `position`, for finding repeated positions.
+///
+/// The pieces on the board, the pieces in hand and the side to move are hashed, which is what
+/// makes positions the same for the rule of repetition (千日手). The ply and the last move are not.
+/// Different positions may have the same hash, though this is very unlikely.
+///
+/// Hashes are the same on every platform, but may change between versions of this crate.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::position_hash;
+/// let a = Position::from_usi("startpos moves 7g7f 3c3d 2g2f").unwrap();
+/// let b = Position::from_usi("startpos moves 2g2f 3c3d 7g7f").unwrap();
+/// assert_eq!(position_hash(a.inner()), position_hash(b.inner()));
+/// let c = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// assert_ne!(position_hash(a.inner()), position_hash(c.inner()));
+/// ```
+pub fn position_hash(position: &PartialPosition) -> u64 {
+    let mut hash = 0;
+    for square in Square::all() {
+        if let Some(piece) = position.piece_at(square) {
+            let piece_index = piece.color().array_index() * 14 + piece.piece_kind().array_index();
+            hash ^= KEYS[piece_index * 81 + square.array_index()];
+        }
+    }
+    for color in Color::all() {
+        hash ^= hand_hash(position.hand_of_a_player(color), color);
+    }
+    if position.side_to_move() == Color::White {
+        hash ^= KEYS[NUM_KEYS - 1];
+    }
+    hash
+}
+
+fn hand_hash(hand: Hand, color: Color) -> u64 {
+    let mut hash = 0;
+    for (kind_index, piece_kind) in Hand::all_hand_pieces().enumerate() {
+        let count = hand.count(piece_kind).unwrap_or(0) as usize;
+        // At most 18 pieces of a kind exist.
+        let index = (color.array_index() * 7 + kind_index) * 19 + count.min(18);
+        hash ^= KEYS[HAND_OFFSET + index];
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn position_hash_works() {
+        let hash = |usi: &str| position_hash(Position::from_usi(usi).unwrap().inner());
+        // The ply is ignored.
+        assert_eq!(
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 1"),
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 31"),
+        );
+        // The side to move and the hands count.
+        assert_ne!(
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 1"),
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 w P 1"),
+        );
+        assert_ne!(
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 1"),
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b p 1"),
+        );
+        assert_ne!(
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 1"),
+            hash("sfen 4k4/9/9/9/9/9/9/9/4K4 b 2P 1"),
+        );
+        // Going back and forth repeats the position.
+        assert_eq!(hash("startpos"), hash("startpos moves 2h3h 8b7b 3h2h 7b8b"),);
+    }
+}
@@ -0,0 +1,239 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+use shogi_core::{Color, ToUsi};
+
+use crate::{display_single_move_write_with_style, GameRecord, Notation, Numbering, Style};
+
+/// Writes the moves of a game as an HTML list, so that web pages can style and link every move.
+///
+/// The moves are written in an `<ol class="kifu">`, one `<li>` for each move with these attributes:
+/// - `class`: `move` and the side, `black` or `white`.
+/// - `data-ply`: the ply of the move, counting from 1 for a game from the initial position.
+///   Games from other positions are numbered as [`numbering`](HtmlMoves::numbering) sets.
+/// - `data-usi`: the move in USI, such as `7g7f`.
+///
+/// If [`anchors`](HtmlMoves::anchors) is set, every `<li>` also has an `id` such as `ply-37`,
+/// which [`ply_fragment`] links to.
+///
+/// If [`ruby`](HtmlMoves::ruby) is set, every move is wrapped in `<ruby>` with its reading in `<rt>`,
+/// as [`Notation::Yomi`] reads it.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, HtmlMoves};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// assert_eq!(
+///     HtmlMoves::new(&record).render().unwrap(),
+///     "\
+/// <ol class=\"kifu\">
+/// <li class=\"move black\" data-ply=\"1\" data-usi=\"7g7f\">▲７６歩</li>
+/// <li class=\"move white\" data-ply=\"2\" data-usi=\"3c3d\">△３４歩</li>
+/// </ol>
+/// ",
+/// );
+/// assert!(HtmlMoves::new(&record)
+///     .ruby(true)
+///     .render()
+///     .unwrap()
+///     .contains("<ruby>▲７６歩<rt>ななろくふ</rt></ruby>"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct HtmlMoves<'a> {
+    record: &'a GameRecord,
+    style: Style,
+    ruby: bool,
+    anchors: bool,
+    numbering: Numbering,
+}
+
+impl<'a> HtmlMoves<'a> {
+    /// Creates an [`HtmlMoves`] that writes the moves of `record` in [`Style::OFFICIAL`] without readings.
+    pub fn new(record: &'a GameRecord) -> Self {
+        Self {
+            record,
+            style: Style::OFFICIAL,
+            ruby: false,
+            anchors: false,
+            numbering: Numbering::Continue,
+        }
+    }
+
+    /// Sets the [`Style`] that moves are written in.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets whether every move is annotated with its reading in `<ruby>`.
+    pub fn ruby(mut self, ruby: bool) -> Self {
+        self.ruby = ruby;
+        self
+    }
+
+    /// Sets whether every move has an `id` that [`ply_fragment`] links to, so that pages can link to a move.
+    ///
+    /// The `id` follows the number of the move, as [`numbering`](HtmlMoves::numbering) sets,
+    /// so it only depends on the ply and stays the same when the record is published again.
+    pub fn anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Sets how moves are numbered if the initial position is not at ply 1. The default is [`Numbering::Continue`].
+    pub fn numbering(mut self, numbering: Numbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Writes the list into a [`String`].
+    ///
+    /// Returns [`None`] if a move in the record cannot be made.
+    pub fn render(&self) -> Option<String> {
+        let mut ret = String::new();
+        self.write(&mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Writes the list to a [`Write`].
+    ///
+    /// If the first move is not numbered 1, the `start` attribute of `<ol>` is set to its number.
+    /// Every move is rendered before anything is written, so nothing is written if a move cannot be made.
+    ///
+    /// Returns `Ok(None)` if a move in the record cannot be made.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+        let reading_style = Style {
+            notation: Notation::Yomi,
+            ..self.style
+        };
+        let mut position = self.record.initial().clone();
+        // Plies are counted here rather than taken from `position`, whose ply is a `u16`.
+        let first_ply = self.numbering.first_ply(&position);
+        let mut items = Vec::with_capacity(self.record.len());
+        for (index, mv) in self.record.moves().enumerate() {
+            let mut item = String::new();
+            let side = if position.side_to_move() == Color::Black {
+                "black"
+            } else {
+                "white"
+            };
+            let ply = first_ply + index;
+            item.push_str("<li ");
+            if self.anchors {
+                write!(item, "id=\"{}{}\" ", ANCHOR_PREFIX, ply)?;
+            }
+            write!(
+                item,
+                "class=\"move {}\" data-ply=\"{}\" data-usi=\"",
+                side, ply
+            )?;
+            mv.to_usi(&mut item)?;
+            item.push_str("\">");
+            if self.ruby {
+                item.push_str("<ruby>");
+            }
+            if display_single_move_write_with_style(&position, mv, &self.style, &mut item)?
+                .is_none()
+            {
+                return Ok(None);
+            }
+            if self.ruby {
+                item.push_str("<rt>");
+                display_single_move_write_with_style(&position, mv, &reading_style, &mut item)?;
+                item.push_str("</rt></ruby>");
+            }
+            item.push_str("</li>\n");
+            if position.make_move(mv).is_none() {
+                return Ok(None);
+            }
+            items.push(item);
+        }
+        if first_ply == 1 {
+            w.write_str("<ol class=\"kifu\">\n")?;
+        } else {
+            writeln!(w, "<ol class=\"kifu\" start=\"{}\">", first_ply)?;
+        }
+        for item in &items {
+            w.write_str(item)?;
+        }
+        w.write_str("</ol>\n")?;
+        Ok(Some(()))
+    }
+}
+
+/// The beginning of the anchors of moves, followed by the ply.
+pub(crate) const ANCHOR_PREFIX: &str = "ply-";
+
+/// Returns the URL fragment that links to the move at `ply`, such as `#ply-37`,
+/// in the documents that [`HtmlMoves`] and [`MarkdownTable`](crate::MarkdownTable) write with anchors.
+///
+/// `ply` is the number of the move as it is written, which depends on the [`Numbering`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{ply_fragment, GameRecord, HtmlMoves};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let html = HtmlMoves::new(&record).anchors(true).render().unwrap();
+/// assert_eq!(ply_fragment(2), "#ply-2");
+/// assert!(html.contains("<li id=\"ply-2\" class=\"move white\""));
+/// let url = format!("https://example.com/kifu/1.html{}", ply_fragment(2));
+/// assert_eq!(url, "https://example.com/kifu/1.html#ply-2");
+/// ```
+pub fn ply_fragment(ply: usize) -> String {
+    alloc::format!("#{}{}", ANCHOR_PREFIX, ply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, PartialPosition, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn html_works() {
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 99 moves G*5b 5a5b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let result = HtmlMoves::new(&record)
+            .style(Style::TRADITIONAL)
+            .ruby(true)
+            .render()
+            .unwrap();
+        assert_eq!(
+            result,
+            "\
+<ol class=\"kifu\" start=\"99\">
+<li class=\"move black\" data-ply=\"99\" data-usi=\"G*5b\"><ruby>▲５二金<rt>ごにきん</rt></ruby></li>
+<li class=\"move white\" data-ply=\"100\" data-usi=\"5a5b\"><ruby>△同玉<rt>どうぎょく</rt></ruby></li>
+</ol>
+",
+        );
+        let result = HtmlMoves::new(&record)
+            .numbering(Numbering::Restart)
+            .render()
+            .unwrap();
+        assert!(result.starts_with("<ol class=\"kifu\">\n<li class=\"move black\" data-ply=\"1\""));
+        let result = HtmlMoves::new(&record).anchors(true).render().unwrap();
+        assert!(result.contains("<li id=\"ply-99\" class=\"move black\" data-ply=\"99\""));
+        assert!(result.contains("<li id=\"ply-100\" class=\"move white\" data-ply=\"100\""));
+        assert_eq!(ply_fragment(100), "#ply-100");
+
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        let mut result = String::new();
+        assert_eq!(HtmlMoves::new(&record).write(&mut result), Ok(None));
+        assert_eq!(result, "");
+    }
+}
@@ -0,0 +1,175 @@
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition};
+
+use crate::display_single_move_write;
+
+/// An adapter that lets the writers of this crate, which write to a [`core::fmt::Write`], write to a [`std::io::Write`].
+///
+/// Every string is written with [`write_all`](std::io::Write::write_all) as it comes,
+/// so wrap files and sockets in a [`BufWriter`](std::io::BufWriter).
+/// [`core::fmt::Error`] carries no information, so the [`std::io::Error`] that caused it is kept,
+/// and [`IoWriter::finish`] turns the result of a writer back into a [`std::io::Result`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameLayout, GameRecord, IoWriter};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut w = IoWriter::new(Vec::new());
+/// let result = GameLayout::new().write(&record, &mut w);
+/// assert_eq!(w.finish(result).unwrap(), Some(()));
+/// assert_eq!(w.into_inner(), "1 ▲７六歩 2 △３四歩\n".as_bytes());
+/// ```
+#[derive(Debug)]
+pub struct IoWriter<W> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: std::io::Write> IoWriter<W> {
+    /// Creates an [`IoWriter`] that writes to `inner`.
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Returns a reference to the underlying [`std::io::Write`].
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying [`std::io::Write`].
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Unwraps this [`IoWriter`], returning the underlying [`std::io::Write`].
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Converts `result`, returned by a writer that wrote to `self`, into a [`std::io::Result`].
+    ///
+    /// A [`core::fmt::Error`] becomes the [`std::io::Error`] that caused it,
+    /// or an error of [`ErrorKind::Other`](std::io::ErrorKind::Other) if the writer failed on its own.
+    pub fn finish<T>(&mut self, result: Result<T, core::fmt::Error>) -> std::io::Result<T> {
+        result.map_err(|_| {
+            self.error.take().unwrap_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::Other, "formatter error")
+            })
+        })
+    }
+}
+
+impl<W: std::io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            core::fmt::Error
+        })
+    }
+}
+
+/// Runs `write` with `w` wrapped in an [`IoWriter`], and converts the result into a [`std::io::Result`].
+pub(crate) fn write_io<W: std::io::Write, T>(
+    w: &mut W,
+    write: impl FnOnce(&mut IoWriter<&mut W>) -> Result<T, core::fmt::Error>,
+) -> std::io::Result<T> {
+    let mut w = IoWriter::new(w);
+    let result = write(&mut w);
+    w.finish(result)
+}
+
+/// Finds the string representation of a [`Move`] and write it to a [`std::io::Write`].
+///
+/// Returns `Ok(None)` if the move cannot be written.
+///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::display_single_move_write_io;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// let mut out = Vec::new();
+/// assert_eq!(display_single_move_write_io(&pos, mv, &mut out).unwrap(), Some(()));
+/// assert_eq!(out, "▲７６歩".as_bytes());
+/// ```
+pub fn display_single_move_write_io<W: std::io::Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> std::io::Result<Option<()>> {
+    write_io(w, |w| display_single_move_write(position, mv, w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{csa::CsaRecord, ki2, GameRecord, KifFile};
+    use shogi_core::{Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    // Accepts `limit` bytes, and then fails with `ErrorKind::WriteZero`.
+    struct Full {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl std::io::Write for Full {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.limit - self.written.len());
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn io_errors_are_kept() {
+        let pos = PartialPosition::startpos();
+        let mv = Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        };
+        let mut full = Full {
+            written: Vec::new(),
+            limit: 4,
+        };
+        let error = display_single_move_write_io(&pos, mv, &mut full).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::WriteZero);
+        assert!(full.written.starts_with("▲".as_bytes()));
+
+        let mut w = IoWriter::new(Vec::new());
+        let error = w.finish::<()>(Err(core::fmt::Error)).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn game_writers_agree_with_fmt() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut out = Vec::new();
+        ki2::write_io(&record, &mut out).unwrap().unwrap();
+        assert_eq!(out, ki2::to_string(&record).unwrap().as_bytes());
+        let kif = KifFile::new(&record);
+        let mut out = Vec::new();
+        kif.write_io(&mut out).unwrap().unwrap();
+        assert_eq!(out, kif.render().unwrap().as_bytes());
+        let csa = CsaRecord::new(pos);
+        let mut out = Vec::new();
+        csa.write_io(&mut out).unwrap().unwrap();
+        assert_eq!(out, csa.render().unwrap().as_bytes());
+    }
+}
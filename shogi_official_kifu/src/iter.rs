@@ -0,0 +1,115 @@
+use alloc::string::String;
+use core::iter::FusedIterator;
+use shogi_core::{Hand, Move, PartialPosition};
+
+use crate::{hands, try_display_single_move, KifuError};
+
+/// Writes moves made one after another from `position`, as they are pulled from the iterator.
+///
+/// Each item is the official notation of a move, with `同` where it captures on the square the previous one moved to.
+/// The first move that cannot be written or made yields an error, and the iterator ends after it.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{PartialPosition, Position};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::kifu_moves;
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let moves: Result<Vec<_>, _> =
+///     kifu_moves(PartialPosition::startpos(), pos.moves().iter().copied()).collect();
+/// assert_eq!(moves.unwrap(), ["▲７６歩", "△３４歩", "▲２２角成", "△同銀"]);
+/// ```
+pub fn kifu_moves<I: IntoIterator<Item = Move>>(
+    position: PartialPosition,
+    moves: I,
+) -> KifuMoves<I::IntoIter> {
+    KifuMoves {
+        position,
+        moves: moves.into_iter(),
+        failed: false,
+    }
+}
+
+/// An iterator over the notation of moves, as returned by [`kifu_moves`].
+#[derive(Clone, Debug)]
+pub struct KifuMoves<I> {
+    position: PartialPosition,
+    moves: I,
+    failed: bool,
+}
+
+impl<I> KifuMoves<I> {
+    /// Returns the position after the moves yielded so far.
+    pub fn position(&self) -> &PartialPosition {
+        &self.position
+    }
+
+    /// Returns the pieces in hand of both sides after the moves yielded so far, indexed by [`Color::array_index`](shogi_core::Color::array_index).
+    ///
+    /// Reading them after every item gives the same as [`hand_timeline`](crate::hand_timeline) without making the moves again.
+    pub fn hands(&self) -> [Hand; 2] {
+        hands::hands(&self.position)
+    }
+}
+
+impl<I: Iterator<Item = Move>> Iterator for KifuMoves<I> {
+    type Item = Result<String, KifuError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let mv = self.moves.next()?;
+        let result = try_display_single_move(&self.position, mv).and_then(|notation| {
+            self.position
+                .make_move(mv)
+                .map(|()| notation)
+                .ok_or(KifuError::Unreachable)
+        });
+        self.failed = result.is_err();
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.failed {
+            return (0, Some(0));
+        }
+        let (_, upper) = self.moves.size_hint();
+        (0, upper)
+    }
+}
+
+impl<I: Iterator<Item = Move>> FusedIterator for KifuMoves<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Square;
+
+    #[test]
+    fn kifu_moves_stops_at_errors() {
+        let moves = [
+            Move::Normal {
+                from: Square::SQ_7G,
+                to: Square::SQ_7F,
+                promote: false,
+            },
+            Move::Normal {
+                from: Square::SQ_7F,
+                to: Square::SQ_7E,
+                promote: false,
+            },
+            Move::Normal {
+                from: Square::SQ_3C,
+                to: Square::SQ_3D,
+                promote: false,
+            },
+        ];
+        let mut iter = kifu_moves(PartialPosition::startpos(), moves);
+        assert_eq!(iter.next(), Some(Ok("▲７６歩".into())));
+        assert_eq!(iter.next(), Some(Err(KifuError::WrongSide)));
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.position().ply(), 2);
+    }
+}
@@ -0,0 +1,125 @@
+use core::fmt::Write;
+
+use crate::{kifu_move, wrap::Wrapper, GameRecord, Style};
+
+/// The width of a line that [`write`] and [`to_string`] wrap at.
+///
+/// Widths are measured in columns, where a full-width character takes two columns.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// Writes the moves of `record` in KI2 text, wrapped at [`DEFAULT_WIDTH`].
+///
+/// Returns [`None`] if a move in `record` cannot be made.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{ki2, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// assert_eq!(
+///     ki2::to_string(&record),
+///     Some("▲７六歩 △３四歩 ▲２二角成 △同銀\n".to_string()),
+/// );
+/// ```
+pub fn to_string(record: &GameRecord) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    write(record, &mut ret).expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Writes the moves of `record` in KI2 text to a [`Write`], wrapped at [`DEFAULT_WIDTH`].
+///
+/// Returns `Ok(None)` if a move in `record` cannot be made, in which case the moves before it have already been written.
+///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+pub fn write<W: Write>(record: &GameRecord, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+    write_with_width(record, DEFAULT_WIDTH, w)
+}
+
+/// Writes the moves of `record` in KI2 text to a [`std::io::Write`], such as a file, wrapped at [`DEFAULT_WIDTH`].
+///
+/// Same as [`write`].
+///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub fn write_io<W: std::io::Write>(record: &GameRecord, w: &mut W) -> std::io::Result<Option<()>> {
+    crate::io::write_io(w, |w| write(record, w))
+}
+
+/// Writes the moves of `record` in KI2 text to a [`Write`], wrapped at `width` columns.
+///
+/// Moves are separated by a space, and every line (including the last one) ends with `\n`.
+/// A move is never split, so a line may exceed `width` if a single move does not fit.
+///
+/// # Errors
+/// Same as [`write`].
+pub fn write_with_width<W: Write>(
+    record: &GameRecord,
+    width: usize,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let mut position = record.initial().clone();
+    let mut buffer = alloc::string::String::new();
+    let mut wrapper = Wrapper::new(width, "");
+    for mv in record.moves() {
+        let kifu_move = if let Ok(kifu_move) = kifu_move::analyze(&position, mv) {
+            kifu_move
+        } else {
+            return Ok(None);
+        };
+        if position.make_move(mv).is_none() {
+            return Ok(None);
+        }
+        buffer.clear();
+        kifu_move.write(&Style::TRADITIONAL, &mut buffer)?;
+        wrapper.write_word(&buffer, " ", w)?;
+    }
+    wrapper.finish(w)?;
+    Ok(Some(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, PartialPosition, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn write_with_width_works() {
+        let pos = Position::from_usi("startpos moves 2g2f 8c8d 2f2e 8d8e 2e2d 2c2d 2h2d").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        // Each move takes 8 columns.
+        let mut result = alloc::string::String::new();
+        write_with_width(&record, 17, &mut result).unwrap().unwrap();
+        assert_eq!(
+            result,
+            "▲２六歩 △８四歩\n▲２五歩 △８五歩\n▲２四歩 △同歩\n▲同飛\n",
+        );
+        // A move wider than the line is not split.
+        let mut result = alloc::string::String::new();
+        write_with_width(&record, 4, &mut result).unwrap().unwrap();
+        assert_eq!(result.lines().count(), 7);
+    }
+
+    #[test]
+    fn write_empty_record() {
+        let record = GameRecord::new(PartialPosition::startpos());
+        assert_eq!(to_string(&record), Some(alloc::string::String::new()));
+    }
+
+    #[test]
+    fn write_stops_at_invalid_move() {
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        assert_eq!(to_string(&record), None);
+    }
+}
@@ -0,0 +1,1744 @@
+use alloc::{collections::BTreeSet, string::String, vec::Vec};
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition, Piece, PieceKind, Square};
+
+use crate::{
+    bod::{self, BodReader},
+    detect_handicap, display_special_move,
+    handicap::handicap_position,
+    is_promotable_piece, ki2, piece_kind_to_kanji,
+    record::is_startpos,
+    sanitize, wrap, Ending, GameRecord, GameTree, KingGlyphs, Numbering, Outcome, ReadLimits,
+    SpecialMove, KANSUJI, SANYOU_SUJI,
+};
+
+/// The width in columns that moves are padded to before their times, enough for the widest move (`２二銀不成(33)`).
+const MOVE_WIDTH: usize = 14;
+
+/// The width in columns that ply numbers are right-aligned to in games of up to 9999 moves, as KIF readers expect.
+const NUMBER_WIDTH: usize = 4;
+
+/// Returns the width that the ply numbers up to `last_ply` are right-aligned to:
+/// [`NUMBER_WIDTH`], or the number of digits of `last_ply` if it has more, so that longer games stay aligned.
+fn number_width(last_ply: usize) -> usize {
+    let mut digits = 1;
+    let mut rest = last_ply / 10;
+    while rest > 0 {
+        digits += 1;
+        rest /= 10;
+    }
+    digits.max(NUMBER_WIDTH)
+}
+
+/// Finds the KIF representation of a [`Move`], such as `７六歩(77)`.
+///
+/// Instead of the suffixes of the official notation, the origin of a normal move is written in parentheses.
+/// Sides are not written, and `同` is followed by a full-width space (`同　銀(39)`).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::display_single_move_kif;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(display_single_move_kif(&pos, mv), Some("７六歩(77)".to_string()));
+/// ```
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+pub fn display_single_move_kif(
+    position: &PartialPosition,
+    mv: Move,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_kif(position, mv, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the KIF representation of a [`Move`] and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`](crate::display_single_move_write).
+///
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+pub fn display_single_move_write_kif<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    write_kif(position, mv, KingGlyphs::Gyoku, w)
+}
+
+fn write_kif<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    kings: KingGlyphs,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let side = position.side_to_move();
+    let name = |piece_kind| match piece_kind {
+        PieceKind::King => kings.glyph(side, side),
+        _ => piece_kind_to_kanji(piece_kind),
+    };
+    match mv {
+        Move::Normal { from, to, promote } => {
+            let p = if let Some(p) = position.piece_at(from) {
+                p
+            } else {
+                return Ok(None);
+            };
+            if position.last_move().map(|last_move| last_move.to()) == Some(to) {
+                w.write_str("同　")?;
+            } else {
+                w.write_char(*unsafe { SANYOU_SUJI.get_unchecked(to.file() as usize - 1) })?;
+                w.write_char(*unsafe { KANSUJI.get_unchecked(to.rank() as usize - 1) })?;
+            }
+            w.write_str(name(p.piece_kind()))?;
+            let could_promote = is_promotable_piece(p.piece_kind())
+                && (from.relative_rank(side) <= 3 || to.relative_rank(side) <= 3);
+            if promote {
+                w.write_char('成')?;
+            } else if could_promote {
+                w.write_str("不成")?;
+            }
+            write!(w, "({}{})", from.file(), from.rank())?;
+        }
+        Move::Drop { to, piece } => {
+            w.write_char(*unsafe { SANYOU_SUJI.get_unchecked(to.file() as usize - 1) })?;
+            w.write_char(*unsafe { KANSUJI.get_unchecked(to.rank() as usize - 1) })?;
+            w.write_str(name(piece.piece_kind()))?;
+            w.write_char('打')?;
+        }
+    }
+    Ok(Some(()))
+}
+
+/// A builder of a whole KIF document.
+///
+/// Headers that are not given are omitted, except for `手合割`:
+/// it defaults to `平手` if the game starts from the initial position,
+/// to the name of the handicap if it starts from that of a standard handicap (see [`detect_handicap`](crate::detect_handicap)),
+/// and otherwise the initial position is written as a board diagram.
+///
+/// In a handicap game, the players are called `下手` (Black) and `上手` (White) instead of `先手` and `後手`.
+///
+/// Header values and comments are escaped so that text from users cannot start a line of its own,
+/// which would be read as a move or a header: `\` is written as `\\`, a line feed as `\n`, a carriage return as `\r`,
+/// and the other characters that some editors take as line breaks (U+000B, U+000C, U+0085, U+2028 and U+2029)
+/// as `\u{b}`, `\u{c}`, `\u{85}`, `\u{2028}` and `\u{2029}`.
+/// A line feed in a comment still starts a new line of the comment, which begins with `*` again.
+/// [`parse_kif`] reverses the escaping.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, KifFile};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let kif = KifFile::new(&record).sente("羽生善治").gote("藤井聡太").render();
+/// assert_eq!(
+///     kif.as_deref(),
+///     Some(
+///         "手合割：平手\n\
+///          先手：羽生善治\n\
+///          後手：藤井聡太\n\
+///          手数----指手---------消費時間--\n   \
+///          1 ７六歩(77)\n   \
+///          2 ３四歩(33)\n\
+///          まで2手\n",
+///     ),
+/// );
+/// ```
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+#[derive(Clone, Debug)]
+pub struct KifFile<'a> {
+    record: &'a GameRecord,
+    start_time: Option<&'a str>,
+    event: Option<&'a str>,
+    handicap: Option<&'a str>,
+    sente: Option<&'a str>,
+    gote: Option<&'a str>,
+    comments: Vec<(usize, &'a str)>,
+    comment_width: usize,
+    anonymize: bool,
+    outcome: Option<Outcome>,
+    mark_repetitions: bool,
+    kings: KingGlyphs,
+    numbering: Numbering,
+    special_move: Option<SpecialMove>,
+    times: &'a [u32],
+    variations: &'a [(usize, GameTree)],
+}
+
+impl<'a> KifFile<'a> {
+    /// Creates a [`KifFile`] that writes `record` with no headers.
+    pub fn new(record: &'a GameRecord) -> Self {
+        Self {
+            record,
+            start_time: None,
+            event: None,
+            handicap: None,
+            sente: None,
+            gote: None,
+            comments: Vec::new(),
+            comment_width: ki2::DEFAULT_WIDTH,
+            anonymize: false,
+            outcome: None,
+            mark_repetitions: false,
+            kings: KingGlyphs::Gyoku,
+            numbering: Numbering::Restart,
+            special_move: None,
+            times: &[],
+            variations: &[],
+        }
+    }
+
+    /// Creates a [`KifFile`] that writes the main line of `tree` with no headers, followed by its variations.
+    ///
+    /// Moves with variations are marked with `+`, and every variation is written after the last line as a section
+    /// starting with `変化：N手`, where `N` is the ply of its first move. As Kifu for Windows does, variations forking later
+    /// are written first and every variation is followed by its own, so that each section forks from the last line
+    /// written that has a move at ply `N`. A variation that replaces the first move of another variation is read back
+    /// by [`parse_kif`] as a variation of the same line as that one.
+    /// Headers, comments, times and the special move apply to the main line only.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::{Move, Position, Square};
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{parse_kif, GameRecord, GameTree, KifFile};
+    /// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// let mut tree = GameTree::new(record);
+    /// let mv = Move::Normal {
+    ///     from: Square::SQ_8C,
+    ///     to: Square::SQ_8D,
+    ///     promote: false,
+    /// };
+    /// tree.add_variation(1, [mv]).unwrap();
+    /// let kif = KifFile::from_tree(&tree).render().unwrap();
+    /// assert_eq!(
+    ///     kif,
+    ///     "手合割：平手\n\
+    ///      手数----指手---------消費時間--\n   \
+    ///      1 ７六歩(77)\n   \
+    ///      2 ３四歩(33)+\n\
+    ///      まで2手\n\
+    ///      \n\
+    ///      変化：2手\n   \
+    ///      2 ８四歩(83)\n",
+    /// );
+    /// assert_eq!(parse_kif(&kif).unwrap().into_tree(), tree);
+    /// ```
+    pub fn from_tree(tree: &'a GameTree) -> Self {
+        let mut file = Self::new(&tree.main_line);
+        file.variations = &tree.variations;
+        file
+    }
+
+    /// Sets `開始日時`, such as `2023/01/01 10:00:00`.
+    pub fn start_time(mut self, start_time: &'a str) -> Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Sets `棋戦`.
+    pub fn event(mut self, event: &'a str) -> Self {
+        self.event = Some(event);
+        self
+    }
+
+    /// Sets `手合割`, such as `香落ち`.
+    ///
+    /// If this is set, the initial position is not written, so it must be the one the handicap describes.
+    pub fn handicap(mut self, handicap: &'a str) -> Self {
+        self.handicap = Some(handicap);
+        self
+    }
+
+    /// Sets `先手`, or `下手` in a handicap game.
+    pub fn sente(mut self, sente: &'a str) -> Self {
+        self.sente = Some(sente);
+        self
+    }
+
+    /// Sets `後手`, or `上手` in a handicap game.
+    pub fn gote(mut self, gote: &'a str) -> Self {
+        self.gote = Some(gote);
+        self
+    }
+
+    /// Sets the special move that ended the game, such as [`SpecialMove::Resign`], which is written after the last move (`   3 投了`).
+    ///
+    /// It does not count as a move in the last line.
+    pub fn special_move(mut self, special_move: SpecialMove) -> Self {
+        self.special_move = Some(special_move);
+        self
+    }
+
+    /// Sets the seconds that each move took, counting from the first move, which are written after the moves (`( 0:03/00:01:15)`).
+    ///
+    /// The time before the slash is the time the move took, in minutes and seconds,
+    /// and the one after it is the total time the side has taken so far, in hours, minutes and seconds.
+    /// Moves after the end of `times` are written without times.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::Position;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{GameRecord, KifFile};
+    /// let pos = Position::from_usi("startpos moves 7g7f 3c3d 2g2f").unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// let kif = KifFile::new(&record).times(&[3, 72, 65]).render().unwrap();
+    /// assert_eq!(
+    ///     kif.lines().skip(2).take(3).collect::<Vec<_>>(),
+    ///     [
+    ///         "   1 ７六歩(77)     ( 0:03/00:00:03)",
+    ///         "   2 ３四歩(33)     ( 1:12/00:01:12)",
+    ///         "   3 ２六歩(27)     ( 1:05/00:01:08)",
+    ///     ],
+    /// );
+    /// ```
+    pub fn times(mut self, times: &'a [u32]) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Sets how the game ended, which is written in the last line (`まで2手で後手の勝ち`).
+    ///
+    /// Sides are called `下手` and `上手` in a handicap game.
+    pub fn outcome(mut self, outcome: Outcome) -> Self {
+        self.outcome = Some(outcome);
+        self
+    }
+
+    /// Adds a comment after the move at `ply`, counting from 1, or before the first move if `ply` is 0.
+    ///
+    /// Comments are written on lines starting with `*`, wrapped at [`KifFile::comment_width`].
+    /// Comments after the same move are written in the order they are added.
+    /// [`parse_kif`] reads them back into [`KifRecord::comments`].
+    pub fn comment(mut self, ply: usize, comment: &'a str) -> Self {
+        self.comments.push((ply, comment));
+        self
+    }
+
+    /// Sets the width in columns that comments are wrapped at, including `*`.
+    ///
+    /// A full-width character takes two columns and is never split. Defaults to [`ki2::DEFAULT_WIDTH`].
+    pub fn comment_width(mut self, comment_width: usize) -> Self {
+        self.comment_width = comment_width;
+        self
+    }
+
+    /// Sets which kings are written `王` instead of `玉`, both in the board diagram and in the moves.
+    ///
+    /// Defaults to [`KingGlyphs::Gyoku`].
+    pub fn kings(mut self, kings: KingGlyphs) -> Self {
+        self.kings = kings;
+        self
+    }
+
+    /// Sets how moves are numbered if the initial position is not at ply 1.
+    ///
+    /// Defaults to [`Numbering::Restart`]. With [`Numbering::Continue`], the moves, the special move, `まで` and `変化：N手`
+    /// continue from the ply of the initial position, and [`parse_kif`] gives the initial position that ply back.
+    /// Comments are still added to moves counting from 1.
+    pub fn numbering(mut self, numbering: Numbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Adds a comment such as `*同一局面3回目` after every move that repeats a position,
+    /// counted by [`GameRecord::repetition_counts`].
+    ///
+    /// The fourth occurrence is marked `*同一局面4回目（千日手）`.
+    /// The comments are written before the ones added by [`KifFile::comment`].
+    pub fn mark_repetitions(mut self) -> Self {
+        self.mark_repetitions = true;
+        self
+    }
+
+    /// Removes personal data from the headers, for publishing records anonymously.
+    ///
+    /// The names of the players are replaced with `先手` and `後手`, or `下手` and `上手` in a handicap game,
+    /// and other headers containing an email address are omitted. Comments are written as they are.
+    pub fn anonymize(mut self) -> Self {
+        self.anonymize = true;
+        self
+    }
+
+    /// Writes the document into a [`String`](alloc::string::String).
+    ///
+    /// Returns [`None`] if a move in the record cannot be made.
+    pub fn render(&self) -> Option<alloc::string::String> {
+        let mut ret = alloc::string::String::new();
+        self.write(&mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Writes the document in Shift_JIS (CP932), the encoding that most `.kif` files use.
+    ///
+    /// Returns [`None`] if a move in the record cannot be made
+    /// or the document has a character that Shift_JIS cannot represent, such as `☗` in a player's name.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::Position;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{parse_kif_bytes, GameRecord, KifFile};
+    /// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// let bytes = KifFile::new(&record).sente("羽生").render_shift_jis().unwrap();
+    /// assert!(std::str::from_utf8(&bytes).is_err());
+    /// assert_eq!(parse_kif_bytes(&bytes).unwrap().header("先手"), Some("羽生"));
+    /// assert_eq!(KifFile::new(&record).sente("☗").render_shift_jis(), None);
+    /// ```
+    #[cfg(feature = "encoding")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+    pub fn render_shift_jis(&self) -> Option<Vec<u8>> {
+        let rendered = self.render()?;
+        let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(&rendered);
+        if had_errors {
+            return None;
+        }
+        Some(bytes.into_owned())
+    }
+
+    /// Writes the document to a [`std::io::Write`], such as a file.
+    ///
+    /// Same as [`KifFile::write`], with the document in UTF-8.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    #[cfg(feature = "std")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+    pub fn write_io<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<Option<()>> {
+        crate::io::write_io(w, |w| self.write(w))
+    }
+
+    /// Writes the document to a [`Write`].
+    ///
+    /// Returns `Ok(None)` if a move in the record cannot be made, in which case the lines before it have already been written.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+        if let Some(start_time) = self.header(self.start_time) {
+            write_header("開始日時", start_time, w)?;
+        }
+        if let Some(event) = self.header(self.event) {
+            write_header("棋戦", event, w)?;
+        }
+        let initial = self.record.initial();
+        let handicap = self.header(self.handicap).or_else(|| {
+            if is_startpos(initial) {
+                Some("平手")
+            } else {
+                detect_handicap(initial)
+            }
+        });
+        match handicap {
+            Some(handicap) => write_header("手合割", handicap, w)?,
+            None => bod::write_bod(initial, self.kings, w)?,
+        }
+        let is_handicap = handicap.map_or(false, |handicap| handicap != "平手");
+        let (black, white) = if is_handicap {
+            ("下手", "上手")
+        } else {
+            ("先手", "後手")
+        };
+        if let Some(sente) = self.sente {
+            write_header(black, if self.anonymize { black } else { sente }, w)?;
+        }
+        if let Some(gote) = self.gote {
+            write_header(white, if self.anonymize { white } else { gote }, w)?;
+        }
+        w.write_str("手数----指手---------消費時間--\n")?;
+        self.write_comments(0, w)?;
+        let repetition_counts = if self.mark_repetitions {
+            // A move that cannot be made stops the document below anyway.
+            self.record.repetition_counts().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let mut position = initial.clone();
+        let mut buffer = alloc::string::String::new();
+        let mut totals = [0; 2];
+        // Ply numbers are counted here rather than taken from `position`, whose ply is a `u16`.
+        let first_ply = self.numbering.first_ply(initial);
+        let width = number_width(first_ply + self.record.len());
+        for (index, mv) in self.record.moves().enumerate() {
+            write!(w, "{:>width$} ", first_ply + index, width = width)?;
+            buffer.clear();
+            let side = position.side_to_move();
+            if write_kif(&position, mv, self.kings, &mut buffer)?.is_none()
+                || position.make_move(mv).is_none()
+            {
+                return Ok(None);
+            }
+            w.write_str(&buffer)?;
+            if let Some(&time) = self.times.get(index) {
+                let total = &mut totals[side.array_index()];
+                *total += time;
+                for _ in wrap::display_width(&buffer)..MOVE_WIDTH {
+                    w.write_char(' ')?;
+                }
+                write!(
+                    w,
+                    " ({:>2}:{:02}/{:02}:{:02}:{:02})",
+                    time / 60,
+                    time % 60,
+                    *total / 3600,
+                    *total / 60 % 60,
+                    *total % 60,
+                )?;
+            }
+            if self.variations.iter().any(|&(i, _)| i == index) {
+                w.write_char('+')?;
+            }
+            w.write_char('\n')?;
+            match repetition_counts.get(index + 1) {
+                Some(4) => w.write_str("*同一局面4回目（千日手）\n")?,
+                Some(&count) if count >= 2 => writeln!(w, "*同一局面{}回目", count)?,
+                _ => {}
+            }
+            self.write_comments(index + 1, w)?;
+        }
+        if let Some(special_move) = self.special_move {
+            writeln!(
+                w,
+                "{:>width$} {}",
+                first_ply + self.record.len(),
+                display_special_move(special_move),
+                width = width,
+            )?;
+        }
+        let ending = Ending {
+            plies: first_ply - 1 + self.record.len(),
+            outcome: self.outcome,
+            handicap: is_handicap,
+        };
+        writeln!(w, "{}", ending)?;
+        self.write_variations(self.variations, first_ply, width, w)
+    }
+
+    /// Writes `variations` of a line whose first move is at `first_ply`, followed by their own variations,
+    /// with ply numbers right-aligned to `width`.
+    fn write_variations<W: Write>(
+        &self,
+        variations: &[(usize, GameTree)],
+        first_ply: usize,
+        width: usize,
+        w: &mut W,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        // Later forks first, and forks at the same move in the order they were added.
+        let mut order: Vec<_> = variations.iter().collect();
+        order.sort_by_key(|&&(index, _)| core::cmp::Reverse(index));
+        for (index, tree) in order {
+            let ply = first_ply + index;
+            write!(w, "\n変化：{}手\n", ply)?;
+            let mut position = tree.main_line.initial().clone();
+            for (i, mv) in tree.main_line.moves().enumerate() {
+                write!(w, "{:>width$} ", ply + i, width = width)?;
+                if write_kif(&position, mv, self.kings, w)?.is_none()
+                    || position.make_move(mv).is_none()
+                {
+                    return Ok(None);
+                }
+                if tree.variations.iter().any(|&(j, _)| j == i) {
+                    w.write_char('+')?;
+                }
+                w.write_char('\n')?;
+            }
+            if self
+                .write_variations(&tree.variations, ply, width, w)?
+                .is_none()
+            {
+                return Ok(None);
+            }
+        }
+        Ok(Some(()))
+    }
+
+    /// Returns `value` unless it must be omitted for anonymity.
+    fn header(&self, value: Option<&'a str>) -> Option<&'a str> {
+        value.filter(|value| !(self.anonymize && sanitize::contains_email(value)))
+    }
+
+    /// Writes the comments on the move at `ply`, followed by the bookmarks of the position after it.
+    fn write_comments<W: Write>(&self, ply: usize, w: &mut W) -> core::fmt::Result {
+        for &(_, comment) in self.comments.iter().filter(|&&(p, _)| p == ply) {
+            wrap::write_wrapped(comment, "*", self.comment_width, sanitize::kif_escape, w)?;
+        }
+        for (name, _) in self.record.bookmarks().filter(|&(_, p)| p == ply) {
+            w.write_char('&')?;
+            sanitize::write_kif_escaped(name, w)?;
+            w.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a line of a header, escaping `value`.
+fn write_header<W: Write>(key: &str, value: &str, w: &mut W) -> core::fmt::Result {
+    write!(w, "{}：", key)?;
+    sanitize::write_kif_escaped(value, w)?;
+    w.write_char('\n')
+}
+
+/// A game record read from KIF by [`parse_kif`].
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::{parse_kif, SpecialMove};
+/// let text = "\
+/// 手合割：平手
+/// 先手：羽生善治
+/// 手数----指手---------消費時間--
+/// *序盤の研究
+///    1 ７六歩(77)
+/// *角道を開ける
+///    2 投了
+/// まで1手で先手の勝ち
+/// ";
+/// let kif = parse_kif(text).unwrap();
+/// assert_eq!(kif.header("先手"), Some("羽生善治"));
+/// assert_eq!(kif.record.len(), 1);
+/// assert_eq!(
+///     kif.comments,
+///     [(0, "序盤の研究".to_string()), (1, "角道を開ける".to_string())],
+/// );
+/// assert_eq!(kif.special_move, Some(SpecialMove::Resign));
+/// assert!(kif.kif_file().render().unwrap().contains("*角道を開ける\n"));
+/// ```
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct KifRecord {
+    /// Headers other than `手合割`, such as `先手` and `棋戦`, as pairs of a key and a value, in the order they appear.
+    pub headers: Vec<(String, String)>,
+    /// The initial position and the moves.
+    pub record: GameRecord,
+    /// Comments (`*`), as pairs of the ply of the move they follow, counting from 1, and the text without `*`.
+    ///
+    /// Comments before the first move have ply 0. Every line is a comment of its own,
+    /// so a comment that [`KifFile`] wrapped comes back as several.
+    pub comments: Vec<(usize, String)>,
+    /// The special move that ended the game, such as [`SpecialMove::Resign`] for `投了`.
+    pub special_move: Option<SpecialMove>,
+    /// The variations of the main line, as in [`GameTree::variations`].
+    pub variations: Vec<(usize, GameTree)>,
+}
+
+impl KifRecord {
+    /// Returns the value of the first header named `key`, such as `先手`.
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the main line and the variations as a [`GameTree`].
+    pub fn into_tree(self) -> GameTree {
+        GameTree {
+            main_line: self.record,
+            variations: self.variations,
+        }
+    }
+
+    /// Creates a [`KifFile`] that writes the record back.
+    ///
+    /// The players, `開始日時`, `棋戦`, the comments, the special move and the variations are kept. Other headers are dropped.
+    /// Moves are numbered from the ply of the initial position, which [`parse_kif`] takes from the first move,
+    /// so they are numbered as they were read.
+    pub fn kif_file(&self) -> KifFile<'_> {
+        let mut file = KifFile::new(&self.record).numbering(Numbering::Continue);
+        file.variations = &self.variations;
+        for (key, value) in &self.headers {
+            file = match key.as_str() {
+                "開始日時" => file.start_time(value),
+                "棋戦" => file.event(value),
+                "先手" | "下手" => file.sente(value),
+                "後手" | "上手" => file.gote(value),
+                _ => file,
+            };
+        }
+        for (ply, comment) in &self.comments {
+            file = file.comment(*ply, comment);
+        }
+        if let Some(special_move) = self.special_move {
+            file = file.special_move(special_move);
+        }
+        file
+    }
+}
+
+/// The reason why a KIF document could not be read.
+///
+/// Line numbers start from 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KifError {
+    /// The line is not valid.
+    InvalidLine(usize),
+    /// The move on the line cannot be made.
+    IllegalMove(usize),
+    /// The input is neither UTF-8 nor Shift_JIS. Only [`parse_kif_bytes`] returns this.
+    InvalidEncoding,
+    /// The document has more moves or longer headers than the [`ReadLimits`] allow.
+    TooLarge,
+}
+
+impl core::fmt::Display for KifError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KifError::InvalidLine(line) => write!(f, "invalid line {}", line),
+            KifError::IllegalMove(line) => write!(f, "illegal move at line {}", line),
+            KifError::InvalidEncoding => f.write_str("neither UTF-8 nor Shift_JIS"),
+            KifError::TooLarge => f.write_str("the document is too large"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for KifError {}
+
+/// Reads a KIF document, as written by [`KifFile`].
+///
+/// The initial position is read from a board diagram if there is one, and otherwise from `手合割`,
+/// which must be `平手` or a standard handicap (see [`detect_handicap`](crate::detect_handicap)).
+/// Without either, the game starts from the initial position.
+/// Moves may be numbered from a ply other than 1, as [`KifFile::numbering`] writes them with [`Numbering::Continue`],
+/// in which case the initial position is given the number of the first move as its ply.
+/// Times after the moves, the last line (`まで…`) and lines starting with `#` are skipped.
+/// Bookmarks (`&`) are read into the bookmarks of [`KifRecord::record`] (see [`GameRecord::add_bookmark`]),
+/// naming the position after the move they follow.
+/// Variations (`変化：N手`) are read into [`KifRecord::variations`], each forking from the last line read that has a move at ply `N`,
+/// as Kifu for Windows writes them, and are put in the order of the moves they replace.
+/// Comments, bookmarks and special moves in variations are skipped.
+/// Header values and comments are unescaped as described in [`KifFile`].
+/// Moves are checked only as far as [`PartialPosition::make_move`] does.
+///
+/// The input is read once from the start to the end without recursion,
+/// and documents over [`ReadLimits::DEFAULT`] are rejected with [`KifError::TooLarge`].
+/// The [`GameTree`] of the variations is dropped, cloned, compared and written by recursing into it,
+/// once for every level of [`ReadLimits::max_variation_depth`], so the stack it takes is bounded by that limit.
+///
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+pub fn parse_kif(s: &str) -> Result<KifRecord, KifError> {
+    parse_kif_with_limits(s, &ReadLimits::DEFAULT)
+}
+
+/// Same as [`parse_kif`], but rejects documents over `limits` instead of [`ReadLimits::DEFAULT`].
+///
+/// The moves of the variations count towards [`ReadLimits::max_moves`], every line of the form `key：value` and every bookmark
+/// towards [`ReadLimits::max_header_bytes`], and variations nested deeper than [`ReadLimits::max_variation_depth`] are rejected.
+///
+/// See [`ReadLimits`] for an example.
+pub fn parse_kif_with_limits(s: &str, limits: &ReadLimits) -> Result<KifRecord, KifError> {
+    let mut move_count = 0;
+    let mut header_bytes = 0;
+    let mut headers = Vec::new();
+    let mut comments = Vec::new();
+    let mut bookmarks = Vec::new();
+    let mut special_move = None;
+    let mut handicap = None;
+    let mut bod = BodReader::new();
+    // Every line read so far, from the main line. Empty until the first move.
+    let mut lines: Vec<KifLine> = Vec::new();
+    // The index of the line being read.
+    let mut current = 0;
+    // The index of the last line read that has a move at each ply, counting from 1.
+    let mut last_line_at: Vec<usize> = Vec::new();
+    let mut next_ply = 1;
+    // The number of the first move, which is the ply of the initial position if it continues a game.
+    let mut first_number = None;
+    for (index, line) in s.lines().enumerate() {
+        let line_number = index + 1;
+        let invalid = KifError::InvalidLine(line_number);
+        if line.trim().is_empty()
+            || line.starts_with('#')
+            || line.starts_with("手数")
+            || line.starts_with("まで")
+        {
+            continue;
+        }
+        if let Some(ply) = line.strip_prefix("変化：") {
+            let ply: usize = ply
+                .trim_end()
+                .strip_suffix('手')
+                .and_then(|ply| ply.parse::<usize>().ok())
+                .and_then(|ply| ply.checked_sub(first_number.unwrap_or(1) - 1))
+                .ok_or(invalid)?;
+            // The variation forks from the last line read that has a move at `ply`.
+            // A variation starting at `ply` is an alternative to its own first move,
+            // so the fork goes to the line that variation forks from.
+            let mut parent = *ply
+                .checked_sub(1)
+                .and_then(|index| last_line_at.get(index))
+                .ok_or(invalid)?;
+            while lines[parent].first == ply {
+                match lines[parent].parent {
+                    Some(grandparent) => parent = grandparent,
+                    None => break,
+                }
+            }
+            let depth = lines[parent].depth + 1;
+            if depth > limits.max_variation_depth {
+                return Err(KifError::TooLarge);
+            }
+            let initial = lines[parent].positions[ply - lines[parent].first].clone();
+            lines.push(KifLine {
+                parent: Some(parent),
+                depth,
+                first: ply,
+                record: GameRecord::new(initial.clone()),
+                positions: alloc::vec![initial],
+            });
+            current = lines.len() - 1;
+            next_ply = ply;
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('&') {
+            header_bytes += name.len();
+            if header_bytes > limits.max_header_bytes {
+                return Err(KifError::TooLarge);
+            }
+            // Bookmarks in variations are not kept.
+            if current == 0 {
+                bookmarks.push((sanitize::unescape_kif(name), next_ply - 1));
+            }
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('*') {
+            // Comments in variations are not kept.
+            if current == 0 {
+                comments.push((next_ply - 1, sanitize::unescape_kif(comment)));
+            }
+            continue;
+        }
+        let trimmed = line.trim_start();
+        let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+        if digits > 0 {
+            let number: usize = trimmed[..digits].parse().map_err(|_| invalid)?;
+            let first = *first_number.get_or_insert(number);
+            if first == 0
+                || first > usize::from(u16::MAX)
+                || number != first + next_ply - 1
+                || (current == 0 && special_move.is_some())
+            {
+                return Err(invalid);
+            }
+            // A move with variations is marked with `+`.
+            let text = trimmed[digits..]
+                .trim_start()
+                .split(' ')
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('+');
+            if let Some(special) = SpecialMove::from_kif(text) {
+                if current == 0 {
+                    special_move = Some(special);
+                }
+                continue;
+            }
+            if move_count >= limits.max_moves {
+                return Err(KifError::TooLarge);
+            }
+            if lines.is_empty() {
+                let initial = initial_position(&bod, handicap.as_ref(), first);
+                lines.push(KifLine {
+                    parent: None,
+                    depth: 0,
+                    first: 1,
+                    record: GameRecord::new(initial.clone()),
+                    positions: alloc::vec![initial],
+                });
+            }
+            let line = &mut lines[current];
+            let mut position = line
+                .positions
+                .last()
+                .expect("a line has the position before its first move")
+                .clone();
+            let mv = read_kif_move(&position, text).ok_or(invalid)?;
+            position
+                .make_move(mv)
+                .ok_or(KifError::IllegalMove(line_number))?;
+            line.record.push(mv);
+            line.positions.push(position);
+            match last_line_at.get_mut(next_ply - 1) {
+                Some(last) => *last = current,
+                None => last_line_at.push(current),
+            }
+            move_count += 1;
+            next_ply += 1;
+            continue;
+        }
+        if bod.read_line(line).ok_or(invalid)? {
+            if !lines.is_empty() {
+                return Err(invalid);
+            }
+            continue;
+        }
+        let (key, value) = line.split_once('：').ok_or(invalid)?;
+        header_bytes += key.len() + value.len();
+        if header_bytes > limits.max_header_bytes {
+            return Err(KifError::TooLarge);
+        }
+        let value = sanitize::unescape_kif(value);
+        if key == "手合割" {
+            handicap = if value == "平手" {
+                Some(PartialPosition::startpos())
+            } else {
+                // An unknown handicap must come with a board diagram.
+                handicap_position(&value)
+            };
+        } else {
+            headers.push((String::from(key), value));
+        }
+    }
+    let mut tree = match assemble(lines) {
+        Some(tree) => tree,
+        None => GameTree::new(GameRecord::new(initial_position(
+            &bod,
+            handicap.as_ref(),
+            first_number.unwrap_or(1),
+        ))),
+    };
+    // A name given again moves to the later position, as `GameRecord::add_bookmark` does.
+    let mut seen = BTreeSet::new();
+    bookmarks.reverse();
+    bookmarks.retain(|(name, _)| seen.insert(name.clone()));
+    bookmarks.reverse();
+    tree.main_line.bookmarks = bookmarks;
+    Ok(KifRecord {
+        headers,
+        record: tree.main_line,
+        variations: tree.variations,
+        comments,
+        special_move,
+    })
+}
+
+/// Returns the position that the moves of a KIF document start from: the board diagram, the handicap or the initial position,
+/// at ply `first`, which must be a valid ply.
+fn initial_position(
+    bod: &BodReader,
+    handicap: Option<&PartialPosition>,
+    first: usize,
+) -> PartialPosition {
+    let mut initial = bod
+        .position()
+        .or(handicap)
+        .cloned()
+        .unwrap_or_else(PartialPosition::startpos);
+    u16::try_from(first)
+        .ok()
+        .and_then(|first| initial.ply_set(first))
+        .expect("the number of the first move is checked to be a ply");
+    initial
+}
+
+/// A line of a KIF document being read by [`parse_kif_with_limits`]: the main line or a variation.
+struct KifLine {
+    /// The index of the line it forks from, or [`None`] for the main line.
+    parent: Option<usize>,
+    /// The number of lines it is nested in, which is 0 for the main line.
+    depth: usize,
+    /// The ply of the first move, counting from 1.
+    first: usize,
+    record: GameRecord,
+    /// The position before every move and after the last,
+    /// so that a variation can fork anywhere without making the moves again.
+    positions: Vec<PartialPosition>,
+}
+
+/// Reads a KIF document in UTF-8 or Shift_JIS (CP932), detecting the encoding.
+///
+/// Input that is valid UTF-8 (with or without a BOM) is read as UTF-8, as `.kifu` files are,
+/// and anything else as Shift_JIS, as `.kif` files usually are. See [`parse_kif`] for what is read.
+///
+/// # Errors
+/// Returns [`KifError::InvalidEncoding`] if the input is in neither encoding, and otherwise the errors of [`parse_kif`].
+#[cfg(feature = "encoding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+pub fn parse_kif_bytes(bytes: &[u8]) -> Result<KifRecord, KifError> {
+    parse_kif_bytes_with_limits(bytes, &ReadLimits::DEFAULT)
+}
+
+/// Same as [`parse_kif_bytes`], but rejects documents over `limits` instead of [`ReadLimits::DEFAULT`].
+#[cfg(feature = "encoding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+pub fn parse_kif_bytes_with_limits(
+    bytes: &[u8],
+    limits: &ReadLimits,
+) -> Result<KifRecord, KifError> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    if let Ok(s) = core::str::from_utf8(bytes) {
+        return parse_kif_with_limits(s, limits);
+    }
+    let s = encoding_rs::SHIFT_JIS
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or(KifError::InvalidEncoding)?;
+    parse_kif_with_limits(&s, limits)
+}
+
+/// Assembles the lines read by [`parse_kif_with_limits`] into a [`GameTree`], or returns [`None`] if there are none.
+///
+/// The variations of every line are put in the order of the moves they replace, keeping the order of the file for the same move.
+/// Every line comes after the line it forks from, so the lines are assembled from the last one without recursion.
+fn assemble(mut lines: Vec<KifLine>) -> Option<GameTree> {
+    let mut variations: Vec<Vec<(usize, GameTree)>> = lines.iter().map(|_| Vec::new()).collect();
+    while let Some(line) = lines.pop() {
+        // The variations were added from the last one.
+        let mut own = variations.pop().expect("one list for every line");
+        own.reverse();
+        own.sort_by_key(|&(index, _)| index);
+        let tree = GameTree {
+            main_line: line.record,
+            variations: own,
+        };
+        match line.parent {
+            Some(parent) => variations[parent].push((line.first - lines[parent].first, tree)),
+            None => return Some(tree),
+        }
+    }
+    None
+}
+
+/// The names of pieces in KIF moves, including the ones that other writers use.
+static KIF_PIECE_NAMES: [(&str, PieceKind); 19] = [
+    ("玉", PieceKind::King),
+    ("王", PieceKind::King),
+    ("飛", PieceKind::Rook),
+    ("角", PieceKind::Bishop),
+    ("金", PieceKind::Gold),
+    ("銀", PieceKind::Silver),
+    ("桂", PieceKind::Knight),
+    ("香", PieceKind::Lance),
+    ("歩", PieceKind::Pawn),
+    ("竜", PieceKind::ProRook),
+    ("龍", PieceKind::ProRook),
+    ("馬", PieceKind::ProBishop),
+    ("成銀", PieceKind::ProSilver),
+    ("全", PieceKind::ProSilver),
+    ("成桂", PieceKind::ProKnight),
+    ("圭", PieceKind::ProKnight),
+    ("成香", PieceKind::ProLance),
+    ("杏", PieceKind::ProLance),
+    ("と", PieceKind::ProPawn),
+];
+
+/// Reads a move written by [`display_single_move_kif`], such as `７六歩(77)` or `同　銀(31)`.
+fn read_kif_move(position: &PartialPosition, s: &str) -> Option<Move> {
+    let side = position.side_to_move();
+    let (to, rest) = if let Some(rest) = s.strip_prefix('同') {
+        (position.last_move()?.to(), rest.trim_start_matches('　'))
+    } else {
+        let mut chars = s.chars();
+        let (file, rank) = (chars.next()?, chars.next()?);
+        let file = SANYOU_SUJI.iter().position(|&c| c == file)?;
+        let rank = KANSUJI.iter().position(|&c| c == rank)?;
+        (Square::new(file as u8 + 1, rank as u8 + 1)?, chars.as_str())
+    };
+    let (piece_kind, rest) = KIF_PIECE_NAMES
+        .iter()
+        .find_map(|&(name, piece_kind)| rest.strip_prefix(name).map(|rest| (piece_kind, rest)))?;
+    if rest == "打" {
+        return Some(Move::Drop {
+            piece: Piece::new(piece_kind, side),
+            to,
+        });
+    }
+    let (promote, rest) = if let Some(rest) = rest.strip_prefix("不成") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix('成') {
+        (true, rest)
+    } else {
+        (false, rest)
+    };
+    let from = rest.strip_prefix('(')?.strip_suffix(')')?.as_bytes();
+    if from.len() != 2 {
+        return None;
+    }
+    let from = Square::new(from[0].checked_sub(b'0')?, from[1].checked_sub(b'0')?)?;
+    if position.piece_at(from)? != Piece::new(piece_kind, side) {
+        return None;
+    }
+    Some(Move::Normal { from, to, promote })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Color, Piece, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn display_single_move_kif_works() {
+        let mut pos = PartialPosition::from_usi("sfen 4k4/9/4p4/9/4P4/9/9/9/4K4 b S 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("５四歩(55)".to_string())
+        );
+        pos.make_move(mv).unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_5C,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("同　歩(53)".to_string())
+        );
+        pos.make_move(mv).unwrap();
+
+        // Drops are always written with 打.
+        let mv = Move::Drop {
+            piece: Piece::B_S,
+            to: Square::SQ_4C,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("４三銀打".to_string())
+        );
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_kif(&pos, mv), None);
+    }
+
+    #[test]
+    fn kif_file_works() {
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w g 1 moves G*5b 5i4h").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let kif = KifFile::new(&record)
+            .start_time("2023/01/01 10:00:00")
+            .event("詰将棋")
+            .render()
+            .unwrap();
+        let expected = "\
+開始日時：2023/01/01 10:00:00
+棋戦：詰将棋
+後手の持駒：金　
+  ９ ８ ７ ６ ５ ４ ３ ２ １
++---------------------------+
+| ・ ・ ・ ・v玉 ・ ・ ・ ・|一
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|二
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|三
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|四
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|五
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|六
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|七
+| ・ ・ ・ ・ ・ ・ ・ ・ ・|八
+| ・ ・ ・ ・ 玉 ・ ・ ・ ・|九
++---------------------------+
+先手の持駒：なし
+後手番
+手数----指手---------消費時間--
+   1 ５二金打
+   2 ４八玉(59)
+まで2手
+";
+        assert_eq!(kif, expected);
+
+        // Kings follow the same glyphs in the diagram and in the moves.
+        let kif = KifFile::new(&record)
+            .kings(KingGlyphs::BlackOu)
+            .render()
+            .unwrap();
+        assert!(kif.contains("| ・ ・ ・ ・v玉 ・ ・ ・ ・|一\n"));
+        assert!(kif.contains("| ・ ・ ・ ・ 王 ・ ・ ・ ・|九\n"));
+        assert!(kif.contains("   2 ４八王(59)\n"));
+
+        // The handicap replaces the board diagram.
+        let kif = KifFile::new(&record).handicap("その他").render().unwrap();
+        assert!(kif.starts_with("手合割：その他\n手数"));
+
+        // Sides are called 下手 and 上手 in handicap games, including detected ones.
+        let pos = Position::from_usi(
+            "sfen lnsgkgsnl/7b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1 moves 3c3d",
+        )
+        .unwrap();
+        let handicap_record =
+            GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let kif = KifFile::new(&handicap_record)
+            .sente("Alice")
+            .gote("Bob")
+            .special_move(SpecialMove::Resign)
+            .outcome(Outcome::Win(Color::White))
+            .render()
+            .unwrap();
+        let expected = "\
+手合割：飛車落ち
+下手：Alice
+上手：Bob
+手数----指手---------消費時間--
+   1 ３四歩(33)
+   2 投了
+まで1手で上手の勝ち
+";
+        assert_eq!(kif, expected);
+
+        let kif = KifFile::new(&record)
+            .outcome(Outcome::Win(Color::Black))
+            .render()
+            .unwrap();
+        assert!(kif.ends_with("まで2手で先手の勝ち\n"));
+        let kif = KifFile::new(&record)
+            .handicap("香落ち")
+            .outcome(Outcome::Win(Color::Black))
+            .render()
+            .unwrap();
+        assert!(kif.ends_with("まで2手で下手の勝ち\n"));
+
+        // An invalid move stops the document.
+        let mut record = record;
+        record.push(Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        });
+        assert_eq!(KifFile::new(&record).render(), None);
+    }
+
+    #[test]
+    fn kif_file_anonymize() {
+        let record = GameRecord::new(PartialPosition::startpos());
+        let kif = KifFile::new(&record)
+            .event("taro@example.com 主催")
+            .sente("taro")
+            .gote("hanako")
+            .anonymize()
+            .render()
+            .unwrap();
+        assert_eq!(
+            kif,
+            "手合割：平手\n先手：先手\n後手：後手\n手数----指手---------消費時間--\nまで0手\n",
+        );
+    }
+
+    #[test]
+    fn kif_file_mark_repetitions() {
+        let pos = Position::from_usi(
+            "startpos moves 2h3h 8b7b 3h2h 7b8b 2h3h 8b7b 3h2h 7b8b 2h3h 8b7b 3h2h 7b8b",
+        )
+        .unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let kif = KifFile::new(&record).mark_repetitions().render().unwrap();
+        let expected = "\
+手合割：平手
+手数----指手---------消費時間--
+   1 ３八飛(28)
+   2 ７二飛(82)
+   3 ２八飛(38)
+   4 ８二飛(72)
+*同一局面2回目
+   5 ３八飛(28)
+*同一局面2回目
+   6 ７二飛(82)
+*同一局面2回目
+   7 ２八飛(38)
+*同一局面2回目
+   8 ８二飛(72)
+*同一局面3回目
+   9 ３八飛(28)
+*同一局面3回目
+  10 ７二飛(82)
+*同一局面3回目
+  11 ２八飛(38)
+*同一局面3回目
+  12 ８二飛(72)
+*同一局面4回目（千日手）
+まで12手
+";
+        assert_eq!(kif, expected);
+    }
+
+    #[test]
+    fn parse_kif_round_trip() {
+        let usis = [
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e",
+            "sfen lnsgkgsnl/1r7/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1 moves 3c3d 7g7f",
+            "sfen 4k4/2s1s4/9/9/9/9/9/4+S1+S2/4K4 w rbgsnlp 1 moves 7b6c 5h5g N*5c",
+        ];
+        for usi in usis {
+            let pos = Position::from_usi(usi).unwrap();
+            let record =
+                GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+            let file = KifFile::new(&record)
+                .sente("先手さん")
+                .event("練習対局")
+                .comment(0, "開始")
+                .comment(2, "二手目")
+                .comment(2, "もう一つ")
+                .times(&[1, 2, 3])
+                .special_move(SpecialMove::Resign)
+                .outcome(Outcome::Win(Color::White));
+            let text = file.render().unwrap();
+            let parsed = parse_kif(&text).unwrap();
+            assert_eq!(parsed.record, record, "{}", text);
+            assert_eq!(parsed.header("棋戦"), Some("練習対局"));
+            assert_eq!(parsed.comments.len(), 3);
+            assert_eq!(parsed.special_move, Some(SpecialMove::Resign));
+            let rewritten = parsed.kif_file().outcome(Outcome::Win(Color::White));
+            let rewritten = rewritten.times(&[1, 2, 3]).render().unwrap();
+            assert_eq!(rewritten, text);
+        }
+    }
+
+    #[test]
+    fn bookmarks_round_trip() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+        let mut record =
+            GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        record.add_bookmark("開始", 0);
+        record.add_bookmark("中盤の急所\n#", 4);
+        let text = KifFile::new(&record).comment(4, "同銀").render().unwrap();
+        assert!(text.contains("\n&開始\n   1 "), "{}", text);
+        assert!(
+            text.ends_with("*同銀\n&中盤の急所\\n#\nまで4手\n"),
+            "{}",
+            text
+        );
+        let parsed = parse_kif(&text).unwrap();
+        assert_eq!(parsed.record, record);
+        assert_eq!(parsed.record.bookmark("中盤の急所\n#"), Some(4));
+        assert_eq!(parsed.kif_file().render().unwrap(), text);
+    }
+
+    #[test]
+    fn many_bookmarks() {
+        let count = 10000;
+        let mut text = String::from("   1 ７六歩(77)\n");
+        for i in 0..count {
+            text.push_str(&alloc::format!("&a{}\n", i));
+        }
+        // Given again, the first name moves after the others.
+        text.push_str("   2 ３四歩(33)\n&a0\n");
+        let parsed = parse_kif(&text).unwrap();
+        assert_eq!(parsed.record.bookmarks().len(), count);
+        assert_eq!(parsed.record.bookmarks().next(), Some(("a1", 1)));
+        assert_eq!(parsed.record.bookmarks().last(), Some(("a0", 2)));
+
+        let mut limits = ReadLimits::DEFAULT;
+        limits.max_header_bytes = text.len() / 2;
+        assert_eq!(
+            parse_kif_with_limits(&text, &limits),
+            Err(KifError::TooLarge)
+        );
+    }
+
+    #[test]
+    fn longest_game_round_trip() {
+        // Kings walking back and forth, as long as the ply of `PartialPosition` can count.
+        let cycle = [
+            (Square::SQ_5I, Square::SQ_5H),
+            (Square::SQ_5A, Square::SQ_5B),
+            (Square::SQ_5H, Square::SQ_5I),
+            (Square::SQ_5B, Square::SQ_5A),
+        ];
+        let moves = cycle.iter().cycle().take(u16::MAX as usize - 1);
+        let moves = moves.map(|&(from, to)| Move::Normal {
+            from,
+            to,
+            promote: false,
+        });
+        let record = GameRecord::with_moves(PartialPosition::startpos(), moves);
+        let text = KifFile::new(&record)
+            .times(&[1; 10000])
+            .special_move(SpecialMove::Resign)
+            .render()
+            .unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines[2], "    1 ５八玉(59)     ( 0:01/00:00:01)");
+        assert_eq!(lines[10000], " 9999 ５九玉(58)     ( 0:01/01:23:20)");
+        assert_eq!(lines[10001], "10000 ５一玉(52)     ( 0:01/01:23:20)");
+        assert_eq!(lines[10002], "10001 ５八玉(59)");
+        assert_eq!(lines[65535], "65534 ５二玉(51)");
+        assert_eq!(lines[65536], "65535 投了");
+        let parsed = parse_kif(&text).unwrap();
+        assert_eq!(parsed.record, record);
+
+        // No more moves can be made after them.
+        let mut record = record;
+        record.push(Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_5I,
+            promote: false,
+        });
+        assert_eq!(KifFile::new(&record).render(), None);
+    }
+
+    #[test]
+    fn continued_numbering_round_trip() {
+        let pos =
+            Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 31 moves G*5b 5a5b 5i5h").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut tree = GameTree::new(record);
+        let mv = Move::Normal {
+            from: Square::SQ_5A,
+            to: Square::SQ_4A,
+            promote: false,
+        };
+        tree.add_variation(1, [mv]).unwrap();
+        let restarted = KifFile::from_tree(&tree).render().unwrap();
+        assert!(
+            restarted.contains("\n   1 ５二金打\n   2 同　玉(51)+\n"),
+            "{}",
+            restarted
+        );
+        let file = KifFile::from_tree(&tree)
+            .numbering(Numbering::Continue)
+            .comment(2, "同玉")
+            .special_move(SpecialMove::Resign);
+        let text = file.render().unwrap();
+        let expected = "\
+手数----指手---------消費時間--
+  31 ５二金打
+  32 同　玉(51)+
+*同玉
+  33 ５八玉(59)
+  34 投了
+まで33手
+
+変化：32手
+  32 ４一玉(51)
+";
+        assert!(text.ends_with(expected), "{}", text);
+        let parsed = parse_kif(&text).unwrap();
+        assert_eq!(parsed.record.initial().ply(), 31);
+        assert_eq!(parsed.comments, [(2, "同玉".to_string())]);
+        assert_eq!(parsed.kif_file().render().unwrap(), text);
+        assert_eq!(parsed.into_tree(), tree);
+
+        // The restarted numbering is read back from ply 1.
+        let parsed = parse_kif(&restarted).unwrap();
+        assert_eq!(parsed.record.initial().ply(), 1);
+        assert_eq!(parsed.record.len(), 3);
+        assert_eq!(
+            parse_kif("  31 ７六歩(77)\n  33 ３四歩(33)\n"),
+            Err(KifError::InvalidLine(2))
+        );
+        assert_eq!(
+            parse_kif("   0 ７六歩(77)\n"),
+            Err(KifError::InvalidLine(1))
+        );
+    }
+
+    #[test]
+    fn variations_round_trip() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 2g2f 8c8d 2f2e").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut tree = GameTree::new(record);
+        let line = |usi: &str| {
+            let pos = Position::from_usi(usi).unwrap();
+            pos.moves()[pos.moves().len() - 2..].to_vec()
+        };
+        // Forks at ply 2 with a fork of its own at ply 3, and two forks at ply 3.
+        let b = tree
+            .add_variation(1, line("startpos moves 7g7f 8c8d 2g2f"))
+            .unwrap();
+        b.add_variation(
+            1,
+            line("startpos moves 7g7f 8c8d 6g6f 3c3d")
+                .into_iter()
+                .take(1),
+        )
+        .unwrap();
+        tree.add_variation(2, line("startpos moves 7g7f 3c3d 6g6f 8c8d"))
+            .unwrap();
+        tree.add_variation(2, line("startpos moves 7g7f 3c3d 5g5f 8c8d"))
+            .unwrap();
+        let text = KifFile::from_tree(&tree).render().unwrap();
+        let expected = "\
+手合割：平手
+手数----指手---------消費時間--
+   1 ７六歩(77)
+   2 ３四歩(33)+
+   3 ２六歩(27)+
+   4 ８四歩(83)
+   5 ２五歩(26)
+まで5手
+
+変化：3手
+   3 ６六歩(67)
+   4 ８四歩(83)
+
+変化：3手
+   3 ５六歩(57)
+   4 ８四歩(83)
+
+変化：2手
+   2 ８四歩(83)
+   3 ２六歩(27)+
+
+変化：3手
+   3 ６六歩(67)
+";
+        assert_eq!(text, expected);
+        let parsed = parse_kif(&text).unwrap();
+        assert_eq!(parsed.kif_file().render().unwrap(), text);
+        assert_eq!(parsed.into_tree(), tree);
+    }
+
+    #[test]
+    fn parse_kif_errors() {
+        assert_eq!(
+            parse_kif("   1 ７六歩(77)\n   3 ３四歩(33)\n"),
+            Err(KifError::InvalidLine(2))
+        );
+        assert_eq!(
+            parse_kif("   1 ７六歩(78)\n"),
+            Err(KifError::InvalidLine(1))
+        );
+        assert_eq!(parse_kif("   1 ５五角打\n"), Err(KifError::IllegalMove(1)));
+        assert_eq!(parse_kif("先手\n"), Err(KifError::InvalidLine(1)));
+        assert_eq!(
+            parse_kif("   1 投了\n   2 ７六歩(77)\n"),
+            Err(KifError::InvalidLine(2))
+        );
+        // A variation needs a move to fork from.
+        assert_eq!(
+            parse_kif("   1 ７六歩(77)\n\n変化：2手\n   2 ３四歩(33)\n"),
+            Err(KifError::InvalidLine(3))
+        );
+        assert_eq!(
+            parse_kif("   1 ７六歩(77)\n\n変化：1手\n   2 ２六歩(27)\n"),
+            Err(KifError::InvalidLine(4))
+        );
+    }
+
+    #[test]
+    fn parse_kif_with_limits_works() {
+        let text = "先手：羽生\n   1 ７六歩(77)\n   2 ３四歩(33)\n\n変化：2手\n   2 ８四歩(83)\n";
+        let mut limits = ReadLimits::DEFAULT;
+        limits.max_moves = 3;
+        limits.max_header_bytes = "先手羽生".len();
+        assert_eq!(parse_kif_with_limits(text, &limits), parse_kif(text));
+        limits.max_moves = 2;
+        assert_eq!(
+            parse_kif_with_limits(text, &limits),
+            Err(KifError::TooLarge)
+        );
+        limits.max_moves = 3;
+        limits.max_header_bytes -= 1;
+        assert_eq!(
+            parse_kif_with_limits(text, &limits),
+            Err(KifError::TooLarge)
+        );
+    }
+
+    // Variations nested as deep as the limit allows are read, written and dropped, and deeper ones are rejected.
+    #[test]
+    fn parse_kif_deep_variations() {
+        // The kings walk back and forth.
+        let moves = ["５八玉(59)", "５二玉(51)", "５九玉(58)", "５一玉(52)"];
+        let line = |ply: usize| alloc::format!("{:>5} {}\n", ply, moves[(ply - 1) % 4]);
+        // Each variation forks from the one before it, one move later,
+        // and the main line takes the rest of the moves.
+        let document = |depth: usize| {
+            let mut text = String::new();
+            for ply in 1..=ReadLimits::DEFAULT.max_moves - 2 * depth {
+                text.push_str(&line(ply));
+            }
+            for ply in 2..=depth + 1 {
+                text.push_str(&alloc::format!("\n変化：{}手\n", ply));
+                text.push_str(&line(ply));
+                text.push_str(&line(ply + 1));
+            }
+            text
+        };
+        let depth = ReadLimits::DEFAULT.max_variation_depth;
+        let parsed = parse_kif(&document(depth)).unwrap();
+        let mut tree = &parsed.variations[0].1;
+        let mut found = 1;
+        while let [(index, variation)] = tree.variations() {
+            assert_eq!(*index, 1);
+            tree = variation;
+            found += 1;
+        }
+        assert_eq!(found, depth);
+        assert_eq!(tree.main_line().len(), 2);
+        let text = parsed.kif_file().render().unwrap();
+        let reparsed = parse_kif(&text).unwrap();
+        assert_eq!(reparsed, parsed);
+        assert_eq!(reparsed.into_tree(), parsed.clone().into_tree());
+        drop(parsed);
+
+        let deepest = (ReadLimits::DEFAULT.max_moves - 2) / 2;
+        assert_eq!(parse_kif(&document(depth + 1)), Err(KifError::TooLarge));
+        assert_eq!(parse_kif(&document(deepest)), Err(KifError::TooLarge));
+        let mut limits = ReadLimits::DEFAULT;
+        limits.max_variation_depth = depth + 1;
+        assert!(parse_kif_with_limits(&document(depth + 1), &limits).is_ok());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn parse_kif_bytes_detects_encoding() {
+        let shift_jis = b"   1 \x82\x56\x98\x5a\x95\xe0(77)\n";
+        let utf8 = "\u{feff}   1 ７六歩(77)\n".as_bytes();
+        for bytes in [&shift_jis[..], utf8] {
+            let parsed = parse_kif_bytes(bytes).unwrap();
+            assert_eq!(parsed.record.len(), 1);
+        }
+        assert_eq!(
+            parse_kif_bytes(b"   1 \x82\x56\xff(77)\n"),
+            Err(KifError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn kif_file_times() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        // The last move has no time.
+        let kif = KifFile::new(&record)
+            .times(&[0, 3599, 61, 7200])
+            .special_move(SpecialMove::Resign)
+            .render()
+            .unwrap();
+        let expected = "\
+手合割：平手
+手数----指手---------消費時間--
+   1 ７六歩(77)     ( 0:00/00:00:00)
+   2 ３四歩(33)     (59:59/00:59:59)
+   3 ２二角成(88)   ( 1:01/00:01:01)
+   4 同　銀(31)     (120:00/02:59:59)
+   5 ４五角打
+   6 投了
+まで5手
+";
+        assert_eq!(kif, expected);
+    }
+
+    #[test]
+    fn kif_file_comments() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let kif = KifFile::new(&record)
+            .comment(1, "角道を開ける手")
+            .comment(0, "平手の対局")
+            .comment(1, "二つ目")
+            .comment_width(8)
+            .render()
+            .unwrap();
+        let expected = "\
+手合割：平手
+手数----指手---------消費時間--
+*平手の
+*対局
+   1 ７六歩(77)
+*角道を
+*開ける
+*手
+*二つ目
+   2 ３四歩(33)
+まで2手
+";
+        assert_eq!(kif, expected);
+    }
+
+    // Text from users must not be read back as moves or headers.
+    #[test]
+    fn kif_file_escapes_user_text() {
+        let pos = Position::from_usi("startpos moves 7g7f").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let sente = "羽生\n   1 ７六歩(77)";
+        let gote = "藤井\r後手：偽者\\n";
+        let event = "棋戦\u{2028}開始日時：2020";
+        let comment = "C:\\\r   2 ３四歩(33)";
+        let kif = KifFile::new(&record)
+            .sente(sente)
+            .gote(gote)
+            .event(event)
+            .comment(1, comment)
+            .comment_width(6)
+            .render()
+            .unwrap();
+        let expected = "\
+棋戦：棋戦\\u{2028}開始日時：2020
+手合割：平手
+先手：羽生\\n   1 ７六歩(77)
+後手：藤井\\r後手：偽者\\\\n
+手数----指手---------消費時間--
+   1 ７六歩(77)
+*C:\\\\
+*\\r   \n\
+*2 ３
+*四歩(
+*33)
+まで1手
+";
+        assert_eq!(kif, expected);
+        let parsed = parse_kif(&kif).unwrap();
+        assert_eq!(parsed.header("先手"), Some(sente));
+        assert_eq!(parsed.header("後手"), Some(gote));
+        assert_eq!(parsed.header("棋戦"), Some(event));
+        assert_eq!(parsed.header("開始日時"), None);
+        assert_eq!(parsed.record, record);
+        let comments: Vec<_> = parsed.comments.iter().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(comments.concat(), comment);
+
+        // Every line of a comment is kept, even an empty one, and `\r` is escaped wherever it is.
+        for comment in ["", "a\n", "a\r\nb"] {
+            let kif = KifFile::new(&record).comment(1, comment).render().unwrap();
+            let parsed = parse_kif(&kif).unwrap();
+            let comments: Vec<_> = parsed.comments.iter().map(|(_, c)| c.as_str()).collect();
+            assert_eq!(comments.join("\n"), comment, "{}", kif);
+        }
+    }
+
+    #[test]
+    fn display_single_move_kif_promotion() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/6N2/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3D,
+            to: Square::SQ_2B,
+            promote: true,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("２二桂成(34)".to_string())
+        );
+        let mv = Move::Normal {
+            from: Square::SQ_3D,
+            to: Square::SQ_4B,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("４二桂不成(34)".to_string())
+        );
+
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K2+S1 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_2I,
+            to: Square::SQ_2H,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_kif(&pos, mv),
+            Some("２八成銀(29)".to_string())
+        );
+    }
+}
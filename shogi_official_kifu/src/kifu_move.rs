@@ -0,0 +1,445 @@
+use core::fmt::Write;
+use shogi_core::{Bitboard, Color, Move, PartialPosition, Piece, PieceKind, Square};
+
+use crate::{
+    disambiguation, is_promotable_piece, is_promotion_forced, normal_candidates, western, yomi,
+    Coordinates, Modifier, Notation, Style, KANSUJI, SANYOU_SUJI,
+};
+
+/// The components of a move in the notation, as found by [`analyze_single_move`].
+///
+/// Every writer of this crate spells these components, so which suffixes a move needs is decided in one place.
+/// GUIs can lay out the components themselves, or write the move with [`Display`](core::fmt::Display),
+/// which gives the official notation, or with [`KifuMove::display`] in any [`Style`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Color, Move, PartialPosition, PieceKind, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{analyze_single_move, Modifier, Style};
+/// // Two golds can move to 5B: ５二金右 and ５二金左
+/// let pos = PartialPosition::from_usi("sfen 4k4/3G1G3/9/9/9/9/9/9/4K4 b - 1").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_4B,
+///     to: Square::SQ_5B,
+///     promote: false,
+/// };
+/// let kifu_move = analyze_single_move(&pos, mv).unwrap();
+/// assert_eq!(kifu_move.side(), Color::Black);
+/// assert_eq!(kifu_move.to(), Square::SQ_5B);
+/// assert_eq!(kifu_move.piece_kind(), PieceKind::Gold);
+/// assert_eq!(kifu_move.relative(), Some(Modifier::Right));
+/// assert_eq!(kifu_move.movement(), None);
+/// assert!(!kifu_move.same() && !kifu_move.drop());
+/// assert_eq!(kifu_move.promotion(), None);
+/// assert_eq!(kifu_move.to_string(), "▲５２金右");
+/// assert_eq!(kifu_move.display(&Style::TRADITIONAL).to_string(), "▲５二金右");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KifuMove {
+    pub(crate) side: Color,
+    /// The origin of a normal move, or [`None`] for a drop.
+    pub(crate) from: Option<Square>,
+    pub(crate) to: Square,
+    /// Whether a piece is captured on `to`.
+    pub(crate) capture: bool,
+    /// Whether `to` is the destination of the last move, in which case `同` is written.
+    pub(crate) same: bool,
+    pub(crate) piece_kind: PieceKind,
+    /// `左`, `右` or `直`.
+    pub(crate) relative: Option<char>,
+    /// `上`, `引` or `寄`.
+    pub(crate) movement: Option<char>,
+    /// Whether `打` is needed.
+    pub(crate) drop: bool,
+    /// `Some(true)` for `成`, `Some(false)` for `不成`, and `None` if the move could not promote.
+    pub(crate) promotion: Option<bool>,
+}
+
+/// The reason why a move could not be written.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_official_kifu::{try_display_single_move, KifuError};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_5E,
+///     to: Square::SQ_5D,
+///     promote: false,
+/// };
+/// assert_eq!(try_display_single_move(&pos, mv), Err(KifuError::NoPieceAtOrigin));
+/// let mv = Move::Normal {
+///     from: Square::SQ_3C,
+///     to: Square::SQ_3D,
+///     promote: false,
+/// };
+/// assert_eq!(try_display_single_move(&pos, mv), Err(KifuError::WrongSide));
+/// let mv = Move::Drop {
+///     piece: Piece::B_P,
+///     to: Square::SQ_7G,
+/// };
+/// assert_eq!(try_display_single_move(&pos, mv), Err(KifuError::InvalidDrop));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KifuError {
+    /// No piece is on the origin of the move.
+    NoPieceAtOrigin,
+    /// The piece that moves does not belong to the side to move.
+    WrongSide,
+    /// The piece on the origin cannot move to the destination.
+    Unreachable,
+    /// The suffixes of the notation cannot tell the move from the other moves to the same destination.
+    AmbiguityUnresolvable,
+    /// The piece cannot be dropped: it is a king or a promoted piece, or the destination is occupied.
+    ///
+    /// The color of the piece is not looked at, because USI writes drops without it (`P*5e`).
+    InvalidDrop,
+}
+
+impl core::fmt::Display for KifuError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            KifuError::NoPieceAtOrigin => "no piece at the origin",
+            KifuError::WrongSide => "the piece does not belong to the side to move",
+            KifuError::Unreachable => "the piece cannot move to the destination",
+            KifuError::AmbiguityUnresolvable => "the move cannot be told from the others",
+            KifuError::InvalidDrop => "the piece cannot be dropped",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for KifuError {}
+
+/// Finds the components of `mv` in the notation, or why `mv` cannot be written in `position`.
+///
+/// See [`KifuMove`] for an example.
+pub fn analyze_single_move(position: &PartialPosition, mv: Move) -> Result<KifuMove, KifuError> {
+    analyze(position, mv)
+}
+
+/// Finds the components of `mv`, or why `mv` cannot be written in `position`.
+pub(crate) fn analyze(position: &PartialPosition, mv: Move) -> Result<KifuMove, KifuError> {
+    analyze_with(position, mv, |piece, to| {
+        normal_candidates(position, piece, to)
+    })
+}
+
+/// Same as [`analyze`], but finds the candidates with `find_candidates` instead of [`normal_candidates`].
+pub(crate) fn analyze_with<F: Fn(Piece, Square) -> Bitboard>(
+    position: &PartialPosition,
+    mv: Move,
+    find_candidates: F,
+) -> Result<KifuMove, KifuError> {
+    let side = position.side_to_move();
+    match mv {
+        Move::Normal { from, to, promote } => {
+            let p = position.piece_at(from).ok_or(KifuError::NoPieceAtOrigin)?;
+            if p.color() != side {
+                return Err(KifuError::WrongSide);
+            }
+            let same = position.last_move().map(|last_move| last_move.to()) == Some(to);
+            let candidates = find_candidates(p, to);
+            if !candidates.contains(from) {
+                return Err(KifuError::Unreachable);
+            }
+            let (relative, movement) = disambiguation::run(position, from, to, candidates)
+                .ok_or(KifuError::AmbiguityUnresolvable)?;
+            let could_promote = is_promotable_piece(p.piece_kind())
+                && (from.relative_rank(side) <= 3 || to.relative_rank(side) <= 3);
+            let promotion = if promote {
+                Some(true)
+            } else if could_promote {
+                Some(false)
+            } else {
+                None
+            };
+            Ok(KifuMove {
+                side,
+                from: Some(from),
+                to,
+                capture: position.piece_at(to).is_some(),
+                same,
+                piece_kind: p.piece_kind(),
+                relative,
+                movement,
+                drop: false,
+                promotion,
+            })
+        }
+        Move::Drop { to, piece } => {
+            let piece_kind = piece.piece_kind();
+            if piece_kind == PieceKind::King
+                || piece_kind.unpromote().is_some()
+                || position.piece_at(to).is_some()
+            {
+                return Err(KifuError::InvalidDrop);
+            }
+            let p = Piece::new(piece_kind, side);
+            Ok(KifuMove {
+                side,
+                from: None,
+                to,
+                capture: false,
+                same: false,
+                piece_kind,
+                relative: None,
+                movement: None,
+                drop: !find_candidates(p, to).is_empty(),
+                promotion: None,
+            })
+        }
+    }
+}
+
+impl KifuMove {
+    /// Returns the side that makes the move.
+    pub fn side(&self) -> Color {
+        self.side
+    }
+
+    /// Returns the origin of a normal move, or [`None`] for a drop.
+    pub fn from(&self) -> Option<Square> {
+        self.from
+    }
+
+    /// Returns the destination.
+    pub fn to(&self) -> Square {
+        self.to
+    }
+
+    /// Returns whether a piece is captured on the destination.
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    /// Returns whether the destination is that of the last move, in which case `同` is written.
+    pub fn same(&self) -> bool {
+        self.same
+    }
+
+    /// Returns the kind of the piece before it moves.
+    pub fn piece_kind(&self) -> PieceKind {
+        self.piece_kind
+    }
+
+    /// Returns the suffix for the relative position of the piece that the notation needs,
+    /// [`Modifier::Left`] (`左`), [`Modifier::Right`] (`右`) or [`Modifier::Straight`] (`直`).
+    pub fn relative(&self) -> Option<Modifier> {
+        self.relative.and_then(Modifier::from_char)
+    }
+
+    /// Returns the suffix for the movement of the piece that the notation needs,
+    /// [`Modifier::Up`] (`上`), [`Modifier::Down`] (`引`) or [`Modifier::Sideways`] (`寄`).
+    pub fn movement(&self) -> Option<Modifier> {
+        self.movement.and_then(Modifier::from_char)
+    }
+
+    /// Returns whether `打` is needed, that is, whether the move is a drop and a piece on the board can also move to the destination.
+    pub fn drop(&self) -> bool {
+        self.drop
+    }
+
+    /// Returns `Some(true)` for `成`, `Some(false)` for `不成`, and [`None`] if the move could not promote.
+    pub fn promotion(&self) -> Option<bool> {
+        self.promotion
+    }
+
+    /// Returns a value that writes `self` in `style` with [`Display`](core::fmt::Display).
+    pub fn display<'a>(&'a self, style: &'a Style) -> KifuMoveDisplay<'a> {
+        KifuMoveDisplay {
+            kifu_move: self,
+            style,
+        }
+    }
+
+    /// Writes `self` in the notation of `style`.
+    pub(crate) fn write_notation<W: Write>(&self, style: &Style, w: &mut W) -> core::fmt::Result {
+        match style.notation {
+            Notation::Japanese => self.write(style, w),
+            Notation::Western => western::write_western(self, b'a', w),
+            Notation::Hosking => western::write_western(self, b'1', w),
+            Notation::Yomi => self.write(style, &mut yomi::Reading::new(w)),
+        }
+    }
+
+    /// Writes `self` in the Japanese notation, spelling the destination as `style` says.
+    pub(crate) fn write<W: Write>(&self, style: &Style, w: &mut W) -> core::fmt::Result {
+        let (black, white) = style.side_markers.marks();
+        w.write_char(if self.side == Color::Black {
+            black
+        } else {
+            white
+        })?;
+        let piece = style.piece_name(self.piece_kind, self.side, self.side);
+        if self.same && style.write_same {
+            w.write_char('同')?;
+            if style.pad_same && piece.chars().count() == 1 {
+                w.write_char('　')?;
+            }
+        } else {
+            let (files, ranks) = match style.coordinates {
+                Coordinates::Arabic => (&SANYOU_SUJI, &SANYOU_SUJI),
+                Coordinates::KansujiRank => (&SANYOU_SUJI, &KANSUJI),
+                Coordinates::Kansuji => (&KANSUJI, &KANSUJI),
+            };
+            w.write_char(*unsafe { files.get_unchecked(self.to.file() as usize - 1) })?;
+            w.write_char(*unsafe { ranks.get_unchecked(self.to.rank() as usize - 1) })?;
+        }
+        w.write_str(piece)?;
+        let (relative, movement) = match self.from {
+            Some(from) if style.full_disambiguation => disambiguation::complete(
+                self.side,
+                self.piece_kind,
+                from,
+                self.to,
+                (self.relative, self.movement),
+            ),
+            _ => (self.relative, self.movement),
+        };
+        if let Some(relative) = relative {
+            w.write_char(relative)?;
+        }
+        if let Some(movement) = movement {
+            w.write_char(movement)?;
+        }
+        if self.drop || (style.always_mark_drops && self.from.is_none()) {
+            w.write_char('打')?;
+        }
+        match self.promotion {
+            Some(true) => {
+                let forced = is_promotion_forced(Piece::new(self.piece_kind, self.side), self.to);
+                if style.write_forced_promotion || !forced {
+                    w.write_char('成')?;
+                }
+            }
+            Some(false) => w.write_str("不成")?,
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// The official notation, such as `▲７６歩`.
+impl core::fmt::Display for KifuMove {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.write(&Style::OFFICIAL, f)
+    }
+}
+
+/// A [`KifuMove`] written in a [`Style`], as returned by [`KifuMove::display`].
+#[derive(Clone, Copy, Debug)]
+pub struct KifuMoveDisplay<'a> {
+    kifu_move: &'a KifuMove,
+    style: &'a Style,
+}
+
+impl core::fmt::Display for KifuMoveDisplay<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.kifu_move.write_notation(self.style, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{caption, display_single_move, display_single_move_with_style};
+    use shogi_core::{LegalityChecker, Position};
+    use shogi_legality_lite::LiteLegalityChecker;
+    use shogi_usi_parser::FromUsi;
+
+    // Every writer must agree on the structure of a move: only the spelling of the destination may differ.
+    #[test]
+    fn writers_agree_on_suffixes() {
+        let sfens = [
+            "startpos",
+            "sfen 4k4/9/3GGG3/9/9/9/1+P4S1S/+P8/+P+P+P1K1SS1 b - 1",
+            "sfen 4k1S1S/9/6SS1/9/9/9/9/9/4K4 b - 1",
+            "sfen 9/9/9/9/4+R3+R/9/9/9/2k1K4 b - 1",
+            "sfen 4k4/2S1S4/9/9/9/9/9/4+S1+S2/4K4 w RBGSNLP 2",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+",
+        ];
+        for sfen in sfens {
+            let pos = Position::from_usi(sfen).unwrap();
+            let pos = pos.inner();
+            for mv in LiteLegalityChecker.all_legal_moves_partial(pos) {
+                let kifu_move = analyze(pos, mv).unwrap();
+                let mut suffix = alloc::string::String::new();
+                suffix.extend(kifu_move.relative);
+                suffix.extend(kifu_move.movement);
+                assert!(matches!(
+                    kifu_move.relative(),
+                    None | Some(Modifier::Left | Modifier::Right | Modifier::Straight)
+                ));
+                assert!(matches!(
+                    kifu_move.movement(),
+                    None | Some(Modifier::Up | Modifier::Down | Modifier::Sideways)
+                ));
+                assert_eq!(
+                    kifu_move.relative().and_then(Modifier::as_char),
+                    kifu_move.relative
+                );
+                assert_eq!(
+                    kifu_move.movement().and_then(Modifier::as_char),
+                    kifu_move.movement
+                );
+                if kifu_move.drop {
+                    suffix.push('打');
+                }
+                match kifu_move.promotion {
+                    Some(true) => suffix.push('成'),
+                    Some(false) => suffix.push_str("不成"),
+                    None => {}
+                }
+
+                let official = display_single_move(pos, mv).unwrap();
+                assert!(official.ends_with(&suffix), "{} {}", official, suffix);
+                let styled = display_single_move_with_style(pos, mv, &Style::OFFICIAL).unwrap();
+                assert_eq!(styled, official);
+
+                let mut kansuji = alloc::string::String::new();
+                kifu_move.write(&Style::TRADITIONAL, &mut kansuji).unwrap();
+                assert!(kansuji.ends_with(&suffix), "{} {}", kansuji, suffix);
+                assert_eq!(kansuji.chars().count(), official.chars().count());
+                assert_eq!(
+                    caption(pos, mv).unwrap(),
+                    alloc::format!("図は{}まで", kansuji)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn display_agrees_with_writers() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        let kifu_move = analyze_single_move(pos.inner(), mv).unwrap();
+        assert!(kifu_move.same() && kifu_move.capture());
+        for notation in [
+            Notation::Japanese,
+            Notation::Western,
+            Notation::Hosking,
+            Notation::Yomi,
+        ] {
+            let style = Style {
+                notation,
+                ..Style::TRADITIONAL
+            };
+            assert_eq!(
+                Some(kifu_move.display(&style).to_string()),
+                display_single_move_with_style(pos.inner(), mv, &style)
+            );
+        }
+        assert_eq!(
+            Some(kifu_move.to_string()),
+            display_single_move(pos.inner(), mv)
+        );
+    }
+}
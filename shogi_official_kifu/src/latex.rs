@@ -0,0 +1,188 @@
+use core::fmt::Write;
+use shogi_core::{Color, Hand, PartialPosition, PieceKind, Square};
+
+use crate::{
+    bod::{piece_kind_to_bod, write_kansuji_count},
+    display_single_move_write_with_style,
+    record::is_startpos,
+    GameRecord, Style,
+};
+
+/// Writes `record` as LaTeX: a diagram of the initial position unless it is the standard one, followed by the moves.
+///
+/// Returns [`None`] if a move in `record` cannot be made.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{latex, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// assert_eq!(
+///     latex::to_string(&record),
+///     Some("\\sashite{1}{▲７六歩}\n\\sashite{2}{△３四歩}\n".to_string()),
+/// );
+/// ```
+pub fn to_string(record: &GameRecord) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    write(record, &mut ret).expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Writes `record` as LaTeX to a [`Write`].
+///
+/// The diagram is written as [`diagram_write`] does, and every move as `\sashite{ply}{move}` on a line of its own,
+/// with the move in [`Style::TRADITIONAL`].
+///
+/// Returns `Ok(None)` if a move in `record` cannot be made, in which case the lines before it have already been written.
+///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+pub fn write<W: Write>(record: &GameRecord, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+    let mut position = record.initial().clone();
+    if !is_startpos(&position) {
+        diagram_write(&position, w)?;
+    }
+    let mut buffer = alloc::string::String::new();
+    let first_ply = position.ply() as usize;
+    for (index, mv) in record.moves().enumerate() {
+        buffer.clear();
+        let ply = first_ply + index;
+        if display_single_move_write_with_style(&position, mv, &Style::TRADITIONAL, &mut buffer)?
+            .is_none()
+            || position.make_move(mv).is_none()
+        {
+            return Ok(None);
+        }
+        writeln!(w, "\\sashite{{{}}}{{{}}}", ply, buffer)?;
+    }
+    Ok(Some(()))
+}
+
+/// Writes `position` as a LaTeX diagram into a [`String`](alloc::string::String).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::latex;
+/// let pos = PartialPosition::from_usi("sfen 8k/9/9/9/9/9/9/9/8K w 2G3Pp 1").unwrap();
+/// assert_eq!(
+///     latex::diagram(&pos),
+///     "\
+/// \\mochigoma{後手}{歩}
+/// \\banmen{
+/// &&&&&&&&v玉\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&\\\\
+/// &&&&&&&&玉\\\\
+/// }
+/// \\mochigoma{先手}{金二　歩三}
+/// \\teban{後手}
+/// ",
+/// );
+/// ```
+pub fn diagram(position: &PartialPosition) -> alloc::string::String {
+    let mut ret = alloc::string::String::new();
+    diagram_write(position, &mut ret).expect("fmt::Write for String cannot return an error");
+    ret
+}
+
+/// Writes `position` as a LaTeX diagram to a [`Write`].
+///
+/// The board is written in `\banmen{...}` one rank on a line from the first rank, with files from 9 to 1 separated by `&`.
+/// A piece is written in kanji as in board diagrams, preceded by `v` if it belongs to White, and an empty square is left empty.
+/// The pieces in hand are written in `\mochigoma{side}{pieces}` above the board for White and below it for Black,
+/// with `なし` for an empty hand, and `\teban{後手}` is added if White is to move.
+///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+pub fn diagram_write<W: Write>(position: &PartialPosition, w: &mut W) -> core::fmt::Result {
+    write_mochigoma("後手", position.hand_of_a_player(Color::White), w)?;
+    w.write_str("\\banmen{\n")?;
+    for rank in 1..=9 {
+        for file in (1..=9).rev() {
+            if file != 9 {
+                w.write_char('&')?;
+            }
+            // Safety: 1 <= file, rank <= 9
+            let square = unsafe { Square::new(file, rank).unwrap_unchecked() };
+            if let Some(piece) = position.piece_at(square) {
+                if piece.color() == Color::White {
+                    w.write_char('v')?;
+                }
+                w.write_char(piece_kind_to_bod(piece.piece_kind()))?;
+            }
+        }
+        w.write_str("\\\\\n")?;
+    }
+    w.write_str("}\n")?;
+    write_mochigoma("先手", position.hand_of_a_player(Color::Black), w)?;
+    if position.side_to_move() == Color::White {
+        w.write_str("\\teban{後手}\n")?;
+    }
+    Ok(())
+}
+
+fn write_mochigoma<W: Write>(side: &str, hand: Hand, w: &mut W) -> core::fmt::Result {
+    write!(w, "\\mochigoma{{{}}}{{", side)?;
+    let mut empty = true;
+    // Stronger pieces first.
+    for piece_kind in [
+        PieceKind::Rook,
+        PieceKind::Bishop,
+        PieceKind::Gold,
+        PieceKind::Silver,
+        PieceKind::Knight,
+        PieceKind::Lance,
+        PieceKind::Pawn,
+    ] {
+        let count = hand.count(piece_kind).unwrap_or(0);
+        if count == 0 {
+            continue;
+        }
+        if !empty {
+            w.write_char('　')?;
+        }
+        empty = false;
+        w.write_char(piece_kind_to_bod(piece_kind))?;
+        write_kansuji_count(count, w)?;
+    }
+    if empty {
+        w.write_str("なし")?;
+    }
+    w.write_str("}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, Position};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn diagram_is_written_for_other_positions() {
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/4+S4/4K4 b G 3 moves G*5b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let result = to_string(&record).unwrap();
+        assert!(result.starts_with("\\mochigoma{後手}{なし}\n\\banmen{\n&&&&v玉&&&&\\\\\n"));
+        assert!(result.contains("&&&&全&&&&\\\\\n"));
+        assert!(result.ends_with("\\mochigoma{先手}{金}\n\\sashite{3}{▲５二金}\n"));
+
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        let mut result = alloc::string::String::new();
+        assert_eq!(write(&record, &mut result), Ok(None));
+        assert_eq!(result, "");
+    }
+}
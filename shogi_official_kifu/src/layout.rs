@@ -0,0 +1,235 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{
+    display_single_move_write_with_style, wrap::display_width, GameRecord, Numbering, Style,
+};
+
+/// Lays out the moves of a whole game in numbered columns, as newspapers print them.
+///
+/// Every move is prefixed with its ply number, right-aligned to the widest number in the game,
+/// and padded to the widest move so that moves line up in columns.
+/// By default moves are written in [`Style::TRADITIONAL`], six on a line.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameLayout, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b 2g2f").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let layout = GameLayout::new().moves_per_line(2);
+/// assert_eq!(
+///     layout.render(&record).unwrap(),
+///     "1 ▲７六歩   2 △３四歩\n3 ▲２二角成 4 △同銀\n5 ▲２六歩\n",
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GameLayout {
+    style: Style,
+    numbered: bool,
+    numbering: Numbering,
+    moves_per_line: usize,
+}
+
+impl Default for GameLayout {
+    fn default() -> Self {
+        Self {
+            style: Style::TRADITIONAL,
+            numbered: true,
+            numbering: Numbering::Continue,
+            moves_per_line: 6,
+        }
+    }
+}
+
+impl GameLayout {
+    /// Creates a [`GameLayout`] with numbered moves in [`Style::TRADITIONAL`], six on a line.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the [`Style`] that moves are written in.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets whether moves are prefixed with their ply numbers.
+    pub fn numbered(mut self, numbered: bool) -> Self {
+        self.numbered = numbered;
+        self
+    }
+
+    /// Sets how moves are numbered if the initial position is not at ply 1. The default is [`Numbering::Continue`].
+    pub fn numbering(mut self, numbering: Numbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Sets how many moves are written on a line. `0` writes every move on one line.
+    pub fn moves_per_line(mut self, moves_per_line: usize) -> Self {
+        self.moves_per_line = moves_per_line;
+        self
+    }
+
+    /// Lays out the moves of `record`.
+    ///
+    /// Returns [`None`] if a move in `record` cannot be made.
+    pub fn render(&self, record: &GameRecord) -> Option<String> {
+        let mut ret = String::new();
+        self.write(record, &mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Lays out the moves of `record` to a [`Write`].
+    ///
+    /// Ply numbers start as [`numbering`](GameLayout::numbering) sets, so a game from the initial position starts at 1.
+    /// Moves on a line are separated by a space, and every line (including the last one) ends with `\n`.
+    /// Every move is rendered before anything is written, so nothing is written if a move cannot be made.
+    ///
+    /// Returns `Ok(None)` if a move in `record` cannot be made.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    pub fn write<W: Write>(
+        &self,
+        record: &GameRecord,
+        w: &mut W,
+    ) -> Result<Option<()>, core::fmt::Error> {
+        let mut position = record.initial().clone();
+        let first_ply = self.numbering.first_ply(&position);
+        let last_ply = (first_ply + record.len()).saturating_sub(1);
+        let number_width = alloc::format!("{}", last_ply).len();
+        let mut entries = Vec::with_capacity(record.len());
+        for (index, mv) in record.moves().enumerate() {
+            let mut entry = String::new();
+            if self.numbered {
+                write!(
+                    entry,
+                    "{:>width$} ",
+                    first_ply + index,
+                    width = number_width
+                )?;
+            }
+            if display_single_move_write_with_style(&position, mv, &self.style, &mut entry)?
+                .is_none()
+                || position.make_move(mv).is_none()
+            {
+                return Ok(None);
+            }
+            entries.push(entry);
+        }
+        let column_width = entries
+            .iter()
+            .map(|entry| display_width(entry))
+            .max()
+            .unwrap_or(0);
+        for (index, entry) in entries.iter().enumerate() {
+            if index > 0 {
+                if self.moves_per_line != 0 && index % self.moves_per_line == 0 {
+                    w.write_char('\n')?;
+                } else {
+                    let previous = &entries[index - 1];
+                    for _ in display_width(previous)..column_width {
+                        w.write_char(' ')?;
+                    }
+                    w.write_char(' ')?;
+                }
+            }
+            w.write_str(entry)?;
+        }
+        if !entries.is_empty() {
+            w.write_char('\n')?;
+        }
+        Ok(Some(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, PartialPosition, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn numbers_are_aligned() {
+        let pos = Position::from_usi(
+            "startpos moves 2h3h 8b7b 3h2h 7b8b 2h3h 8b7b 3h2h 7b8b 2h3h 8b7b 3h2h",
+        )
+        .unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let result = GameLayout::new().render(&record).unwrap();
+        assert_eq!(
+            result,
+            " 1 ▲３八飛  2 △７二飛  3 ▲２八飛  4 △８二飛  5 ▲３八飛  6 △７二飛\n 7 ▲２八飛  8 △８二飛  9 ▲３八飛 10 △７二飛 11 ▲２八飛\n",
+        );
+        let result = GameLayout::new()
+            .numbered(false)
+            .moves_per_line(0)
+            .style(Style::OFFICIAL)
+            .render(&record)
+            .unwrap();
+        assert_eq!(result.lines().count(), 1);
+        assert!(result.starts_with("▲３８飛 △７２飛 "));
+    }
+
+    #[test]
+    fn numbers_continue_from_initial_ply() {
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 99 moves G*5b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        assert_eq!(
+            GameLayout::new().render(&record),
+            Some("99 ▲５二金\n".into())
+        );
+    }
+
+    #[test]
+    fn hand_changes_follow_the_game() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e 8b3b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let style = Style {
+            hand_changes: true,
+            ..Style::TRADITIONAL
+        };
+        let result = GameLayout::new()
+            .style(style)
+            .moves_per_line(1)
+            .render(&record)
+            .unwrap();
+        assert_eq!(
+            result.lines().collect::<Vec<_>>(),
+            [
+                "1 ▲７六歩",
+                "2 △３四歩",
+                "3 ▲２二角成 (持駒: 角)",
+                "4 △同銀 (持駒: 角)",
+                "5 ▲４五角 (持駒: なし)",
+                "6 △３二飛",
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_is_written_for_invalid_moves() {
+        assert_eq!(
+            GameLayout::new().render(&GameRecord::new(PartialPosition::startpos())),
+            Some(String::new())
+        );
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        });
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        let mut result = String::new();
+        assert_eq!(GameLayout::new().write(&record, &mut result), Ok(None));
+        assert_eq!(result, "");
+    }
+}
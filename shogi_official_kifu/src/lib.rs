@@ -4,22 +4,221 @@
 #![doc = include_str!("../README.md")]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
+use core::ffi::c_void;
 use core::fmt::Write;
 use shogi_core::{
-    c_compat::OptionPiece, Bitboard, Color, CompactMove, Move, PartialPosition, Piece, PieceKind,
-    Square,
+    c_compat::OptionPiece, Bitboard, CompactMove, Move, PartialPosition, Piece, PieceKind,
+    Position, Square, ToUsi,
 };
 
-/// Disambiguation of normal moves.
-mod disambiguation;
+/// Moves whose notation needs suffixes.
+mod ambiguity;
+/// Attacks on squares.
+mod attack;
+/// Completion of moves typed partially.
+mod autocomplete;
+/// Rendering many moves into one buffer.
+mod batch;
+/// Board diagrams.
+mod bod;
+/// Limits on the work of operations.
+mod budget;
+/// Classification of moves.
+mod classify;
+/// Positions prepared for writing many moves.
+mod context;
+/// Moves and game records in the CSA format.
+pub mod csa;
+/// Formats of game records that can be added from outside.
+mod dialect;
+/// Differences between texts of moves.
+mod diff;
+/// Disambiguation of normal moves by the rules of the Japan Shogi Association.
+///
+/// Formats of their own can reuse the rules in two steps:
+/// [`normal_candidates`](disambiguation::normal_candidates) finds the pieces that can move to a destination,
+/// and [`run`](disambiguation::run) chooses the suffixes that tell the moving piece from the others.
+/// [`complete`](disambiguation::complete) adds the suffixes that are not needed, for formats that always write them.
+///
+/// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
+pub mod disambiguation;
+/// Moves written lazily with `Display`.
+mod display;
+/// The sentence that ends a record.
+mod ending;
+/// A builder of styles that writes moves.
+mod formatter;
+/// Standard handicaps.
+mod handicap;
+/// Pieces in hand over a game.
+mod hands;
+/// Hashes of positions.
+mod hash;
+/// Moves of whole games in HTML.
+mod html;
+/// Writing to `std::io::Write`.
+#[cfg(feature = "std")]
+mod io;
+/// Iterators over the notation of moves.
+mod iter;
+/// Writing whole games in the KI2 format.
+///
+/// KI2 lists moves in the traditional notation (`▲７六歩`) one after another,
+/// wrapping lines at a given width.
+///
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>
+pub mod ki2;
+/// Moves and documents in the KIF format.
+mod kif;
+/// The components of a move shared by every writer.
+mod kifu_move;
+/// Writing whole games and positions in LaTeX.
+///
+/// Moves are written as `\sashite{ply}{move}` and positions with `\banmen` and `\mochigoma`,
+/// for documents that define these macros to typeset shogi, such as books and papers generated from game records.
+pub mod latex;
+/// Numbered columns of moves in whole games.
+mod layout;
+/// Limits on the size of the records that readers accept.
+mod limits;
+/// Moves of whole games in Markdown tables.
+mod markdown;
+/// Counting the pieces of a position.
+mod material;
+/// Conversion of many games at once on all cores.
+#[cfg(feature = "rayon")]
+mod parallel;
+/// Conversion from the notation to moves.
+mod parse;
+/// Generation of random games.
+#[cfg(feature = "random")]
+mod random;
+/// Games as sequences of moves.
+mod record;
+/// C functions on parsed records.
+mod record_ffi;
+/// Random access to the moves of a game.
+mod record_view;
+/// Stepping through a game in both directions.
+mod replay;
+/// Removal of personal data.
+mod sanitize;
+/// Serde support for moves and records.
+#[cfg(feature = "serde")]
+mod serialization;
+/// Games in progress whose moves can be taken back.
+mod session;
+/// Moves that end a game.
+mod special;
+/// Options that control how moves are written.
+mod style;
+/// Helpers for testing code that writes notation into bounded sinks.
+pub mod test_support;
+/// Games with variations.
+mod tree;
+/// Games in USEN, the URL-safe encoding of games used by Shogi Playground.
+#[cfg(feature = "usen")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usen")))]
+pub mod usen;
+/// Moves given in USI.
+#[cfg(feature = "usi")]
+mod usi;
+/// Moves in the western notation.
+mod western;
+/// Wrapping lines by display width.
+mod wrap;
+/// Writing moves as they are played.
+mod writer;
+/// Readings of moves for text-to-speech.
+mod yomi;
 
-const SANYOU_SUJI: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
+pub use ambiguity::{AmbiguousMove, AmbiguousMoves};
+pub use attack::gives_check;
+pub use autocomplete::autocomplete;
+pub use batch::RenderedMoves;
+pub use budget::{Budget, Inconclusive};
+pub use classify::{classify, compare_moves, MoveClass};
+pub use context::NotationContext;
+pub use csa::{display_single_move_csa, display_single_move_write_csa};
+pub use dialect::{ConvertError, Converter, KifuDialect};
+pub use diff::{diff_kifu_text, DiffKind, MoveDiff};
+pub use disambiguation::{relative_direction, required_modifier, Modifier};
+pub use display::KifuDisplay;
 #[cfg(feature = "kansuji")]
+#[cfg_attr(docsrs, doc(cfg(feature = "kansuji")))]
+pub use display::KifuDisplayKansuji;
+pub use ending::{Ending, Outcome};
+pub use formatter::KifuFormatter;
+pub use handicap::detect_handicap;
+pub use hands::hand_timeline;
+pub use hash::position_hash;
+pub use html::{ply_fragment, HtmlMoves};
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+pub use io::{display_single_move_write_io, IoWriter};
+pub use iter::{kifu_moves, KifuMoves};
+pub use kif::{
+    display_single_move_kif, display_single_move_write_kif, parse_kif, parse_kif_with_limits,
+    KifError, KifFile, KifRecord,
+};
+#[cfg(feature = "encoding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+pub use kif::{parse_kif_bytes, parse_kif_bytes_with_limits};
+pub use kifu_move::{analyze_single_move, KifuError, KifuMove, KifuMoveDisplay};
+pub use layout::GameLayout;
+pub use limits::ReadLimits;
+pub use markdown::MarkdownTable;
+pub use material::{material_conservation_check, MaterialError};
+#[cfg(feature = "rayon")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+pub use parallel::{kif_documents_par, render_records_par};
+pub use parse::{parse_single_move, ParseError, MAX_SINGLE_MOVE_INPUT_BYTES};
+#[cfg(feature = "random")]
+#[cfg_attr(docsrs, doc(cfg(feature = "random")))]
+pub use random::random_game;
+pub use record::{GameRecord, KingPolicy, RecordError};
+pub use record_ffi::{
+    kifu_record_black_name, kifu_record_free, kifu_record_move, kifu_record_move_count,
+    kifu_record_parse_csa, kifu_record_result, kifu_record_white_name, KifuRecord,
+};
+pub use record_view::RecordView;
+pub use replay::Replay;
+pub use session::KifuSession;
+pub use special::{display_special_move, SpecialMove};
+pub use style::{Coordinates, KingGlyphs, Notation, Numbering, SideMarkers, Style};
+pub use tree::GameTree;
+#[cfg(feature = "usi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "usi")))]
+pub use usi::{display_pv, display_pv_prefix, display_usi_move, PvError, UsiMoveError};
+pub use western::{display_single_move_western, display_single_move_write_western};
+pub use writer::{KifuWriter, KifuWriterError};
+pub use yomi::{display_single_move_write_yomi, display_single_move_yomi};
+
+/// The maximum length in bytes of a string that [`display_single_move_write`] and [`display_single_move_write_kansuji`] write.
+///
+/// The longest ones look like `▲２２銀左上不成`: a side, two digits, a piece, two characters for disambiguation and `不成`, all of which take 3 bytes per character in UTF-8.
+/// Promoted pieces that take two characters (such as `成銀`) cannot be followed by `不成`, so they never make a longer string.
+/// The bound also holds for every [`Style`] except those in [`Notation::Yomi`] or with [`Style::hand_changes`] set:
+/// the other options only change characters into others of the same length, or add `打` to moves that have no other suffixes.
+/// [`required_len`] finds the exact length of a move.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::MAX_SINGLE_MOVE_BYTES;
+/// assert_eq!(MAX_SINGLE_MOVE_BYTES, "▲２２銀左上不成".len());
+/// ```
+pub const MAX_SINGLE_MOVE_BYTES: usize = 24;
+
+const SANYOU_SUJI: [char; 9] = ['１', '２', '３', '４', '５', '６', '７', '８', '９'];
 const KANSUJI: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
 
 /// Finds the string representation of a [`Move`].
 ///
+/// Returns [`None`] if `mv` cannot be written in `position`. [`try_display_single_move`] tells why.
+///
 /// Examples:
 /// ```
 /// # use shogi_core::{Move, PartialPosition, Square};
@@ -71,28 +270,456 @@ pub fn display_single_move_kansuji(
     Some(ret)
 }
 
-struct Bridge(*mut u8);
+/// Finds the string representation of a [`Move`] in the given [`Style`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{display_single_move_with_style, Style};
+/// let pos = PartialPosition::from_usi("sfen 4k4/8P/9/9/9/9/9/9/4K4 b - 1").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_1B,
+///     to: Square::SQ_1A,
+///     promote: true,
+/// };
+/// let mut style = Style::default();
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲１１歩成".to_string()));
+/// style.write_forced_promotion = false;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲１１歩".to_string()));
+/// ```
+pub fn display_single_move_with_style(
+    position: &PartialPosition,
+    mv: Move,
+    style: &Style,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_with_style(position, mv, style, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the USI representation and the string representation of a [`Move`] at once.
+///
+/// Returns `(usi, kifu)`, or [`None`] if the kifu representation could not be found.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::render_with_usi;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/8P/9/9/9/4G4/4K4 b G 1").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_1D,
+///     to: Square::SQ_1C,
+///     promote: true,
+/// };
+/// let result = render_with_usi(&pos, mv);
+/// assert_eq!(result, Some(("1d1c+".to_string(), "▲１３歩成".to_string())));
+/// ```
+pub fn render_with_usi(
+    position: &PartialPosition,
+    mv: Move,
+) -> Option<(alloc::string::String, alloc::string::String)> {
+    let kifu = display_single_move(position, mv)?;
+    Some((mv.to_usi_owned(), kifu))
+}
+
+/// Finds the string representation of a [`Move`] made in the current position of a [`Position`].
+///
+/// `同` is written if `mv` captures on the destination of the last move in the history of `position`.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_move;
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_3A,
+///     to: Square::SQ_2B,
+///     promote: false,
+/// };
+/// assert_eq!(display_move(&pos, mv), Some("△同銀".to_string()));
+/// ```
+pub fn display_move(position: &Position, mv: Move) -> Option<alloc::string::String> {
+    let mut current = position.inner().clone();
+    if let Some(&last_move) = position.moves().last() {
+        current.last_move_set(Some(last_move));
+    }
+    display_single_move(&current, mv)
+}
+
+/// Finds the string representation of the last move in the history of a [`Position`].
+///
+/// The position before the last move is found by replaying the history from [`Position::initial_position`],
+/// so `同` is written if the last move captures on the destination of the one before it.
+///
+/// Returns [`None`] if `position` has no moves or the last move cannot be written.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_last_move;
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// assert_eq!(display_last_move(&pos), Some("△同銀".to_string()));
+/// assert_eq!(display_last_move(&Position::startpos()), None);
+/// ```
+pub fn display_last_move(position: &Position) -> Option<alloc::string::String> {
+    let (&last_move, history) = position.moves().split_last()?;
+    let mut before = position.initial_position().clone();
+    for &mv in history {
+        before.make_move(mv)?;
+    }
+    display_single_move(&before, last_move)
+}
+
+/// Finds the caption written under a diagram, such as `図は▲３三桂まで`.
+///
+/// `position` is the position in which `last_move` was made, so the diagram shows the position right after it.
+/// Ranks are written in kansuji as is customary for captions.
+/// `同` is used when `last_move` captures on the destination of [`PartialPosition::last_move`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::caption;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b N 1").unwrap();
+/// let mv = Move::Drop {
+///     piece: shogi_core::Piece::B_N,
+///     to: Square::SQ_3C,
+/// };
+/// assert_eq!(caption(&pos, mv), Some("図は▲３三桂まで".to_string()));
+/// ```
+pub fn caption(position: &PartialPosition, last_move: Move) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    write_caption(position, last_move, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+fn write_caption<W: Write>(
+    position: &PartialPosition,
+    last_move: Move,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let kifu_move = if let Ok(kifu_move) = kifu_move::analyze(position, last_move) {
+        kifu_move
+    } else {
+        return Ok(None);
+    };
+    w.write_str("図は")?;
+    kifu_move.write(&Style::TRADITIONAL, w)?;
+    w.write_str("まで")?;
+    Ok(Some(()))
+}
+
+/// Finds every legal move of the piece on `from`, together with its string representation.
+///
+/// Destinations are listed in ascending order of [`Square::index`].
+/// If both the promoting and the non-promoting move to a destination are legal,
+/// the destination appears twice, the non-promoting one first.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::annotated_destinations;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/2S1S4/9/9/9/9/4K4 b - 1").unwrap();
+/// let result = annotated_destinations(&pos, Square::SQ_7D);
+/// assert_eq!(
+///     result,
+///     [
+///         (Square::SQ_6C, "▲６３銀左不成".to_string()),
+///         (Square::SQ_6C, "▲６３銀左成".to_string()),
+///         (Square::SQ_6E, "▲６５銀左".to_string()),
+///         (Square::SQ_7C, "▲７３銀不成".to_string()),
+///         (Square::SQ_7C, "▲７３銀成".to_string()),
+///         (Square::SQ_8C, "▲８３銀不成".to_string()),
+///         (Square::SQ_8C, "▲８３銀成".to_string()),
+///         (Square::SQ_8E, "▲８５銀".to_string()),
+///     ],
+/// );
+/// ```
+pub fn annotated_destinations(
+    position: &PartialPosition,
+    from: Square,
+) -> alloc::vec::Vec<(Square, alloc::string::String)> {
+    use shogi_core::LegalityChecker;
+    use shogi_legality_lite::LiteLegalityChecker;
+
+    let mut ret = alloc::vec::Vec::new();
+    for to in LiteLegalityChecker.normal_from_candidates(position, from) {
+        for promote in [false, true] {
+            let mv = Move::Normal { from, to, promote };
+            if !LiteLegalityChecker.is_legal_partial_lite(position, mv) {
+                continue;
+            }
+            if let Some(result) = display_single_move(position, mv) {
+                ret.push((to, result));
+            }
+        }
+    }
+    ret
+}
+
+/// Finds every legal move to `to`, together with its string representation.
+///
+/// Normal moves come first, in ascending order of the [`Square::index`] of their origins.
+/// If both the promoting and the non-promoting move from an origin are legal,
+/// the origin appears twice, the non-promoting one first.
+/// Drops follow in ascending order of [`PieceKind`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::moves_to_square;
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/2S1S4/9/9/9/9/4K4 b G 1").unwrap();
+/// let result: Vec<_> = moves_to_square(&pos, Square::SQ_6C)
+///     .into_iter()
+///     .map(|(_, notation)| notation)
+///     .collect();
+/// assert_eq!(
+///     result,
+///     [
+///         "▲６３銀右不成",
+///         "▲６３銀右成",
+///         "▲６３銀左不成",
+///         "▲６３銀左成",
+///         "▲６３金",
+///     ],
+/// );
+/// ```
+pub fn moves_to_square(
+    position: &PartialPosition,
+    to: Square,
+) -> alloc::vec::Vec<(Move, alloc::string::String)> {
+    use shogi_core::LegalityChecker;
+    use shogi_legality_lite::LiteLegalityChecker;
+
+    let mut moves: alloc::vec::Vec<Move> = LiteLegalityChecker
+        .all_legal_moves_partial(position)
+        .into_iter()
+        .filter(|mv| mv.to() == to)
+        .collect();
+    moves.sort_by_key(|&mv| match mv {
+        Move::Normal { from, promote, .. } => (0, from.index(), promote),
+        Move::Drop { piece, .. } => (1, piece.piece_kind() as u8, false),
+    });
+    let context = NotationContext::new(position);
+    moves
+        .into_iter()
+        .filter_map(|mv| Some((mv, context.render(mv)?)))
+        .collect()
+}
+
+/// Returned by the C functions when a move cannot be rendered or made in the given position.
+///
+/// The values of the `KIFU_ERR_*` constants are stable and never reused for another meaning.
+pub const KIFU_ERR_ILLEGAL_MOVE: i32 = -1;
+/// Returned by the C functions when the result does not fit in the given buffer.
+///
+/// A buffer of [`MAX_SINGLE_MOVE_BYTES`] bytes is always large enough for a single move.
+pub const KIFU_ERR_BUFFER_TOO_SMALL: i32 = -2;
+/// Returned by the C functions when an index is out of range.
+pub const KIFU_ERR_OUT_OF_RANGE: i32 = -3;
+/// Returned by the C functions when a record cannot be parsed.
+pub const KIFU_ERR_INVALID_RECORD: i32 = -4;
+
+/// A [`Write`] into a raw buffer that fails instead of writing past `end`.
+struct Bridge {
+    ptr: *mut u8,
+    end: *mut u8,
+}
+
+impl Bridge {
+    /// # Safety
+    /// `ptr` must be valid for writes of `len` bytes.
+    unsafe fn new(ptr: *mut u8, len: usize) -> Self {
+        Self {
+            ptr,
+            end: ptr.add(len),
+        }
+    }
+}
+
 impl Write for Bridge {
     #[inline(always)]
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        fn inner(this: &mut Bridge, s: &str) {
-            let slice = s.as_bytes();
-            unsafe {
-                for (i, &byte) in slice.iter().enumerate() {
-                    core::ptr::write(this.0.add(i), byte);
-                }
-                this.0 = this.0.add(slice.len());
+        let slice = s.as_bytes();
+        unsafe {
+            if (self.end.offset_from(self.ptr) as usize) < slice.len() {
+                return Err(core::fmt::Error);
             }
+            for (i, &byte) in slice.iter().enumerate() {
+                core::ptr::write(self.ptr.add(i), byte);
+            }
+            self.ptr = self.ptr.add(slice.len());
+        }
+        Ok(())
+    }
+}
+
+/// Writes a move with `write` into `ptr`, and returns the number of bytes written or an error code.
+unsafe fn write_to_ptr(
+    ptr: *mut u8,
+    len: usize,
+    write: impl FnOnce(&mut Bridge) -> Result<Option<()>, core::fmt::Error>,
+) -> i32 {
+    let mut sink = Bridge::new(ptr, len);
+    match write(&mut sink) {
+        Ok(Some(())) => sink.ptr.offset_from(ptr) as i32,
+        Ok(None) => KIFU_ERR_ILLEGAL_MOVE,
+        Err(_) => KIFU_ERR_BUFFER_TOO_SMALL,
+    }
+}
+
+/// Finds the string representation of a [`Move`] and write it into `buf`.
+///
+/// Returns the number of bytes written. This is the safe counterpart of [`display_single_compactmove`].
+/// A buffer of [`MAX_SINGLE_MOVE_BYTES`] bytes is always large enough.
+///
+/// # Errors
+/// - [`SliceError::Unwritable`] if the move cannot be written. Nothing is written.
+/// - [`SliceError::BufferTooSmall`] if the result does not fit in `buf`.
+///   A prefix of the result that does not end in the middle of a character may have been written.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{display_single_move_to_slice, SliceError, MAX_SINGLE_MOVE_BYTES};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// let mut buf = [0; MAX_SINGLE_MOVE_BYTES];
+/// let len = display_single_move_to_slice(&pos, mv, &mut buf).unwrap();
+/// assert_eq!(core::str::from_utf8(&buf[..len]), Ok("▲７６歩"));
+/// assert_eq!(
+///     display_single_move_to_slice(&pos, mv, &mut buf[..8]),
+///     Err(SliceError::BufferTooSmall),
+/// );
+/// ```
+pub fn display_single_move_to_slice(
+    position: &PartialPosition,
+    mv: Move,
+    buf: &mut [u8],
+) -> Result<usize, SliceError> {
+    let mut sink = SliceWriter { buf, len: 0 };
+    try_display_single_move_write_with_style(position, mv, &Style::OFFICIAL, &mut sink)
+        .map_err(|_| SliceError::BufferTooSmall)?
+        .map_err(SliceError::Unwritable)?;
+    Ok(sink.len)
+}
+
+/// Finds the length in bytes of the string representation of a [`Move`], without allocating.
+///
+/// This is the size of the buffer that [`display_single_move_to_slice`] and [`display_single_compactmove`] need.
+/// Returns [`None`] if the move cannot be written.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::required_len;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(required_len(&pos, mv), Some("▲７６歩".len()));
+/// ```
+pub fn required_len(position: &PartialPosition, mv: Move) -> Option<usize> {
+    required_len_with_style(position, mv, &Style::OFFICIAL)
+}
+
+/// Finds the length in bytes of the string representation of a [`Move`] in the given [`Style`], without allocating.
+///
+/// Returns [`None`] if the move cannot be written.
+pub fn required_len_with_style(
+    position: &PartialPosition,
+    mv: Move,
+    style: &Style,
+) -> Option<usize> {
+    let mut counter = ByteCounter(0);
+    display_single_move_write_with_style(position, mv, style, &mut counter)
+        .expect("ByteCounter cannot return an error")?;
+    Some(counter.0)
+}
+
+/// A [`Write`] that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// The reason why [`display_single_move_to_slice`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SliceError {
+    /// The move cannot be written.
+    Unwritable(KifuError),
+    /// The result does not fit in the buffer.
+    BufferTooSmall,
+}
+
+impl core::fmt::Display for SliceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SliceError::Unwritable(e) => write!(f, "the move cannot be written: {}", e),
+            SliceError::BufferTooSmall => f.write_str("the buffer is too small"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for SliceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SliceError::Unwritable(e) => Some(e),
+            SliceError::BufferTooSmall => None,
         }
-        inner(self, s);
+    }
+}
+
+/// A [`Write`] into a byte slice that fails instead of writing past its end.
+struct SliceWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Write for SliceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let end = self.len + s.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(core::fmt::Error)?
+            .copy_from_slice(s.as_bytes());
+        self.len = end;
         Ok(())
     }
 }
 
 /// Finds the string representation of a [`Move`] and write it to a [`u8`] pointer.
 ///
+/// Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+/// On [`KIFU_ERR_BUFFER_TOO_SMALL`], a prefix of the result may have been written.
+///
 /// # Safety
-/// `ptr` must have enough space for the result.
+/// `ptr` must be valid for writes of `len` bytes.
+/// [`MAX_SINGLE_MOVE_BYTES`] bytes are always enough.
+/// No terminating NUL is written.
+/// [`display_single_move_to_slice`] does the same without `unsafe`.
 ///
 /// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
 #[no_mangle]
@@ -100,18 +727,22 @@ pub unsafe extern "C" fn display_single_compactmove(
     position: &PartialPosition,
     mv: CompactMove,
     ptr: *mut u8,
-) -> bool {
-    let mut sink = Bridge(ptr);
-    let result =
-        display_single_move_write(position, <Move as From<CompactMove>>::from(mv), &mut sink)
-            .unwrap_unchecked();
-    result.is_some()
+    len: usize,
+) -> i32 {
+    write_to_ptr(ptr, len, |sink| {
+        display_single_move_write(position, <Move as From<CompactMove>>::from(mv), sink)
+    })
 }
 
 /// Finds the string representation of a [`Move`] and write it to a [`u8`] pointer.
 ///
+/// Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+/// On [`KIFU_ERR_BUFFER_TOO_SMALL`], a prefix of the result may have been written.
+///
 /// # Safety
-/// `ptr` must have enough space for the result.
+/// `ptr` must be valid for writes of `len` bytes.
+/// [`MAX_SINGLE_MOVE_BYTES`] bytes are always enough.
+/// No terminating NUL is written.
 ///
 /// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
 #[no_mangle]
@@ -121,35 +752,83 @@ pub unsafe extern "C" fn display_single_compactmove_kansuji(
     position: &PartialPosition,
     mv: CompactMove,
     ptr: *mut u8,
-) -> bool {
-    let mut sink = Bridge(ptr);
-    let result = display_single_move_write_kansuji(
-        position,
-        <Move as From<CompactMove>>::from(mv),
-        &mut sink,
-    )
-    .unwrap_unchecked();
-    result.is_some()
+    len: usize,
+) -> i32 {
+    write_to_ptr(ptr, len, |sink| {
+        display_single_move_write_kansuji(position, <Move as From<CompactMove>>::from(mv), sink)
+    })
+}
+
+/// A function that receives one rendered move from [`display_compactmoves_streaming`].
+///
+/// `ptr` points to `len` bytes of UTF-8 with no terminating NUL, which are valid only during the call.
+/// `user_data` is passed through as it is.
+pub type KifuMoveCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, ptr: *const u8, len: usize);
+
+/// Finds the string representations of `len` moves made one after another from `position`,
+/// and passes each of them to `callback` as soon as it is found.
+///
+/// Each move is written into a buffer of [`MAX_SINGLE_MOVE_BYTES`] bytes on the stack and passed to `callback`
+/// before the next one is found, so a whole game can be converted without a buffer that holds all of it.
+/// Finding the pieces that can move to a square still allocates a short-lived list for every move.
+/// Returns 0 on success, or [`KIFU_ERR_ILLEGAL_MOVE`] at the first move that cannot be rendered or made;
+/// the moves before it have already been passed to `callback`.
+///
+/// # Safety
+/// `moves` must point to `len` valid [`CompactMove`]s, and `callback` must be safe to call with `user_data`.
+///
+/// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
+#[no_mangle]
+pub unsafe extern "C" fn display_compactmoves_streaming(
+    position: &PartialPosition,
+    moves: *const CompactMove,
+    len: usize,
+    callback: KifuMoveCallback,
+    user_data: *mut c_void,
+) -> i32 {
+    let mut position = position.clone();
+    let mut buffer = [0u8; MAX_SINGLE_MOVE_BYTES];
+    for i in 0..len {
+        let mv = <Move as From<CompactMove>>::from(core::ptr::read(moves.add(i)));
+        let written = write_to_ptr(buffer.as_mut_ptr(), buffer.len(), |sink| {
+            display_single_move_write(&position, mv, sink)
+        });
+        if written < 0 {
+            return written;
+        }
+        if position.make_move(mv).is_none() {
+            return KIFU_ERR_ILLEGAL_MOVE;
+        }
+        callback(user_data, buffer.as_ptr(), written as usize);
+    }
+    0
 }
 
 /// Finds the string representation of a [`Move`] and write it to a [`Write`].
 ///
+/// # Errors
+/// Errors from `w` are propagated as they are, and nothing is written after an error.
+/// Every character is written with a single call, so if `w` rejects a whole call on failure
+/// (as [`String`](alloc::string::String) and [`FailingWriter`](test_support::FailingWriter) do),
+/// `w` holds a prefix of the result that does not end in the middle of a character.
+///
 /// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
 pub fn display_single_move_write<W: Write>(
     position: &PartialPosition,
     mv: Move,
     w: &mut W,
 ) -> Result<Option<()>, core::fmt::Error> {
-    if let Some(to) = write_side_and_find_to(position, mv, w)? {
-        w.write_char(*unsafe { SANYOU_SUJI.get_unchecked(to.file() as usize - 1) })?;
-        w.write_char(*unsafe { SANYOU_SUJI.get_unchecked(to.rank() as usize - 1) })?;
-    }
-    disambiguate(position, mv, w)
+    display_single_move_write_with_style(position, mv, &Style::OFFICIAL, w)
 }
 
 /// Finds the string representation of a [`Move`] and write it to a [`Write`].
 ///
 /// Traditional move notation, usually found in books, magazines, articles.
+///
+/// # Errors
+/// Same as [`display_single_move_write`].
+///
 /// Ref: <https://www.shogi.or.jp/faq/kihuhyouki.html>
 #[cfg(feature = "kansuji")]
 #[cfg_attr(docsrs, doc(cfg(feature = "kansuji")))]
@@ -158,113 +837,195 @@ pub fn display_single_move_write_kansuji<W: Write>(
     mv: Move,
     w: &mut W,
 ) -> Result<Option<()>, core::fmt::Error> {
-    if let Some(to) = write_side_and_find_to(position, mv, w)? {
-        w.write_char(*unsafe { SANYOU_SUJI.get_unchecked(to.file() as usize - 1) })?;
-        w.write_char(*unsafe { KANSUJI.get_unchecked(to.rank() as usize - 1) })?;
-    }
-    disambiguate(position, mv, w)
+    display_single_move_write_with_style(position, mv, &Style::TRADITIONAL, w)
 }
 
-/// Returns Ok(Some((to, should_continue))) when the call was successful.
-/// If unsuccessful, this functions tries not to write to w, but it is in a best-effort basis.
-fn write_side_and_find_to<W: Write>(
+/// Finds the string representation of a [`Move`] in the given [`Style`] and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`].
+pub fn display_single_move_write_with_style<W: Write>(
     position: &PartialPosition,
     mv: Move,
+    style: &Style,
     w: &mut W,
-) -> Result<Option<Square>, core::fmt::Error> {
-    let side = position.side_to_move();
-    let side_color = if side == Color::Black { '▲' } else { '△' };
-    let to = match mv {
-        Move::Normal { to, .. } => {
-            if let Some(last_move) = position.last_move() {
-                let last_to = last_move.to();
-                if last_to == to {
-                    w.write_char(side_color)?;
-                    w.write_char('同')?;
-                    return Ok(None);
-                }
-            }
-            to
-        }
-        Move::Drop { to, .. } => to,
-    };
-    w.write_char(side_color)?;
-    Ok(Some(to))
+) -> Result<Option<()>, core::fmt::Error> {
+    Ok(try_display_single_move_write_with_style(position, mv, style, w)?.ok())
+}
+
+/// Finds the string representation of a [`Move`], or why it cannot be written.
+///
+/// Same as [`display_single_move`], which returns [`None`] in place of a [`KifuError`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{try_display_single_move, KifuError};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(try_display_single_move(&pos, mv), Ok("▲７６歩".to_string()));
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7E,
+///     promote: false,
+/// };
+/// assert_eq!(try_display_single_move(&pos, mv), Err(KifuError::Unreachable));
+/// ```
+pub fn try_display_single_move(
+    position: &PartialPosition,
+    mv: Move,
+) -> Result<alloc::string::String, KifuError> {
+    try_display_single_move_with_style(position, mv, &Style::OFFICIAL)
+}
+
+/// Finds the string representation of a [`Move`] in the given [`Style`], or why it cannot be written.
+///
+/// Same as [`display_single_move_with_style`], which returns [`None`] in place of a [`KifuError`].
+pub fn try_display_single_move_with_style(
+    position: &PartialPosition,
+    mv: Move,
+    style: &Style,
+) -> Result<alloc::string::String, KifuError> {
+    let mut ret = alloc::string::String::new();
+    try_display_single_move_write_with_style(position, mv, style, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Ok(ret)
 }
 
-fn disambiguate<W: Write>(
+/// Finds the string representation of a [`Move`] in the given [`Style`] and write it to a [`Write`],
+/// or finds why it cannot be written.
+///
+/// Nothing is written if the move cannot be written.
+///
+/// # Errors
+/// Same as [`display_single_move_write`].
+pub fn try_display_single_move_write_with_style<W: Write>(
     position: &PartialPosition,
     mv: Move,
+    style: &Style,
     w: &mut W,
-) -> Result<Option<()>, core::fmt::Error> {
-    let all_moves = shogi_legality_lite::prelegality::all_valid_moves(position);
-    match mv {
-        Move::Normal { from, to, promote } => {
-            let p = if let Some(p) = position.piece_at(from) {
-                p
-            } else {
-                return Ok(None);
-            };
-            w.write_str(piece_kind_to_kanji(p.piece_kind()))?;
-            let mut candidates = Bitboard::empty();
-            for mv in all_moves {
-                if let Move::Normal {
-                    from, to: mv_to, ..
-                } = mv
-                {
-                    if mv_to != to {
-                        continue;
-                    }
-                    if position.PartialPosition_piece_at(from) != OptionPiece::from(Some(p)) {
-                        continue;
-                    }
-                    candidates |= from;
-                }
-            }
-            if disambiguation::run(position, from, to, candidates, w)?.is_none() {
-                return Ok(None);
-            }
-            let side = position.side_to_move();
-            let could_promote = is_promotable_piece(p.piece_kind())
-                && (from.relative_rank(side) <= 3 || to.relative_rank(side) <= 3);
-            if promote {
-                w.write_char('成')?;
-            } else if could_promote {
-                w.write_str("不成")?;
-            }
-        }
-        Move::Drop { to, piece } => {
-            let piece_kind = piece.piece_kind();
-            let side = position.side_to_move();
-            w.write_str(piece_kind_to_kanji(piece_kind))?;
-            let mut normal_possible = false;
-            let p = Piece::new(piece_kind, side);
-            for mv in all_moves {
-                if let Move::Normal {
-                    from, to: mv_to, ..
-                } = mv
-                {
-                    if mv_to != to {
-                        continue;
-                    }
-                    if position.PartialPosition_piece_at(from) != OptionPiece::from(Some(p)) {
-                        continue;
-                    }
-                    normal_possible = true;
-                    break;
-                }
-            }
-            if normal_possible {
-                w.write_str("打")?
-            }
-        }
+) -> Result<Result<(), KifuError>, core::fmt::Error> {
+    let kifu_move = match kifu_move::analyze(position, mv) {
+        Ok(kifu_move) => kifu_move,
+        Err(e) => return Ok(Err(e)),
+    };
+    kifu_move.write_notation(style, w)?;
+    if style.hand_changes && style.notation == Notation::Japanese {
+        bod::write_hand_change(position, mv, w)?;
     }
-    Ok(Some(()))
+    Ok(Ok(()))
 }
 
-fn piece_kind_to_kanji(piece_kind: PieceKind) -> &'static str {
-    match piece_kind {
-        PieceKind::King => "玉",
+/// Finds the string representation of a [`Move`], taking the destination of the previous move from `last_to`.
+///
+/// [`PartialPosition::last_move`] is ignored: `同` is written if `mv` moves to `last_to`.
+/// This is useful when `position` was built without its history, as engines often do.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_single_move_with_last;
+/// let pos = PartialPosition::from_usi(
+///     "sfen lnsgkgsnl/1r5+B1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL w B 4",
+/// )
+/// .unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_3A,
+///     to: Square::SQ_2B,
+///     promote: false,
+/// };
+/// assert_eq!(
+///     display_single_move_with_last(&pos, mv, Some(Square::SQ_2B)),
+///     Some("△同銀".to_string()),
+/// );
+/// assert_eq!(
+///     display_single_move_with_last(&pos, mv, None),
+///     Some("△２２銀".to_string()),
+/// );
+/// ```
+pub fn display_single_move_with_last(
+    position: &PartialPosition,
+    mv: Move,
+    last_to: Option<Square>,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_with_last(position, mv, last_to, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the string representation of a [`Move`], taking the destination of the previous move from `last_to`,
+/// and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`].
+pub fn display_single_move_write_with_last<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    last_to: Option<Square>,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let mut kifu_move = match kifu_move::analyze(position, mv) {
+        Ok(kifu_move) => kifu_move,
+        Err(_) => return Ok(None),
+    };
+    kifu_move.same = kifu_move.from.is_some() && last_to == Some(kifu_move.to);
+    kifu_move.write_notation(&Style::OFFICIAL, w)?;
+    Ok(Some(()))
+}
+
+/// Finds the squares from which `piece` can move to `to`, ignoring the safety of the king.
+///
+/// These are the candidates that the official notation distinguishes with `左`, `右`, `上` and so on:
+/// moves that leave the king in check are included (see issue #5).
+/// Both the promoting and the non-promoting move to `to` are generated in the promotion zone,
+/// but they share the same origin, so each origin is counted once.
+/// Only pieces of the side to move are considered, so the result is empty if `piece` belongs to the other side.
+///
+/// Writing and parsing moves both use this function, so they agree on the set.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Bitboard, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::normal_candidates;
+/// // The gold on 5H is pinned by the rook on 5A, but it still counts.
+/// let pos = PartialPosition::from_usi("sfen 4r4/9/9/9/9/9/9/4G4/3GKG3 b - 1").unwrap();
+/// let candidates = normal_candidates(&pos, Piece::B_G, Square::SQ_6G);
+/// assert_eq!(candidates, Bitboard::single(Square::SQ_5H));
+/// let candidates = normal_candidates(&pos, Piece::B_G, Square::SQ_6H);
+/// assert_eq!(candidates.count(), 2);
+/// // White's pieces are not considered with Black to move.
+/// let candidates = normal_candidates(&pos, Piece::W_R, Square::SQ_5B);
+/// assert!(candidates.is_empty());
+/// ```
+pub fn normal_candidates(position: &PartialPosition, piece: Piece, to: Square) -> Bitboard {
+    let mut candidates = Bitboard::empty();
+    for mv in shogi_legality_lite::prelegality::all_valid_moves(position) {
+        if let Move::Normal {
+            from, to: mv_to, ..
+        } = mv
+        {
+            if mv_to != to {
+                continue;
+            }
+            if position.PartialPosition_piece_at(from) != OptionPiece::from(Some(piece)) {
+                continue;
+            }
+            candidates |= from;
+        }
+    }
+    candidates
+}
+
+fn piece_kind_to_kanji(piece_kind: PieceKind) -> &'static str {
+    match piece_kind {
+        PieceKind::King => "玉",
         PieceKind::Rook => "飛",
         PieceKind::Bishop => "角",
         PieceKind::Gold => "金",
@@ -281,6 +1042,29 @@ fn piece_kind_to_kanji(piece_kind: PieceKind) -> &'static str {
     }
 }
 
+/// Returns whether `piece` must promote when it moves to `to`.
+///
+/// This is the case for a pawn or a lance moving to the last rank, and a knight moving to the last two ranks,
+/// because they would have no moves afterwards otherwise.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Piece, Square};
+/// # use shogi_official_kifu::is_promotion_forced;
+/// assert!(is_promotion_forced(Piece::B_P, Square::SQ_1A));
+/// assert!(!is_promotion_forced(Piece::B_P, Square::SQ_1B));
+/// assert!(is_promotion_forced(Piece::W_N, Square::SQ_1H));
+/// assert!(!is_promotion_forced(Piece::B_S, Square::SQ_1A));
+/// ```
+pub fn is_promotion_forced(piece: Piece, to: Square) -> bool {
+    let relative_rank = to.relative_rank(piece.color());
+    match piece.piece_kind() {
+        PieceKind::Pawn | PieceKind::Lance => relative_rank == 1,
+        PieceKind::Knight => relative_rank <= 2,
+        _ => false,
+    }
+}
+
 #[inline(always)]
 fn is_promotable_piece(piece_kind: PieceKind) -> bool {
     piece_kind.promote().is_some()
@@ -832,6 +1616,651 @@ mod tests {
         assert_eq!(result, Some("▲４８金".to_string()));
     }
 
+    #[test]
+    fn render_with_usi_works() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4G4/4K4 b G 1").unwrap();
+        let mv = Move::Drop {
+            to: Square::SQ_4H,
+            piece: Piece::B_G,
+        };
+        let result = render_with_usi(&pos, mv);
+        assert_eq!(result, Some(("G*4h".to_string(), "▲４８金打".to_string())));
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(render_with_usi(&pos, mv), None);
+    }
+
+    #[test]
+    fn display_move_uses_history() {
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/4s4/4KG3 b - 10 moves 4i5h").unwrap();
+        assert_eq!(display_last_move(&pos), Some("▲５８金".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_5A,
+            to: Square::SQ_4A,
+            promote: false,
+        };
+        assert_eq!(display_move(&pos, mv), Some("△４１玉".to_string()));
+
+        let pos =
+            Position::from_usi("sfen 4k4/9/9/9/9/9/4g4/4s4/4KG3 b - 10 moves 4i5h 5g5h").unwrap();
+        assert_eq!(display_last_move(&pos), Some("△同金".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_5I,
+            to: Square::SQ_5H,
+            promote: false,
+        };
+        assert_eq!(display_move(&pos, mv), Some("▲同玉".to_string()));
+    }
+
+    #[test]
+    fn moves_to_square_lists_every_legal_move() {
+        use shogi_core::LegalityChecker;
+        use shogi_legality_lite::LiteLegalityChecker;
+
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let pos = pos.inner();
+        let mut total = 0;
+        for to in Square::all() {
+            let result = moves_to_square(pos, to);
+            for (mv, notation) in &result {
+                assert_eq!(mv.to(), to);
+                assert_eq!(display_single_move(pos, *mv).as_ref(), Some(notation));
+            }
+            total += result.len();
+        }
+        assert_eq!(
+            total,
+            LiteLegalityChecker.all_legal_moves_partial(pos).len()
+        );
+        assert_eq!(
+            moves_to_square(pos, Square::SQ_2B),
+            [
+                (
+                    Move::Normal {
+                        from: Square::SQ_3A,
+                        to: Square::SQ_2B,
+                        promote: false,
+                    },
+                    "△同銀".to_string()
+                ),
+                (
+                    Move::Normal {
+                        from: Square::SQ_8B,
+                        to: Square::SQ_2B,
+                        promote: false,
+                    },
+                    "△同飛".to_string()
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn display_single_move_with_last_ignores_last_move() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_with_last(pos.inner(), mv, Some(Square::SQ_3D)),
+            Some("△２２銀".to_string())
+        );
+        // Drops are never written with 同.
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+        let mv = Move::Drop {
+            piece: Piece::B_B,
+            to: Square::SQ_5E,
+        };
+        assert_eq!(
+            display_single_move_with_last(pos.inner(), mv, Some(Square::SQ_5E)),
+            Some("▲５５角".to_string())
+        );
+    }
+
+    #[test]
+    fn caption_works() {
+        let mut pos = PartialPosition::from_usi("sfen 4k4/9/4p4/9/4P4/9/9/9/4K4 b S 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        assert_eq!(caption(&pos, mv), Some("図は▲５四歩まで".to_string()));
+        pos.make_move(mv).unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_5C,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        assert_eq!(caption(&pos, mv), Some("図は△同歩まで".to_string()));
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(caption(&pos, mv), None);
+    }
+
+    #[test]
+    fn write_stops_at_error() {
+        use crate::test_support::FailingWriter;
+
+        let pos = PartialPosition::from_usi("sfen 4k4/9/3GGG3/9/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_6C,
+            to: Square::SQ_5B,
+            promote: false,
+        };
+        let expected = "▲５２金左";
+        for limit in 0..expected.len() {
+            let mut w = FailingWriter::new(limit);
+            let result = display_single_move_write(&pos, mv, &mut w);
+            assert!(result.is_err());
+            assert!(expected.starts_with(w.as_str()));
+            assert!(w.as_str().len() + 3 > limit);
+        }
+        let mut w = FailingWriter::new(expected.len());
+        let result = display_single_move_write(&pos, mv, &mut w);
+        assert_eq!(result, Ok(Some(())));
+        assert_eq!(w.as_str(), expected);
+    }
+
+    #[test]
+    fn display_single_move_to_slice_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        let mut buf = [0u8; MAX_SINGLE_MOVE_BYTES];
+        assert_eq!(
+            display_single_move_to_slice(pos.inner(), mv, &mut buf),
+            Ok(9)
+        );
+        assert_eq!(&buf[..9], "△同銀".as_bytes());
+        // Only whole characters are written.
+        let mut buf = [0u8; 8];
+        assert_eq!(
+            display_single_move_to_slice(pos.inner(), mv, &mut buf),
+            Err(SliceError::BufferTooSmall)
+        );
+        assert_eq!(&buf[..6], "△同".as_bytes());
+        assert_eq!(&buf[6..], [0, 0]);
+        let mv = Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_to_slice(pos.inner(), mv, &mut buf),
+            Err(SliceError::Unwritable(KifuError::NoPieceAtOrigin))
+        );
+    }
+
+    #[test]
+    fn display_single_compactmove_returns_error_codes() {
+        let pos = PartialPosition::startpos();
+        let mv = Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        };
+        let mut buffer = [0u8; MAX_SINGLE_MOVE_BYTES];
+        let result = unsafe {
+            display_single_compactmove(&pos, mv.into(), buffer.as_mut_ptr(), buffer.len())
+        };
+        assert_eq!(result, "▲７６歩".len() as i32);
+        assert_eq!(&buffer[..result as usize], "▲７６歩".as_bytes());
+
+        let result = unsafe { display_single_compactmove(&pos, mv.into(), buffer.as_mut_ptr(), 6) };
+        assert_eq!(result, KIFU_ERR_BUFFER_TOO_SMALL);
+
+        let mv = Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        };
+        let result = unsafe {
+            display_single_compactmove(&pos, mv.into(), buffer.as_mut_ptr(), buffer.len())
+        };
+        assert_eq!(result, KIFU_ERR_ILLEGAL_MOVE);
+    }
+
+    #[test]
+    fn display_compactmoves_streaming_works() {
+        use shogi_core::Position;
+
+        unsafe extern "C" fn push(user_data: *mut c_void, ptr: *const u8, len: usize) {
+            let moves = &mut *(user_data as *mut Vec<String>);
+            let bytes = core::slice::from_raw_parts(ptr, len);
+            moves.push(core::str::from_utf8(bytes).unwrap().to_string());
+        }
+
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+        let mut moves: Vec<CompactMove> = pos.moves().iter().map(|&mv| mv.into()).collect();
+        let mut rendered: Vec<String> = Vec::new();
+        let result = unsafe {
+            display_compactmoves_streaming(
+                pos.initial_position(),
+                moves.as_ptr(),
+                moves.len(),
+                push,
+                &mut rendered as *mut Vec<String> as *mut c_void,
+            )
+        };
+        assert_eq!(result, 0);
+        assert_eq!(rendered, ["▲７６歩", "△３４歩", "▲２２角成", "△同銀"]);
+
+        // The second move cannot be made after the first one twice.
+        moves.insert(1, moves[0]);
+        rendered.clear();
+        let result = unsafe {
+            display_compactmoves_streaming(
+                pos.initial_position(),
+                moves.as_ptr(),
+                moves.len(),
+                push,
+                &mut rendered as *mut Vec<String> as *mut c_void,
+            )
+        };
+        assert_eq!(result, KIFU_ERR_ILLEGAL_MOVE);
+        assert_eq!(rendered, ["▲７６歩"]);
+    }
+
+    #[test]
+    fn max_single_move_bytes_is_reached() {
+        let pos = PartialPosition::from_usi("sfen 4k1S1S/9/6SS1/9/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3C,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        let result = display_single_move(&pos, mv).unwrap();
+        assert_eq!(result, "▲２２銀左上不成");
+        assert_eq!(result.len(), MAX_SINGLE_MOVE_BYTES);
+        #[cfg(feature = "kansuji")]
+        {
+            let result = display_single_move_kansuji(&pos, mv).unwrap();
+            assert_eq!(result, "▲２二銀左上不成");
+            assert_eq!(result.len(), MAX_SINGLE_MOVE_BYTES);
+        }
+    }
+
+    #[test]
+    fn max_single_move_bytes_is_not_exceeded() {
+        use shogi_core::Position;
+
+        let sfens = [
+            "startpos",
+            "sfen 4k1S1S/9/6SS1/9/9/9/9/9/4K4 b - 1",
+            "sfen 4k4/9/3GGG3/9/9/9/1+P4S1S/+P8/+P+P+P1K1SS1 b - 1",
+            "sfen 8+B/9/9/6+B2/9/9/9/9/4K1k2 b - 1",
+            "sfen 4k4/2S1S4/9/9/9/9/9/4+S1+S2/4K4 w RBGSNLP 2",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+",
+        ];
+        let mut styles = Vec::new();
+        for notation in [Notation::Japanese, Notation::Western, Notation::Hosking] {
+            for (pad_same, full_disambiguation) in [(false, false), (true, true)] {
+                let mut style = Style::OFFICIAL;
+                style.notation = notation;
+                style.pad_same = pad_same;
+                style.full_disambiguation = full_disambiguation;
+                style.always_mark_drops = full_disambiguation;
+                style.side_markers = SideMarkers::ShogiPieces;
+                style.coordinates = Coordinates::Kansuji;
+                styles.push(style);
+            }
+        }
+        for sfen in sfens {
+            let pos = Position::from_usi(sfen).unwrap();
+            let pos = pos.inner();
+            for mv in shogi_legality_lite::prelegality::all_valid_moves(pos) {
+                if let Some(result) = display_single_move(pos, mv) {
+                    assert!(result.len() <= MAX_SINGLE_MOVE_BYTES, "{}", result);
+                    assert_eq!(required_len(pos, mv), Some(result.len()));
+                }
+                for style in &styles {
+                    if let Some(result) = display_single_move_with_style(pos, mv, style) {
+                        assert!(result.len() <= MAX_SINGLE_MOVE_BYTES, "{}", result);
+                        assert_eq!(required_len_with_style(pos, mv, style), Some(result.len()));
+                    }
+                }
+                #[cfg(feature = "kansuji")]
+                if let Some(result) = display_single_move_kansuji(pos, mv) {
+                    assert!(result.len() <= MAX_SINGLE_MOVE_BYTES, "{}", result);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normal_candidates_ignore_promotion() {
+        // Both silvers can move to 6C with or without promotion.
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/2S1S4/9/9/9/9/4K4 b - 1").unwrap();
+        let candidates = normal_candidates(&pos, Piece::B_S, Square::SQ_6C);
+        assert_eq!(candidates.count(), 2);
+        assert!(candidates.contains(Square::SQ_7D));
+        assert!(candidates.contains(Square::SQ_5D));
+
+        // Only the knight on 8E can reach 7C, where promotion is optional.
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/1N3N3/9/9/9/4K4 b - 1").unwrap();
+        let candidates = normal_candidates(&pos, Piece::B_N, Square::SQ_7C);
+        assert_eq!(candidates, Bitboard::single(Square::SQ_8E));
+    }
+
+    #[test]
+    fn normal_works_promotion_zone() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/2S1S4/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_7D,
+            to: Square::SQ_6C,
+            promote: false,
+        };
+        let result = display_single_move(&pos, mv);
+        assert_eq!(result, Some("▲６３銀左不成".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_5D,
+            to: Square::SQ_6C,
+            promote: true,
+        };
+        let result = display_single_move(&pos, mv);
+        assert_eq!(result, Some("▲６３銀右成".to_string()));
+
+        // Knights moving into the last two ranks must promote, so only one move per origin exists there.
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/1N1N5/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_8D,
+            to: Square::SQ_7B,
+            promote: true,
+        };
+        let result = display_single_move(&pos, mv);
+        assert_eq!(result, Some("▲７２桂左成".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_6D,
+            to: Square::SQ_7B,
+            promote: true,
+        };
+        let result = display_single_move(&pos, mv);
+        assert_eq!(result, Some("▲７２桂右成".to_string()));
+    }
+
+    #[test]
+    fn forced_promotion_style_works() {
+        let style = Style {
+            write_forced_promotion: false,
+            ..Style::OFFICIAL
+        };
+
+        let mut pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w - 2").unwrap();
+        pos.piece_set(Square::SQ_9G, Some(Piece::W_N));
+        pos.piece_set(Square::SQ_1H, Some(Piece::W_L));
+        pos.piece_set(Square::SQ_5G, Some(Piece::W_P));
+        // Forced
+        let mv = Move::Normal {
+            from: Square::SQ_9G,
+            to: Square::SQ_8I,
+            promote: true,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("△８９桂".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_1H,
+            to: Square::SQ_1I,
+            promote: true,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("△１９香".to_string()));
+        let result = display_single_move(&pos, mv);
+        assert_eq!(result, Some("△１９香成".to_string()));
+        // Not forced
+        let mv = Move::Normal {
+            from: Square::SQ_5G,
+            to: Square::SQ_5H,
+            promote: true,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("△５８歩成".to_string()));
+    }
+
+    #[test]
+    fn pad_same_style_works() {
+        let style = Style {
+            pad_same: true,
+            ..Style::OFFICIAL
+        };
+        let pos = shogi_core::Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        let result = display_single_move_with_style(pos.inner(), mv, &style);
+        assert_eq!(result, Some("△同　銀".to_string()));
+
+        // Pieces written in two characters are not padded.
+        let mut pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/7p1/4K3+S b - 1").unwrap();
+        pos.last_move_set(Some(Move::Normal {
+            from: Square::SQ_2G,
+            to: Square::SQ_2H,
+            promote: false,
+        }));
+        let mv = Move::Normal {
+            from: Square::SQ_1I,
+            to: Square::SQ_2H,
+            promote: false,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲同成銀".to_string()));
+    }
+
+    #[test]
+    fn piece_name_style_works() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/+R3K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_9I,
+            to: Square::SQ_9A,
+            promote: false,
+        };
+        let style = Style {
+            old_form_dragon: true,
+            ..Style::OFFICIAL
+        };
+        assert_eq!(display_single_move(&pos, mv), Some("▲９１竜".to_string()));
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲９１龍".to_string()));
+
+        let mv = Move::Normal {
+            from: Square::SQ_5I,
+            to: Square::SQ_4H,
+            promote: false,
+        };
+        let style = Style {
+            kings: KingGlyphs::OpponentOu,
+            ..Style::OFFICIAL
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲４８玉".to_string()));
+        let style = Style {
+            kings: KingGlyphs::SideToMoveOu,
+            ..Style::OFFICIAL
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲４８王".to_string()));
+    }
+
+    #[test]
+    fn hand_changes_style_works() {
+        let style = Style {
+            hand_changes: true,
+            ..Style::OFFICIAL
+        };
+        let pos = PartialPosition::from_usi("sfen 4k4/4+r4/9/9/9/9/9/4R4/4K4 b 2P 1").unwrap();
+        let capture = Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_5B,
+            promote: true,
+        };
+        let result = display_single_move_with_style(&pos, capture, &style);
+        assert_eq!(result.as_deref(), Some("▲５２飛成 (持駒: 飛　歩二)"));
+        let drop = Move::Drop {
+            piece: Piece::B_P,
+            to: Square::SQ_5E,
+        };
+        let result = display_single_move_with_style(&pos, drop, &style);
+        assert_eq!(result.as_deref(), Some("▲５５歩 (持駒: 歩)"));
+        let quiet = Move::Normal {
+            from: Square::SQ_5H,
+            to: Square::SQ_5G,
+            promote: false,
+        };
+        let result = display_single_move_with_style(&pos, quiet, &style);
+        assert_eq!(result.as_deref(), Some("▲５７飛"));
+        let western = Style {
+            notation: Notation::Western,
+            ..style
+        };
+        let result = display_single_move_with_style(&pos, capture, &western);
+        assert_eq!(result.as_deref(), Some("Rx5b+"));
+    }
+
+    #[test]
+    fn single_char_promoted_style_works() {
+        let style = Style {
+            single_char_promoted: true,
+            pad_same: true,
+            ..Style::OFFICIAL
+        };
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4+N4/+L3K3+S b - 1").unwrap();
+        let cases = [
+            (Square::SQ_9I, Square::SQ_9H, "▲９８杏"),
+            (Square::SQ_5H, Square::SQ_4G, "▲４７圭"),
+            (Square::SQ_1I, Square::SQ_1H, "▲１８全"),
+        ];
+        for (from, to, expected) in cases {
+            let mv = Move::Normal {
+                from,
+                to,
+                promote: false,
+            };
+            let result = display_single_move_with_style(&pos, mv, &style);
+            assert_eq!(result.as_deref(), Some(expected));
+        }
+
+        // A single character is padded after `同`.
+        let mut pos = pos;
+        pos.last_move_set(Some(Move::Normal {
+            from: Square::SQ_2G,
+            to: Square::SQ_1H,
+            promote: false,
+        }));
+        let mv = Move::Normal {
+            from: Square::SQ_1I,
+            to: Square::SQ_1H,
+            promote: false,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲同　全".to_string()));
+    }
+
+    #[test]
+    fn write_same_style_works() {
+        let style = Style {
+            write_same: false,
+            ..Style::OFFICIAL
+        };
+        let pos = shogi_core::Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        let result = display_single_move_with_style(pos.inner(), mv, &style);
+        assert_eq!(result, Some("△２２銀".to_string()));
+        // `pad_same` has no effect without `同`.
+        let style = Style {
+            pad_same: true,
+            ..style
+        };
+        let result = display_single_move_with_style(pos.inner(), mv, &style);
+        assert_eq!(result, Some("△２２銀".to_string()));
+    }
+
+    #[test]
+    fn always_mark_drops_style_works() {
+        let style = Style {
+            always_mark_drops: true,
+            ..Style::OFFICIAL
+        };
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4G4/4K4 b G 1").unwrap();
+        let mv = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_1A,
+        };
+        assert_eq!(display_single_move(&pos, mv), Some("▲１１金".to_string()));
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲１１金打".to_string()));
+
+        // 打 is not doubled when the official notation needs it too.
+        let mv = Move::Drop {
+            piece: Piece::B_G,
+            to: Square::SQ_5G,
+        };
+        assert_eq!(display_single_move(&pos, mv), Some("▲５７金打".to_string()));
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("▲５７金打".to_string()));
+    }
+
+    #[test]
+    fn full_disambiguation_style_works() {
+        let style = Style {
+            full_disambiguation: true,
+            ..Style::OFFICIAL
+        };
+        let pos = PartialPosition::startpos();
+        let cases = [
+            (Square::SQ_7G, Square::SQ_7F, "▲７６歩上"),
+            (Square::SQ_6I, Square::SQ_5H, "▲５８金左上"),
+            (Square::SQ_4I, Square::SQ_4H, "▲４８金直"),
+            (Square::SQ_5I, Square::SQ_6H, "▲６８玉右上"),
+            (Square::SQ_2H, Square::SQ_3H, "▲３８飛右寄"),
+        ];
+        for (from, to, expected) in cases {
+            let mv = Move::Normal {
+                from,
+                to,
+                promote: false,
+            };
+            let result = display_single_move_with_style(&pos, mv, &style);
+            assert_eq!(result.as_deref(), Some(expected));
+        }
+
+        let pos = PartialPosition::from_usi("sfen 4k4/7r1/9/9/9/9/9/9/4K4 w - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_2B,
+            to: Square::SQ_2F,
+            promote: false,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("△２６飛上".to_string()));
+        let mv = Move::Normal {
+            from: Square::SQ_2B,
+            to: Square::SQ_5B,
+            promote: false,
+        };
+        let result = display_single_move_with_style(&pos, mv, &style);
+        assert_eq!(result, Some("△５２飛左寄".to_string()));
+    }
+
     // A test taken from https://github.com/rust-shogi-crates/shogi_official_kifu/issues/5's comment.
     #[test]
     fn normal_includes_illegal() {
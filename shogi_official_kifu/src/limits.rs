@@ -0,0 +1,54 @@
+/// Limits on the size of the records that the readers accept.
+///
+/// Every reader of whole records ([`csa::parse`](crate::csa::parse), [`parse_kif`](crate::parse_kif) and `usen::decode`)
+/// reads its input once from the start to the end without recursion, and stops with a `TooLarge` error
+/// as soon as the input goes over a limit, so the time and memory they take are bounded even for hostile uploads.
+/// What they return recurses only as deep as [`max_variation_depth`](ReadLimits::max_variation_depth) when it is dropped or written.
+/// The functions without `_with_limits` use [`ReadLimits::DEFAULT`].
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::{parse_kif_with_limits, KifError, ReadLimits};
+/// let mut limits = ReadLimits::default();
+/// limits.max_moves = 1;
+/// let text = "手数----指手---------消費時間--
+///    1 ７六歩(77)
+///    2 ３四歩(33)
+/// ";
+/// assert_eq!(parse_kif_with_limits(text, &limits), Err(KifError::TooLarge));
+/// limits.max_moves = 2;
+/// assert!(parse_kif_with_limits(text, &limits).is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ReadLimits {
+    /// The maximum number of moves, counting the moves of every variation.
+    pub max_moves: usize,
+    /// The maximum total length in bytes of the headers, such as the names of the players, and the names of the bookmarks.
+    pub max_header_bytes: usize,
+    /// The maximum number of variations nested in each other, counting a variation of the main line as 1.
+    ///
+    /// A [`GameTree`](crate::GameTree) is dropped, cloned, compared and written by recursing into its variations,
+    /// so deeper trees could overflow the stack.
+    pub max_variation_depth: usize,
+}
+
+impl ReadLimits {
+    /// The limits used by the readers without `_with_limits`.
+    ///
+    /// [`max_moves`](ReadLimits::max_moves) is the number of moves after which the ply of
+    /// [`PartialPosition`](shogi_core::PartialPosition), a [`u16`], reaches its maximum from 1,
+    /// [`max_header_bytes`](ReadLimits::max_header_bytes) and [`max_variation_depth`](ReadLimits::max_variation_depth)
+    /// are far more than real records have.
+    pub const DEFAULT: Self = Self {
+        max_moves: u16::MAX as usize - 1,
+        max_header_bytes: 64 * 1024,
+        max_variation_depth: 100,
+    };
+}
+
+impl Default for ReadLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
@@ -0,0 +1,250 @@
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+use shogi_core::Color;
+
+use crate::{html::ANCHOR_PREFIX, GameRecord, KifuFormatter, Numbering};
+
+/// Writes the moves of a game as a Markdown table, for pasting into issues, wikis and chat.
+///
+/// Every move takes a row with the ply, the move in the column of its side, the time spent and the comments.
+/// Moves are written by a [`KifuFormatter`], so the table follows the same options as the plain text.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, KifuFormatter, MarkdownTable, Style};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let table = MarkdownTable::new(&record)
+///     .formatter(KifuFormatter::with_style(Style::TRADITIONAL))
+///     .times(&[3, 72])
+///     .comment(2, "角道を開ける | 定跡");
+/// assert_eq!(
+///     table.render().unwrap(),
+///     "\
+/// | 手数 | 先手 | 後手 | 消費時間 | コメント |
+/// |---:|---|---|---:|---|
+/// | 1 | ▲７六歩 |  | 0:03 |  |
+/// | 2 |  | △３四歩 | 1:12 | 角道を開ける \\| 定跡 |
+/// ",
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct MarkdownTable<'a> {
+    record: &'a GameRecord,
+    formatter: KifuFormatter,
+    times: &'a [u32],
+    comments: Vec<(usize, &'a str)>,
+    anchors: bool,
+    numbering: Numbering,
+}
+
+impl<'a> MarkdownTable<'a> {
+    /// Creates a [`MarkdownTable`] that writes `record` in the official notation with no times or comments.
+    pub fn new(record: &'a GameRecord) -> Self {
+        Self {
+            record,
+            formatter: KifuFormatter::new(),
+            times: &[],
+            comments: Vec::new(),
+            anchors: false,
+            numbering: Numbering::Restart,
+        }
+    }
+
+    /// Sets the [`KifuFormatter`] that moves are written with.
+    pub fn formatter(mut self, formatter: KifuFormatter) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Sets the time spent on each move in seconds, written as `m:ss`.
+    ///
+    /// Moves without a time, such as the ones after the end of `times`, have the column left empty.
+    pub fn times(mut self, times: &'a [u32]) -> Self {
+        self.times = times;
+        self
+    }
+
+    /// Adds a comment to the move at `ply`, counting from 1.
+    ///
+    /// Comments on the same move are joined by `<br>` in the order they are added, and so are the lines of a comment.
+    /// The bookmarks of the position after the move (see [`GameRecord::add_bookmark`]) follow them as `しおり：中盤の急所`.
+    /// `|` is escaped so that it does not end the cell.
+    pub fn comment(mut self, ply: usize, comment: &'a str) -> Self {
+        self.comments.push((ply, comment));
+        self
+    }
+
+    /// Sets whether the number of every move is preceded by an anchor such as `<a id="ply-37"></a>`,
+    /// which [`ply_fragment`](crate::ply_fragment) links to.
+    ///
+    /// The anchor follows the number in the first column, so it stays the same when the record is published again.
+    /// Renderers that allow HTML in Markdown, such as GitHub, keep the anchors.
+    pub fn anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Sets how moves are numbered if the initial position is not at ply 1. The default is [`Numbering::Restart`].
+    ///
+    /// Only the numbers in the first column change. Comments are still added to moves counting from 1.
+    pub fn numbering(mut self, numbering: Numbering) -> Self {
+        self.numbering = numbering;
+        self
+    }
+
+    /// Writes the table into a [`String`].
+    ///
+    /// Returns [`None`] if a move in the record cannot be made.
+    pub fn render(&self) -> Option<String> {
+        let mut ret = String::new();
+        self.write(&mut ret)
+            .expect("fmt::Write for String cannot return an error")?;
+        Some(ret)
+    }
+
+    /// Writes the table to a [`Write`].
+    ///
+    /// Plies count from 1 in the order of the moves unless [`numbering`](MarkdownTable::numbering) is [`Numbering::Continue`].
+    /// Every move is rendered before anything is written, so nothing is written if a move cannot be made.
+    ///
+    /// Returns `Ok(None)` if a move in the record cannot be made.
+    ///
+    /// # Errors
+    /// Errors from `w` are propagated as they are, and nothing is written after an error.
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<Option<()>, core::fmt::Error> {
+        let mut position = self.record.initial().clone();
+        let first_ply = self.numbering.first_ply(&position);
+        let mut rows = Vec::with_capacity(self.record.len());
+        for (index, mv) in self.record.moves().enumerate() {
+            let ply = index + 1;
+            let mut rendered = String::new();
+            if self
+                .formatter
+                .format(&position, mv, &mut rendered)?
+                .is_none()
+            {
+                return Ok(None);
+            }
+            let (black, white) = if position.side_to_move() == Color::Black {
+                (rendered.as_str(), "")
+            } else {
+                ("", rendered.as_str())
+            };
+            let mut time = String::new();
+            if let Some(&seconds) = self.times.get(index) {
+                write!(time, "{}:{:02}", seconds / 60, seconds % 60)?;
+            }
+            let mut comments = String::new();
+            for &(_, comment) in self.comments.iter().filter(|&&(p, _)| p == ply) {
+                for line in comment.lines() {
+                    if !comments.is_empty() {
+                        comments.push_str("<br>");
+                    }
+                    write_escaped(line, &mut comments)?;
+                }
+            }
+            for (name, _) in self.record.bookmarks().filter(|&(_, p)| p == ply) {
+                if !comments.is_empty() {
+                    comments.push_str("<br>");
+                }
+                comments.push_str("しおり：");
+                write_escaped(name, &mut comments)?;
+            }
+            let mut row = String::new();
+            row.push_str("| ");
+            if self.anchors {
+                write!(row, "<a id=\"{}{}\"></a>", ANCHOR_PREFIX, first_ply + index)?;
+            }
+            writeln!(
+                row,
+                "{} | {} | {} | {} | {} |",
+                first_ply + index,
+                black,
+                white,
+                time,
+                comments
+            )?;
+            if position.make_move(mv).is_none() {
+                return Ok(None);
+            }
+            rows.push(row);
+        }
+        w.write_str("| 手数 | 先手 | 後手 | 消費時間 | コメント |\n|---:|---|---|---:|---|\n")?;
+        for row in &rows {
+            w.write_str(row)?;
+        }
+        Ok(Some(()))
+    }
+}
+
+fn write_escaped<W: Write>(s: &str, w: &mut W) -> core::fmt::Result {
+    for c in s.chars() {
+        if c == '|' {
+            w.write_char('\\')?;
+        }
+        w.write_char(c)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, PartialPosition, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn markdown_table_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let table = MarkdownTable::new(&record)
+            .times(&[600])
+            .comment(3, "角交換")
+            .comment(3, "急戦\n力戦")
+            .render()
+            .unwrap();
+        assert_eq!(
+            table.lines().skip(2).collect::<Vec<_>>(),
+            [
+                "| 1 | ▲７６歩 |  | 10:00 |  |",
+                "| 2 |  | △３４歩 |  |  |",
+                "| 3 | ▲２２角成 |  |  | 角交換<br>急戦<br>力戦 |",
+            ]
+        );
+
+        let mut record = record;
+        record.add_bookmark("角交換|", 3);
+        let table = MarkdownTable::new(&record).comment(3, "急戦");
+        assert_eq!(
+            table.render().unwrap().lines().nth(4),
+            Some("| 3 | ▲２２角成 |  |  | 急戦<br>しおり：角交換\\| |")
+        );
+
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w G 40 moves 5a5b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let table = MarkdownTable::new(&record).comment(1, "玉上がる");
+        let row = "|  | △５２玉 |  | 玉上がる |";
+        assert_eq!(
+            table.render().unwrap().lines().nth(2),
+            Some(&*alloc::format!("| 1 {}", row))
+        );
+        let table = table.numbering(Numbering::Continue).anchors(true);
+        assert_eq!(
+            table.render().unwrap().lines().nth(2),
+            Some(&*alloc::format!("| <a id=\"ply-40\"></a>40 {}", row))
+        );
+
+        let mut record = GameRecord::new(PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        let mut result = String::new();
+        assert_eq!(MarkdownTable::new(&record).write(&mut result), Ok(None));
+        assert_eq!(result, "");
+    }
+}
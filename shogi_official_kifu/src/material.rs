@@ -0,0 +1,143 @@
+use shogi_core::{Color, Hand, PartialPosition, Piece, PieceKind, Square};
+
+/// The number of pieces of every kind in a set, in the order of [`PieceKind::array_index`] for unpromoted pieces.
+const SET: [(PieceKind, u8); 8] = [
+    (PieceKind::Pawn, 18),
+    (PieceKind::Lance, 4),
+    (PieceKind::Knight, 4),
+    (PieceKind::Silver, 4),
+    (PieceKind::Gold, 4),
+    (PieceKind::Bishop, 2),
+    (PieceKind::Rook, 2),
+    (PieceKind::King, 2),
+];
+
+/// Checks that `position` has the 40 pieces of a set, counting the pieces on the board and in both hands.
+///
+/// Promoted pieces count as their unpromoted kinds.
+/// The first kind whose count is wrong is reported, so positions with pieces left in the box,
+/// such as most tsume problems, fail the check too.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{PartialPosition, PieceKind};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::material_conservation_check;
+/// assert_eq!(material_conservation_check(&PartialPosition::startpos()), Ok(()));
+/// // A pawn was added to Black's hand.
+/// let pos = PartialPosition::from_usi(
+///     "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b P 1",
+/// )
+/// .unwrap();
+/// let error = material_conservation_check(&pos).unwrap_err();
+/// assert_eq!(error.piece_kind, PieceKind::Pawn);
+/// assert_eq!((error.found, error.expected), (19, 18));
+/// ```
+pub fn material_conservation_check(position: &PartialPosition) -> Result<(), MaterialError> {
+    check(position, false)
+}
+
+/// Same as [`material_conservation_check`], but only reports kinds with more pieces than a set if `allow_missing`.
+pub(crate) fn check(position: &PartialPosition, allow_missing: bool) -> Result<(), MaterialError> {
+    let mut counts = [0u8; 8];
+    for square in Square::all() {
+        if let Some(piece) = position.piece_at(square) {
+            let piece_kind = piece.piece_kind();
+            let piece_kind = piece_kind.unpromote().unwrap_or(piece_kind);
+            counts[piece_kind.array_index()] += 1;
+        }
+    }
+    for color in Color::all() {
+        let hand = position.hand_of_a_player(color);
+        for piece_kind in Hand::all_hand_pieces() {
+            counts[piece_kind.array_index()] += hand.count(piece_kind).unwrap_or(0);
+        }
+    }
+    for &(piece_kind, expected) in &SET {
+        let found = counts[piece_kind.array_index()];
+        if found > expected || (found < expected && !allow_missing) {
+            return Err(MaterialError {
+                piece_kind,
+                found,
+                expected,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The kind of piece whose count breaks the invariant checked by [`material_conservation_check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MaterialError {
+    /// The unpromoted kind of the pieces.
+    pub piece_kind: PieceKind,
+    /// How many pieces of the kind the position has.
+    pub found: u8,
+    /// How many pieces of the kind a set has.
+    pub expected: u8,
+}
+
+impl core::fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "{} pieces of {:?} in place of {}",
+            self.found, self.piece_kind, self.expected
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for MaterialError {}
+
+/// Counts the kings of `color` on the board.
+pub(crate) fn king_count(position: &PartialPosition, color: Color) -> u8 {
+    let king = Piece::new(PieceKind::King, color);
+    Square::all()
+        .filter(|&square| position.piece_at(square) == Some(king))
+        .count() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn counts_promoted_pieces_and_hands() {
+        let ok = [
+            "startpos",
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b",
+            "sfen 4k4/9/9/9/9/9/9/9/4K4 b 2R2B4G4S4N4L9P9p 1",
+            "sfen 4k4/9/9/9/9/9/9/9/+R3K4 b B2G2S2N2LPrb2g2s2n2l17p 12",
+        ];
+        for usi in ok {
+            let pos = Position::from_usi(usi).unwrap();
+            assert_eq!(material_conservation_check(pos.inner()), Ok(()), "{}", usi);
+        }
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1").unwrap();
+        assert_eq!(
+            material_conservation_check(&pos),
+            Err(MaterialError {
+                piece_kind: PieceKind::Pawn,
+                found: 0,
+                expected: 18,
+            })
+        );
+        let pos = PartialPosition::from_usi(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1+B5R1/LNSGKGSNL b b 1",
+        )
+        .unwrap();
+        assert_eq!(
+            material_conservation_check(&pos),
+            Err(MaterialError {
+                piece_kind: PieceKind::Bishop,
+                found: 3,
+                expected: 2,
+            })
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use alloc::{string::String, vec::Vec};
+use rayon::prelude::*;
+
+use crate::{GameRecord, KifFile, RenderedMoves};
+
+/// Writes the moves of every game in `records` in the official notation, spreading the games over the threads of rayon.
+///
+/// The result is in the order of `records`, and is the same as calling [`RenderedMoves::push_record`] on each game.
+/// A game with a move that cannot be written or made gives [`None`], and does not affect the others.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{render_records_par, GameRecord};
+/// let records: Vec<GameRecord> = ["startpos moves 7g7f 3c3d", "startpos moves 2g2f"]
+///     .iter()
+///     .map(|usi| {
+///         let pos = Position::from_usi(usi).unwrap();
+///         GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec())
+///     })
+///     .collect();
+/// let rendered = render_records_par(&records);
+/// assert_eq!(rendered[0].as_ref().unwrap().as_str(), "▲７６歩△３４歩");
+/// assert_eq!(rendered[1].as_ref().unwrap().as_str(), "▲２６歩");
+/// ```
+pub fn render_records_par(records: &[GameRecord]) -> Vec<Option<RenderedMoves>> {
+    records
+        .par_iter()
+        .map(|record| {
+            let mut rendered = RenderedMoves::new();
+            rendered.push_record(record)?;
+            Some(rendered)
+        })
+        .collect()
+}
+
+/// Writes every game in `records` as a KIF document with the default headers, spreading the games over the threads of rayon.
+///
+/// The result is in the order of `records`, and is the same as calling [`KifFile::render`] on each game.
+/// A game with a move that cannot be made gives [`None`], and does not affect the others.
+pub fn kif_documents_par(records: &[GameRecord]) -> Vec<Option<String>> {
+    records
+        .par_iter()
+        .map(|record| KifFile::new(record).render())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn parallel_agrees_with_sequential() {
+        let games = [
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e B*8e",
+            "startpos moves 2g2f 8c8d 2f2e 8d8e 6i7h 4a3b 2e2d 2c2d 2h2d",
+            "sfen 4k4/9/9/9/9/9/9/9/3S1S2K b - 1 moves 6i5h",
+        ];
+        let mut records: Vec<GameRecord> = games
+            .iter()
+            .cycle()
+            .take(64)
+            .map(|usi| {
+                let pos = Position::from_usi(usi).unwrap();
+                GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec())
+            })
+            .collect();
+        records[5].push(Move::Normal {
+            from: Square::SQ_5E,
+            to: Square::SQ_5D,
+            promote: false,
+        });
+        let rendered = render_records_par(&records);
+        let documents = kif_documents_par(&records);
+        for (index, record) in records.iter().enumerate() {
+            let mut expected = RenderedMoves::new();
+            let expected = expected.push_record(record).map(|()| expected);
+            assert_eq!(rendered[index], expected);
+            assert_eq!(documents[index], KifFile::new(record).render());
+        }
+        assert_eq!(rendered[5], None);
+    }
+}
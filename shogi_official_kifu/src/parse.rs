@@ -0,0 +1,366 @@
+use core::iter::Peekable;
+use core::str::Chars;
+
+use shogi_core::{Color, LegalityChecker, Move, PartialPosition, Piece, PieceKind, Square};
+use shogi_legality_lite::LiteLegalityChecker;
+
+use crate::{disambiguation, is_promotion_forced, normal_candidates, KANSUJI, SANYOU_SUJI};
+
+/// The reason why a string could not be resolved to a [`Move`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The input is not a move in the notation.
+    InvalidFormat,
+    /// The side in the input is not the side to move.
+    WrongSide,
+    /// The input uses `同`, but the position has no last move.
+    NoLastMove,
+    /// No legal move matches the input.
+    NoMatch,
+    /// More than one legal move matches the input.
+    Ambiguous,
+    /// The input is longer than [`MAX_SINGLE_MOVE_INPUT_BYTES`].
+    TooLarge,
+}
+
+/// The maximum length in bytes of an input that [`parse_single_move`] accepts.
+///
+/// This is far longer than any valid move (see [`MAX_SINGLE_MOVE_BYTES`](crate::MAX_SINGLE_MOVE_BYTES)).
+/// Longer inputs are rejected with [`ParseError::TooLarge`] before they are looked at.
+pub const MAX_SINGLE_MOVE_INPUT_BYTES: usize = 64;
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            ParseError::InvalidFormat => "invalid format",
+            ParseError::WrongSide => "the side is not the side to move",
+            ParseError::NoLastMove => "同 is used without a last move",
+            ParseError::NoMatch => "no legal move matches",
+            ParseError::Ambiguous => "more than one legal move matches",
+            ParseError::TooLarge => "the input is too large",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for ParseError {}
+
+/// The components of a move written in the notation.
+struct Parsed {
+    /// `None` if the side is omitted.
+    side: Option<Color>,
+    /// `None` means `同`.
+    to: Option<Square>,
+    piece_kind: PieceKind,
+    /// Characters among `上`, `引`, `寄`, `左`, `右` and `直`.
+    modifiers: alloc::string::String,
+    drop: bool,
+    /// `Some(true)` for `成`, `Some(false)` for `不成`, `None` for neither.
+    promote: Option<bool>,
+}
+
+/// Finds the [`Move`] that a string in the official notation represents.
+///
+/// This is the reverse of [`display_single_move`](crate::display_single_move) and [`display_single_move_kansuji`](crate::display_single_move_kansuji).
+/// `同`, `打`, the disambiguation suffixes (`上`, `引`, `寄`, `左`, `右`, `直`) and `成`/`不成` are understood.
+/// The rank may be written either in full-width digits (`７６歩`) or in kansuji (`７六歩`),
+/// and the side (`▲`/`△`) may be omitted, in which case the side to move is assumed.
+/// Only legal moves are returned: if the input matches more than one legal move,
+/// [`ParseError::Ambiguous`] is returned, and if it matches none, [`ParseError::NoMatch`].
+///
+/// The input is read once from left to right without recursion or backtracking,
+/// and inputs longer than [`MAX_SINGLE_MOVE_INPUT_BYTES`] are rejected up front,
+/// so it is safe to pass untrusted input directly.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{parse_single_move, ParseError};
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/3S1S3/9/9/9/4K4 b - 1").unwrap();
+/// let expected = Move::Normal {
+///     from: Square::SQ_6E,
+///     to: Square::SQ_5F,
+///     promote: false,
+/// };
+/// assert_eq!(parse_single_move(&pos, "▲５６銀左"), Ok(expected));
+/// assert_eq!(parse_single_move(&pos, "▲５六銀左"), Ok(expected));
+/// assert_eq!(parse_single_move(&pos, "５六銀左"), Ok(expected));
+/// assert_eq!(parse_single_move(&pos, "５六銀"), Err(ParseError::Ambiguous));
+/// assert_eq!(parse_single_move(&pos, "５五銀"), Err(ParseError::NoMatch));
+///
+/// let pos = Position::from_usi("sfen 4k4/7r1/9/9/9/9/9/9/1B2K4 b - 1 moves 8i2c+").unwrap();
+/// let result = parse_single_move(pos.inner(), "△同飛");
+/// assert_eq!(
+///     result,
+///     Ok(Move::Normal {
+///         from: Square::SQ_2B,
+///         to: Square::SQ_2C,
+///         promote: false,
+///     }),
+/// );
+/// ```
+pub fn parse_single_move(position: &PartialPosition, s: &str) -> Result<Move, ParseError> {
+    if s.len() > MAX_SINGLE_MOVE_INPUT_BYTES {
+        return Err(ParseError::TooLarge);
+    }
+    let parsed = parse_components(s)?;
+    if parsed
+        .side
+        .map_or(false, |side| side != position.side_to_move())
+    {
+        return Err(ParseError::WrongSide);
+    }
+    let to = match parsed.to {
+        Some(to) => to,
+        None => position.last_move().ok_or(ParseError::NoLastMove)?.to(),
+    };
+    resolve(position, &parsed, to)
+}
+
+fn parse_components(s: &str) -> Result<Parsed, ParseError> {
+    let mut chars = s.chars().peekable();
+    let side = match chars.peek() {
+        Some('▲') => Some(Color::Black),
+        Some('△') => Some(Color::White),
+        _ => None,
+    };
+    if side.is_some() {
+        chars.next();
+    }
+    let to = if chars.next_if_eq(&'同').is_some() {
+        // KIF pads `同` with a full-width space.
+        chars.next_if_eq(&'　');
+        None
+    } else {
+        let file = parse_digit(&mut chars, &[&SANYOU_SUJI])?;
+        let rank = parse_digit(&mut chars, &[&SANYOU_SUJI, &KANSUJI])?;
+        Some(Square::new(file, rank).ok_or(ParseError::InvalidFormat)?)
+    };
+    let piece_kind = parse_piece_kind(&mut chars)?;
+    let mut modifiers = alloc::string::String::new();
+    let mut drop = false;
+    while let Some(&c) = chars.peek() {
+        match c {
+            '上' | '引' | '寄' | '左' | '右' | '直' => modifiers.push(c),
+            '打' => drop = true,
+            _ => break,
+        }
+        chars.next();
+    }
+    let promote = match chars.next() {
+        None => None,
+        Some('成') => Some(true),
+        Some('不') if chars.next() == Some('成') => Some(false),
+        Some(_) => return Err(ParseError::InvalidFormat),
+    };
+    if chars.next().is_some() || (drop && (!modifiers.is_empty() || promote.is_some())) {
+        return Err(ParseError::InvalidFormat);
+    }
+    Ok(Parsed {
+        side,
+        to,
+        piece_kind,
+        modifiers,
+        drop,
+        promote,
+    })
+}
+
+fn parse_digit(chars: &mut Peekable<Chars>, tables: &[&[char; 9]]) -> Result<u8, ParseError> {
+    let c = chars.next().ok_or(ParseError::InvalidFormat)?;
+    for digits in tables {
+        if let Some(index) = digits.iter().position(|&d| d == c) {
+            return Ok(index as u8 + 1);
+        }
+    }
+    Err(ParseError::InvalidFormat)
+}
+
+fn parse_piece_kind(chars: &mut Peekable<Chars>) -> Result<PieceKind, ParseError> {
+    let piece_kind = match chars.next().ok_or(ParseError::InvalidFormat)? {
+        '玉' | '王' => PieceKind::King,
+        '飛' => PieceKind::Rook,
+        '角' => PieceKind::Bishop,
+        '金' => PieceKind::Gold,
+        '銀' => PieceKind::Silver,
+        '桂' => PieceKind::Knight,
+        '香' => PieceKind::Lance,
+        '歩' => PieceKind::Pawn,
+        '竜' | '龍' => PieceKind::ProRook,
+        '馬' => PieceKind::ProBishop,
+        'と' => PieceKind::ProPawn,
+        '成' => match chars.next() {
+            Some('銀') => PieceKind::ProSilver,
+            Some('桂') => PieceKind::ProKnight,
+            Some('香') => PieceKind::ProLance,
+            _ => return Err(ParseError::InvalidFormat),
+        },
+        _ => return Err(ParseError::InvalidFormat),
+    };
+    Ok(piece_kind)
+}
+
+fn resolve(position: &PartialPosition, parsed: &Parsed, to: Square) -> Result<Move, ParseError> {
+    let side = position.side_to_move();
+    let piece = Piece::new(parsed.piece_kind, side);
+    let mut normal = alloc::vec::Vec::new();
+    let mut drop = None;
+    for mv in LiteLegalityChecker.all_legal_moves_partial(position) {
+        if mv.to() != to {
+            continue;
+        }
+        match mv {
+            Move::Normal { from, promote, .. } => {
+                if position.piece_at(from) != Some(piece) {
+                    continue;
+                }
+                let promote_matches = match parsed.promote {
+                    Some(expected) => promote == expected,
+                    None => !promote || is_promotion_forced(piece, to),
+                };
+                if promote_matches {
+                    normal.push(mv);
+                }
+            }
+            Move::Drop { piece: dropped, .. } => {
+                if dropped.piece_kind() == parsed.piece_kind {
+                    drop = Some(mv);
+                }
+            }
+        }
+    }
+    let candidates = normal_candidates(position, piece, to);
+    // `打` is omitted if no piece on the board can move to the destination.
+    if parsed.drop
+        || candidates.is_empty() && parsed.modifiers.is_empty() && parsed.promote.is_none()
+    {
+        return drop.ok_or(ParseError::NoMatch);
+    }
+    let mut found = None;
+    for &mv in &normal {
+        let from = match mv {
+            Move::Normal { from, .. } => from,
+            Move::Drop { .. } => continue,
+        };
+        let (relative, movement) =
+            if let Some(result) = disambiguation::run(position, from, to, candidates) {
+                result
+            } else {
+                continue;
+            };
+        let mut suffix = alloc::string::String::new();
+        suffix.extend(relative);
+        suffix.extend(movement);
+        if suffix != parsed.modifiers {
+            continue;
+        }
+        if found.is_some() {
+            return Err(ParseError::Ambiguous);
+        }
+        found = Some(mv);
+    }
+    match found {
+        Some(mv) => Ok(mv),
+        None if normal.len() >= 2 && parsed.modifiers.is_empty() => Err(ParseError::Ambiguous),
+        None => Err(ParseError::NoMatch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display_single_move;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn parse_single_move_round_trips() {
+        let sfens = [
+            "startpos",
+            "sfen 4k4/9/3GGG3/9/9/9/1+P4S1S/+P8/+P+P+P1K1SS1 b - 1",
+            "sfen 4k1S1S/9/6SS1/9/9/9/9/9/4K4 b - 1",
+            "sfen 9/9/9/9/4+R3+R/9/9/9/2k1K4 b - 1",
+            "sfen 9/9/3+B5/9/+B8/9/9/9/4K1k2 b - 1",
+            "sfen 4k4/9/9/9/9/9/9/4G4/4K4 b G 1",
+            "sfen 4k4/2S1S4/9/9/9/9/9/4+S1+S2/4K4 w RBGSNLP 2",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+",
+        ];
+        for sfen in sfens {
+            let pos = Position::from_usi(sfen).unwrap();
+            let pos = pos.inner();
+            for mv in LiteLegalityChecker.all_legal_moves_partial(pos) {
+                let s = display_single_move(pos, mv).unwrap();
+                assert_eq!(parse_single_move(pos, &s), Ok(mv), "{}", s);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "kansuji")]
+    fn parse_single_move_round_trips_kansuji() {
+        use crate::display_single_move_kansuji;
+
+        let pos = PartialPosition::startpos();
+        for mv in LiteLegalityChecker.all_legal_moves_partial(&pos) {
+            let s = display_single_move_kansuji(&pos, mv).unwrap();
+            assert_eq!(parse_single_move(&pos, &s), Ok(mv), "{}", s);
+            // Without the side
+            let s: alloc::string::String = s.chars().skip(1).collect();
+            assert_eq!(parse_single_move(&pos, &s), Ok(mv), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_single_move_errors() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/3S1S3/9/9/9/4K4 b G 1").unwrap();
+        assert_eq!(parse_single_move(&pos, ""), Err(ParseError::InvalidFormat));
+        assert_eq!(
+            parse_single_move(&pos, "▲５６"),
+            Err(ParseError::InvalidFormat)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲５６銀左X"),
+            Err(ParseError::InvalidFormat)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲５６金打成"),
+            Err(ParseError::InvalidFormat)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "△５６銀左"),
+            Err(ParseError::WrongSide)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲同銀"),
+            Err(ParseError::NoLastMove)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲５６銀"),
+            Err(ParseError::Ambiguous)
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲５６銀直"),
+            Err(ParseError::NoMatch)
+        );
+        assert_eq!(parse_single_move(&pos, "▲１１銀"), Err(ParseError::NoMatch));
+        let long = "▲５６銀左".repeat(5);
+        assert_eq!(parse_single_move(&pos, &long), Err(ParseError::TooLarge));
+        assert_eq!(
+            parse_single_move(&pos, "▲５６金"),
+            Ok(Move::Drop {
+                piece: Piece::B_G,
+                to: Square::SQ_5F,
+            }),
+        );
+        assert_eq!(
+            parse_single_move(&pos, "▲４６金打"),
+            Ok(Move::Drop {
+                piece: Piece::B_G,
+                to: Square::SQ_4F,
+            }),
+        );
+    }
+}
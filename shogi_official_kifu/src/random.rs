@@ -0,0 +1,111 @@
+use shogi_core::{LegalityChecker, Move, PartialPosition};
+use shogi_legality_lite::LiteLegalityChecker;
+
+use crate::GameRecord;
+
+/// Generates a random game of at most `length` moves from `initial`.
+///
+/// Every move is chosen among the legal moves with a probability proportional to `weight(position, mv)`,
+/// where `position` is the position the move is made in.
+/// The game ends early if there are no legal moves or every legal move has the weight 0.
+/// The same `seed` always gives the same game.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_official_kifu::{classify, random_game};
+/// // Captures are ten times as likely as other moves.
+/// let record = random_game(&PartialPosition::startpos(), 40, 1, |position, mv| {
+///     if classify(position, mv).map_or(false, |class| class.capture) {
+///         10
+///     } else {
+///         1
+///     }
+/// });
+/// assert!(record.len() <= 40);
+/// ```
+pub fn random_game<F: FnMut(&PartialPosition, Move) -> u32>(
+    initial: &PartialPosition,
+    length: usize,
+    seed: u64,
+    mut weight: F,
+) -> GameRecord {
+    let mut rng = SplitMix64(seed);
+    let mut record = GameRecord::new(initial.clone());
+    let mut position = initial.clone();
+    let mut weighted = alloc::vec::Vec::new();
+    while record.len() < length {
+        weighted.clear();
+        let mut total = 0u64;
+        for mv in LiteLegalityChecker.all_legal_moves_partial(&position) {
+            let w = weight(&position, mv);
+            if w != 0 {
+                total += w as u64;
+                weighted.push((total, mv));
+            }
+        }
+        if total == 0 {
+            break;
+        }
+        let target = rng.next() % total;
+        // The first move whose cumulative weight exceeds `target`.
+        let index = weighted.partition_point(|&(cumulative, _)| cumulative <= target);
+        let mv = weighted[index].1;
+        if position.make_move(mv).is_none() {
+            break;
+        }
+        record.push(mv);
+    }
+    record
+}
+
+/// A small deterministic generator, so that no dependency is needed.
+///
+/// Ref: <https://prng.di.unimi.it/splitmix64.c>
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{display_single_move, Replay};
+
+    #[test]
+    fn random_game_is_legal_and_reproducible() {
+        let initial = PartialPosition::startpos();
+        for seed in 0..8 {
+            let record = random_game(&initial, 100, seed, |_, _| 1);
+            assert_eq!(record, random_game(&initial, 100, seed, |_, _| 1));
+            let mut replay = Replay::new(&record);
+            for mv in record.moves() {
+                assert!(LiteLegalityChecker.is_legal_partial_lite(replay.position(), mv));
+                assert!(display_single_move(replay.position(), mv).is_some());
+                replay.forward().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn random_game_respects_weights() {
+        let initial = PartialPosition::startpos();
+        let record = random_game(&initial, 10, 0, |_, _| 0);
+        assert!(record.is_empty());
+        // Only pawn moves are allowed.
+        let record = random_game(&initial, 10, 0, |position, mv| match mv {
+            Move::Normal { from, .. } => position.piece_at(from).map_or(0, |p| {
+                (p.piece_kind() == shogi_core::PieceKind::Pawn) as u32
+            }),
+            Move::Drop { .. } => 0,
+        });
+        assert_eq!(record.len(), 10);
+    }
+}
@@ -0,0 +1,630 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use shogi_core::{Color, CompactMove, IllegalMoveKind, Move, PartialPosition, Square};
+
+use crate::{material, Budget, Inconclusive, MaterialError};
+
+/// A game: the initial position and the moves made from it.
+///
+/// Moves are not checked when they are added. Writers that replay a record report the moves that cannot be made.
+/// Moves are stored as [`CompactMove`]s, which take 2 bytes each, and converted from and to [`Move`]s at the boundary.
+/// [`PartialPosition`] counts plies in a [`u16`], so at most `u16::MAX - initial.ply()` moves of a record can be replayed;
+/// writers report the moves after them as moves that cannot be made.
+///
+/// Positions can be given names with [`GameRecord::add_bookmark`], such as `中盤の急所` for the key position of the middle game,
+/// and looked up by name with [`GameRecord::bookmark`].
+/// [`KifFile`](crate::KifFile) writes them as `&` lines (しおり), which [`parse_kif`](crate::parse_kif) reads back.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::GameRecord;
+/// let mut record = GameRecord::new(PartialPosition::startpos());
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// record.push(mv);
+/// assert_eq!(record.len(), 1);
+/// assert_eq!(record.move_at(0), Some(mv));
+/// assert_eq!(record.moves().collect::<Vec<_>>(), [mv]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    initial: PartialPosition,
+    moves: Vec<CompactMove>,
+    pub(crate) bookmarks: Vec<(String, usize)>,
+}
+
+impl GameRecord {
+    /// Creates a [`GameRecord`] with no moves.
+    pub fn new(initial: PartialPosition) -> Self {
+        Self {
+            initial,
+            moves: Vec::new(),
+            bookmarks: Vec::new(),
+        }
+    }
+
+    /// Creates a [`GameRecord`] with the given moves.
+    pub fn with_moves<I: IntoIterator<Item = Move>>(initial: PartialPosition, moves: I) -> Self {
+        Self {
+            initial,
+            moves: moves.into_iter().map(Into::into).collect(),
+            bookmarks: Vec::new(),
+        }
+    }
+
+    /// Returns the initial position.
+    pub fn initial(&self) -> &PartialPosition {
+        &self.initial
+    }
+
+    /// Returns the number of moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Returns whether there are no moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Returns the move at `ply`, counting from 0.
+    pub fn move_at(&self, ply: usize) -> Option<Move> {
+        self.moves.get(ply).map(|&mv| mv.into())
+    }
+
+    /// Returns the moves in the order they were made.
+    pub fn moves(&self) -> impl DoubleEndedIterator<Item = Move> + ExactSizeIterator + '_ {
+        self.moves.iter().map(|&mv| mv.into())
+    }
+
+    /// Returns the moves as they are stored.
+    pub fn compact_moves(&self) -> &[CompactMove] {
+        &self.moves
+    }
+
+    /// Appends a move.
+    pub fn push(&mut self, mv: Move) {
+        self.moves.push(mv.into());
+    }
+
+    /// Names the position after `ply` moves, counting the initial position as 0, so that it can be looked up by `name`.
+    ///
+    /// A name given to another position before is moved to this one. `ply` may be past the last move,
+    /// in which case the position has no moves to reach it yet, and writers skip the bookmark.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::Position;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::GameRecord;
+    /// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+    /// let mut record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// record.add_bookmark("角交換", 4);
+    /// assert_eq!(record.bookmark("角交換"), Some(4));
+    /// assert_eq!(record.bookmarks().collect::<Vec<_>>(), [("角交換", 4)]);
+    /// // The position can be shown with `latex::diagram`, for example.
+    /// let position = record.bookmarked_position("角交換").unwrap();
+    /// assert_eq!(&position, pos.inner());
+    /// assert!(record.bookmark("中盤の急所").is_none());
+    /// ```
+    pub fn add_bookmark<S: Into<String>>(&mut self, name: S, ply: usize) {
+        let name = name.into();
+        self.bookmarks.retain(|(existing, _)| *existing != name);
+        self.bookmarks.push((name, ply));
+    }
+
+    /// Returns the ply of the position named `name` by [`GameRecord::add_bookmark`].
+    pub fn bookmark(&self, name: &str) -> Option<usize> {
+        self.bookmarks
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|&(_, ply)| ply)
+    }
+
+    /// Returns the names and the plies of the bookmarks in the order they were added.
+    pub fn bookmarks(&self) -> impl ExactSizeIterator<Item = (&str, usize)> + '_ {
+        self.bookmarks
+            .iter()
+            .map(|(name, ply)| (name.as_str(), *ply))
+    }
+
+    /// Returns the position named `name`, made from the initial position, so that it can be shown as a diagram.
+    ///
+    /// Returns [`None`] if there is no such bookmark, or if a move up to it cannot be made or is missing.
+    pub fn bookmarked_position(&self, name: &str) -> Option<PartialPosition> {
+        let ply = self.bookmark(name)?;
+        if ply > self.len() {
+            return None;
+        }
+        let mut position = self.initial.clone();
+        for mv in self.moves().take(ply) {
+            position.make_move(mv)?;
+        }
+        Some(position)
+    }
+
+    /// Returns the [`position_hash`](crate::position_hash) of every position in the game.
+    ///
+    /// The hash at `ply` is of the position after `ply` moves, so the first hash is of the initial position
+    /// and the one at `index + 1` is of the position after the move that [`RenderedMoves::get(index)`](crate::RenderedMoves::get) returns.
+    /// Returns [`None`] if a move cannot be made.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::Position;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::GameRecord;
+    /// let pos = Position::from_usi("startpos moves 2h3h 8b7b 3h2h 7b8b").unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// let hashes = record.position_hashes().unwrap();
+    /// assert_eq!(hashes.len(), 5);
+    /// assert_eq!(hashes[0], hashes[4]);
+    /// ```
+    pub fn position_hashes(&self) -> Option<Vec<u64>> {
+        let mut position = self.initial.clone();
+        let mut hashes = Vec::with_capacity(self.len() + 1);
+        hashes.push(crate::position_hash(&position));
+        for mv in self.moves() {
+            position.make_move(mv)?;
+            hashes.push(crate::position_hash(&position));
+        }
+        Some(hashes)
+    }
+
+    /// Returns how many times every position in the game has occurred so far, including itself.
+    ///
+    /// The count at `ply` is for the position after `ply` moves, as in [`GameRecord::position_hashes`].
+    /// A count of 4 means the game ends in 千日手 (repetition), unless it is by perpetual check.
+    /// Returns [`None`] if a move cannot be made.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::Position;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::GameRecord;
+    /// let pos = Position::from_usi("startpos moves 2h3h 8b7b 3h2h 7b8b 2h3h").unwrap();
+    /// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+    /// assert_eq!(record.repetition_counts(), Some(vec![1, 1, 1, 1, 2, 2]));
+    /// ```
+    pub fn repetition_counts(&self) -> Option<Vec<usize>> {
+        let mut budget = Budget::UNLIMITED;
+        self.repetition_counts_within(&mut budget)
+            .expect("an unlimited budget never runs out")
+    }
+
+    /// Same as [`GameRecord::repetition_counts`], but stops with [`Inconclusive`] when `budget` runs out.
+    ///
+    /// Every move takes 1 step, and [`Inconclusive::completed`] is the number of moves made before the budget ran out.
+    /// See [`Budget`] for an example.
+    pub fn repetition_counts_within(
+        &self,
+        budget: &mut Budget,
+    ) -> Result<Option<Vec<usize>>, Inconclusive> {
+        let mut seen = BTreeMap::new();
+        let mut count = |position: &PartialPosition| {
+            let count = seen.entry(crate::position_hash(position)).or_insert(0);
+            *count += 1;
+            *count
+        };
+        let mut position = self.initial.clone();
+        let mut counts = Vec::with_capacity(self.len() + 1);
+        counts.push(count(&position));
+        for (index, mv) in self.moves().enumerate() {
+            budget.spend(1, index)?;
+            if position.make_move(mv).is_none() {
+                return Ok(None);
+            }
+            counts.push(count(&position));
+        }
+        Ok(Some(counts))
+    }
+
+    /// Checks that every move is legal, including the rules that [`GameRecord::validate`] does not check,
+    /// such as 打ち歩詰め (a mate by a dropped pawn), 二歩 and leaving the king in check.
+    ///
+    /// The moves are checked in order, spending steps of `budget` as [`Budget`] describes,
+    /// and the first one that is not legal is reported. The initial position is not checked.
+    ///
+    /// # Errors
+    /// - [`RecordError::IllegalMove`] with the reason if a move is not legal.
+    /// - [`RecordError::Inconclusive`] if `budget` runs out first, with the number of moves found legal.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::{IllegalMoveKind, Move, PartialPosition, Piece, Square};
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{Budget, GameRecord, RecordError};
+    /// let initial = PartialPosition::from_usi("sfen 3nkn3/9/4S4/9/9/9/9/9/4K4 b P 1").unwrap();
+    /// let mut record = GameRecord::new(initial);
+    /// record.push(Move::Drop {
+    ///     piece: Piece::B_P,
+    ///     to: Square::SQ_5B,
+    /// });
+    /// assert_eq!(
+    ///     record.check_legality(&mut Budget::new(1000)),
+    ///     Err(RecordError::IllegalMove {
+    ///         index: 0,
+    ///         kind: IllegalMoveKind::DropPawnMate,
+    ///     }),
+    /// );
+    /// assert!(matches!(
+    ///     record.check_legality(&mut Budget::new(10)),
+    ///     Err(RecordError::Inconclusive(_)),
+    /// ));
+    /// ```
+    pub fn check_legality(&self, budget: &mut Budget) -> Result<(), RecordError> {
+        let mut position = self.initial.clone();
+        for (index, mv) in self.moves().enumerate() {
+            budget
+                .check_legal(&position, mv, index)
+                .map_err(RecordError::Inconclusive)?
+                .map_err(|kind| RecordError::IllegalMove { index, kind })?;
+            // Legal moves can always be made, unless the ply overflows.
+            position
+                .make_move(mv)
+                .ok_or(RecordError::InvalidMove(index))?;
+        }
+        Ok(())
+    }
+
+    /// Checks that the initial position has the pieces of a set and that every move can be made.
+    ///
+    /// Moves neither add nor remove pieces, so only the initial position is checked with [`material_conservation_check`](crate::material_conservation_check).
+    /// Files converted from hand-edited SFEN often break this silently.
+    /// This is the same as [`GameRecord::validate_with`] with [`KingPolicy::Strict`].
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::PartialPosition;
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{GameRecord, RecordError};
+    /// let record = GameRecord::new(PartialPosition::startpos());
+    /// assert_eq!(record.validate(), Ok(()));
+    /// let initial = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b P 1").unwrap();
+    /// let record = GameRecord::new(initial);
+    /// assert!(matches!(record.validate(), Err(RecordError::Material(_))));
+    /// ```
+    pub fn validate(&self) -> Result<(), RecordError> {
+        self.validate_with(KingPolicy::Strict)
+    }
+
+    /// Same as [`GameRecord::validate`], but treats positions without the kings of a game as `policy` says.
+    ///
+    /// The number of kings is checked before the other pieces, so a missing king is reported as [`RecordError::Kings`].
+    /// Writers do not check the kings, so records that fail here are still written.
+    ///
+    /// Examples:
+    /// ```
+    /// # use shogi_core::{Color, PartialPosition};
+    /// # use shogi_usi_parser::FromUsi;
+    /// # use shogi_official_kifu::{GameRecord, KingPolicy, RecordError};
+    /// // A tsume problem: Black has no king and most pieces are in the box.
+    /// let initial = PartialPosition::from_usi("sfen 4k4/9/4P4/9/9/9/9/9/9 b G 1").unwrap();
+    /// let record = GameRecord::new(initial);
+    /// assert_eq!(
+    ///     record.validate(),
+    ///     Err(RecordError::Kings {
+    ///         color: Color::Black,
+    ///         count: 0
+    ///     }),
+    /// );
+    /// assert_eq!(record.validate_with(KingPolicy::AllowMissing), Ok(()));
+    /// ```
+    pub fn validate_with(&self, policy: KingPolicy) -> Result<(), RecordError> {
+        for color in Color::all() {
+            let count = material::king_count(&self.initial, color);
+            let regular = match policy {
+                KingPolicy::Strict => count == 1,
+                KingPolicy::AllowMissing => count <= 1,
+            };
+            if !regular {
+                return Err(RecordError::Kings { color, count });
+            }
+        }
+        material::check(&self.initial, policy == KingPolicy::AllowMissing)
+            .map_err(RecordError::Material)?;
+        let mut position = self.initial.clone();
+        for (index, mv) in self.moves().enumerate() {
+            position
+                .make_move(mv)
+                .ok_or(RecordError::InvalidMove(index))?;
+        }
+        Ok(())
+    }
+}
+
+/// How [`GameRecord::validate_with`] treats positions without one king on each side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KingPolicy {
+    /// Each side must have one king and every piece of a set must be in play, as in a game.
+    Strict,
+    /// A side may have no king, as the attacker in tsume problems and composed positions,
+    /// and pieces may be left in the box. A side still may not have more than one king,
+    /// nor may there be more pieces of a kind than a set has.
+    AllowMissing,
+}
+
+/// The reason why a [`GameRecord`] is not valid, as found by [`GameRecord::validate`].
+///
+/// Moves are numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RecordError {
+    /// A side of the initial position does not have the number of kings that the [`KingPolicy`] allows.
+    Kings {
+        /// The side.
+        color: Color,
+        /// How many kings the side has on the board.
+        count: u8,
+    },
+    /// The initial position does not have the pieces of a set.
+    Material(MaterialError),
+    /// The move cannot be made.
+    InvalidMove(usize),
+    /// The move is not legal, as found by [`GameRecord::check_legality`].
+    IllegalMove {
+        /// The index of the move.
+        index: usize,
+        /// Why the move is not legal.
+        kind: IllegalMoveKind,
+    },
+    /// The [`Budget`] ran out before every move was checked.
+    Inconclusive(Inconclusive),
+}
+
+impl core::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RecordError::Kings { color, count } => {
+                write!(f, "{} kings of {:?} in the initial position", count, color)
+            }
+            RecordError::Material(error) => write!(f, "invalid initial position: {}", error),
+            RecordError::InvalidMove(index) => write!(f, "invalid move at index {}", index),
+            RecordError::IllegalMove { index, kind } => {
+                write!(f, "illegal move at index {}: {:?}", index, kind)
+            }
+            RecordError::Inconclusive(inconclusive) => inconclusive.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for RecordError {}
+
+/// Returns whether `position` has the pieces of the initial position with Black to move, regardless of the ply and the last move.
+pub(crate) fn is_startpos(position: &PartialPosition) -> bool {
+    let startpos = PartialPosition::startpos();
+    position.side_to_move() == Color::Black
+        && Color::all()
+            .iter()
+            .all(|&c| position.hand_of_a_player(c) == startpos.hand_of_a_player(c))
+        && Square::all().all(|sq| position.piece_at(sq) == startpos.piece_at(sq))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn bookmarks_work() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mut record =
+            GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        record.add_bookmark("中盤の急所", 1);
+        record.add_bookmark("先", 9);
+        record.add_bookmark("中盤の急所", 3);
+        assert_eq!(
+            record.bookmarks().collect::<Vec<_>>(),
+            [("先", 9), ("中盤の急所", 3)]
+        );
+        assert_eq!(
+            record.bookmarked_position("中盤の急所").as_ref(),
+            Some(pos.inner())
+        );
+        assert_eq!(record.bookmarked_position("先"), None);
+        assert_eq!(record.bookmarked_position("後"), None);
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        assert_eq!(record.len(), 5);
+        assert_eq!(record.moves().collect::<Vec<_>>(), pos.moves());
+        assert_eq!(record.moves().next_back(), pos.moves().last().copied());
+        assert_eq!(record.move_at(2), Some(pos.moves()[2]));
+        assert_eq!(record.move_at(5), None);
+        // Storing moves as `CompactMove` is the point of this type.
+        assert!(
+            core::mem::size_of_val(record.compact_moves()) < core::mem::size_of_val(pos.moves())
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let mut record =
+            GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        assert_eq!(record.validate(), Ok(()));
+        // The same drop again, with White to move.
+        record.push(pos.moves()[4]);
+        assert_eq!(record.validate(), Err(RecordError::InvalidMove(5)));
+
+        let initial = PartialPosition::from_usi(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b G 1",
+        )
+        .unwrap();
+        let error = GameRecord::new(initial).validate().unwrap_err();
+        assert_eq!(
+            error,
+            RecordError::Material(MaterialError {
+                piece_kind: shogi_core::PieceKind::Gold,
+                found: 5,
+                expected: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn check_legality_within_budget() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut budget = Budget::new(5);
+        assert_eq!(record.check_legality(&mut budget), Ok(()));
+        assert_eq!(budget.remaining(), 0);
+        assert_eq!(
+            record.repetition_counts_within(&mut budget),
+            Err(Inconclusive { completed: 0 })
+        );
+        assert_eq!(
+            record.check_legality(&mut Budget::new(4)),
+            Err(RecordError::Inconclusive(Inconclusive { completed: 4 }))
+        );
+
+        let initial = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4r4/4KG3 b - 1").unwrap();
+        let mut record = GameRecord::new(initial);
+        // Leaves the king in check, which `make_move` does not notice.
+        record.push(Move::Normal {
+            from: Square::SQ_4I,
+            to: Square::SQ_4H,
+            promote: false,
+        });
+        assert_eq!(
+            record.check_legality(&mut Budget::default()),
+            Err(RecordError::IllegalMove {
+                index: 0,
+                kind: IllegalMoveKind::IgnoredCheck,
+            })
+        );
+        assert_eq!(record.validate_with(KingPolicy::AllowMissing), Ok(()));
+    }
+
+    #[test]
+    fn validate_kings() {
+        // Two kings of White.
+        let initial = PartialPosition::from_usi("sfen 3kk4/9/4P4/9/9/9/9/9/9 b G 1").unwrap();
+        let record = GameRecord::new(initial);
+        let expected = Err(RecordError::Kings {
+            color: Color::White,
+            count: 2,
+        });
+        assert_eq!(record.validate_with(KingPolicy::AllowMissing), expected);
+
+        // Pieces in the box are allowed, but not more pieces than a set has.
+        let initial = PartialPosition::from_usi("sfen 4k4/9/4P4/9/9/9/9/9/9 b 3B 1").unwrap();
+        let record = GameRecord::new(initial);
+        assert!(matches!(
+            record.validate_with(KingPolicy::AllowMissing),
+            Err(RecordError::Material(_))
+        ));
+
+        // Records that fail are still written.
+        let pos = Position::from_usi("sfen 4k4/9/4P4/9/9/9/9/9/9 b G 1 moves G*5b").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        assert_eq!(record.validate_with(KingPolicy::AllowMissing), Ok(()));
+        assert!(record.validate().is_err());
+        assert!(crate::KifFile::new(&record).render().is_some());
+        assert_eq!(crate::ki2::to_string(&record).as_deref(), Some("▲５二金\n"));
+    }
+}
+
+#[cfg(bench)]
+mod benches {
+    extern crate test;
+
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use shogi_core::LegalityChecker;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use test::Bencher;
+
+    /// An allocator that counts the bytes it allocates, to measure the memory that the moves take.
+    struct CountingAllocator;
+
+    static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    /// Returns the result of `f` and the number of bytes allocated while it ran.
+    fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATED.load(Ordering::Relaxed);
+        let value = f();
+        (value, ALLOCATED.load(Ordering::Relaxed) - before)
+    }
+
+    fn sample_moves() -> Vec<Move> {
+        let mut position = PartialPosition::startpos();
+        let mut moves = Vec::new();
+        for _ in 0..200 {
+            let mv = match shogi_legality_lite::LiteLegalityChecker
+                .all_legal_moves_partial(&position)
+                .first()
+            {
+                Some(&mv) => mv,
+                None => break,
+            };
+            position.make_move(mv);
+            moves.push(mv);
+        }
+        moves
+    }
+
+    // The price of the smaller storage is a conversion on every access.
+    #[bench]
+    fn with_moves(b: &mut Bencher) {
+        let moves = sample_moves();
+        b.iter(|| GameRecord::with_moves(PartialPosition::startpos(), moves.iter().copied()));
+    }
+
+    // What the conversions buy: the moves of a record take 2 bytes each, less than in a `Vec<Move>`.
+    // The bytes are printed with `--nocapture`. `b.bytes` is the memory of one copy of the moves,
+    // so the throughput shows how fast each storage is copied.
+    #[bench]
+    fn memory_compact_moves(b: &mut Bencher) {
+        let moves = sample_moves();
+        let (record, allocated) = allocated_by(|| {
+            GameRecord::with_moves(PartialPosition::startpos(), moves.iter().copied())
+        });
+        assert_eq!(allocated, core::mem::size_of_val(record.compact_moves()));
+        assert_eq!(allocated, 2 * moves.len());
+        std::eprintln!("{} moves as CompactMove: {} bytes", moves.len(), allocated);
+        b.bytes = allocated as u64;
+        b.iter(|| record.compact_moves().to_vec());
+    }
+
+    #[bench]
+    fn memory_move_vec(b: &mut Bencher) {
+        let moves = sample_moves();
+        let (copy, allocated) = allocated_by(|| moves.to_vec());
+        assert_eq!(allocated, core::mem::size_of_val(&copy[..]));
+        assert!(allocated > 2 * moves.len());
+        std::eprintln!("{} moves as Move: {} bytes", moves.len(), allocated);
+        b.bytes = allocated as u64;
+        b.iter(|| moves.to_vec());
+    }
+
+    #[bench]
+    fn iterate_moves(b: &mut Bencher) {
+        let record = GameRecord::with_moves(PartialPosition::startpos(), sample_moves());
+        b.iter(|| record.moves().filter(|mv| mv.is_drop()).count());
+    }
+}
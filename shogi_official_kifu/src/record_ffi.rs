@@ -0,0 +1,186 @@
+use alloc::boxed::Box;
+use core::fmt::Write;
+
+use crate::csa::{self, CsaRecord};
+use crate::{
+    write_to_ptr, GameRecord, RenderedMoves, KIFU_ERR_INVALID_RECORD, KIFU_ERR_OUT_OF_RANGE,
+};
+
+/// A record parsed by [`kifu_record_parse_csa`], together with its moves rendered in the official notation.
+///
+/// C code only sees pointers to it and frees it with [`kifu_record_free`].
+#[derive(Clone, Debug)]
+pub struct KifuRecord {
+    csa: CsaRecord,
+    rendered: RenderedMoves,
+}
+
+/// Parses a record in the CSA format and stores a pointer to it in `out`.
+///
+/// Returns 0 on success, or [`KIFU_ERR_INVALID_RECORD`] if `ptr` is not valid UTF-8, not a valid record
+/// or larger than [`ReadLimits::DEFAULT`](crate::ReadLimits::DEFAULT) allows, in which case `out` is left untouched.
+/// [`csa::parse`] does not panic on any input, so malformed records never unwind into C.
+/// The record must be freed with [`kifu_record_free`].
+///
+/// # Safety
+/// `ptr` must be valid for reads of `len` bytes, and `out` must be valid for a write.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_parse_csa(
+    ptr: *const u8,
+    len: usize,
+    out: *mut *mut KifuRecord,
+) -> i32 {
+    let s = match core::str::from_utf8(core::slice::from_raw_parts(ptr, len)) {
+        Ok(s) => s,
+        Err(_) => return KIFU_ERR_INVALID_RECORD,
+    };
+    let csa = match csa::parse(s) {
+        Ok(csa) => csa,
+        Err(_) => return KIFU_ERR_INVALID_RECORD,
+    };
+    let game = GameRecord::with_moves(
+        csa.position.initial_position().clone(),
+        csa.position.moves().iter().copied(),
+    );
+    let mut rendered = RenderedMoves::new();
+    if rendered.push_record(&game).is_none() {
+        return KIFU_ERR_INVALID_RECORD;
+    }
+    *out = Box::into_raw(Box::new(KifuRecord { csa, rendered }));
+    0
+}
+
+/// Frees a record returned by [`kifu_record_parse_csa`]. Does nothing if `record` is null.
+///
+/// # Safety
+/// `record` must be null or a pointer returned by [`kifu_record_parse_csa`] that is not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_free(record: *mut KifuRecord) {
+    if !record.is_null() {
+        drop(Box::from_raw(record));
+    }
+}
+
+/// Writes the name of Black into `ptr`.
+///
+/// Returns the number of bytes written, which is 0 if the name is not given, or a negative `KIFU_ERR_*` code on failure.
+///
+/// # Safety
+/// `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_black_name(
+    record: &KifuRecord,
+    ptr: *mut u8,
+    len: usize,
+) -> i32 {
+    write_str_to_ptr(record.csa.black_name.as_deref().unwrap_or(""), ptr, len)
+}
+
+/// Writes the name of White into `ptr`.
+///
+/// Returns the number of bytes written, which is 0 if the name is not given, or a negative `KIFU_ERR_*` code on failure.
+///
+/// # Safety
+/// `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_white_name(
+    record: &KifuRecord,
+    ptr: *mut u8,
+    len: usize,
+) -> i32 {
+    write_str_to_ptr(record.csa.white_name.as_deref().unwrap_or(""), ptr, len)
+}
+
+/// Writes the special move that ended the game, such as `TORYO`, into `ptr`.
+///
+/// Returns the number of bytes written, which is 0 if the record has no result, or a negative `KIFU_ERR_*` code on failure.
+///
+/// # Safety
+/// `ptr` must be valid for writes of `len` bytes. No terminating NUL is written.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_result(record: &KifuRecord, ptr: *mut u8, len: usize) -> i32 {
+    write_str_to_ptr(record.csa.end.as_deref().unwrap_or(""), ptr, len)
+}
+
+/// Returns the number of moves in `record`.
+#[no_mangle]
+pub extern "C" fn kifu_record_move_count(record: &KifuRecord) -> usize {
+    record.rendered.len()
+}
+
+/// Writes the move at `index`, counting from 0, in the official notation into `ptr`.
+///
+/// Returns the number of bytes written, or a negative `KIFU_ERR_*` code on failure.
+/// [`KIFU_ERR_OUT_OF_RANGE`] is returned if there is no move at `index`.
+///
+/// # Safety
+/// `ptr` must be valid for writes of `len` bytes.
+/// [`MAX_SINGLE_MOVE_BYTES`](crate::MAX_SINGLE_MOVE_BYTES) bytes are always enough.
+/// No terminating NUL is written.
+#[no_mangle]
+pub unsafe extern "C" fn kifu_record_move(
+    record: &KifuRecord,
+    index: usize,
+    ptr: *mut u8,
+    len: usize,
+) -> i32 {
+    match record.rendered.get(index) {
+        Some(rendered) => write_str_to_ptr(rendered, ptr, len),
+        None => KIFU_ERR_OUT_OF_RANGE,
+    }
+}
+
+unsafe fn write_str_to_ptr(s: &str, ptr: *mut u8, len: usize) -> i32 {
+    write_to_ptr(ptr, len, |sink| sink.write_str(s).map(Some))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KIFU_ERR_BUFFER_TOO_SMALL, MAX_SINGLE_MOVE_BYTES};
+
+    #[test]
+    fn accessors_work() {
+        let text = "N+Sente\nPI\n+\n+7776FU\n-3334FU\n%TORYO\n";
+        let mut ptr = core::ptr::null_mut();
+        let result = unsafe { kifu_record_parse_csa(text.as_ptr(), text.len(), &mut ptr) };
+        assert_eq!(result, 0);
+        let record = unsafe { &*ptr };
+        let mut buffer = [0u8; MAX_SINGLE_MOVE_BYTES];
+
+        let written = unsafe { kifu_record_black_name(record, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(&buffer[..written as usize], b"Sente");
+        let written = unsafe { kifu_record_white_name(record, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(written, 0);
+        let written = unsafe { kifu_record_result(record, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(&buffer[..written as usize], b"TORYO");
+
+        assert_eq!(kifu_record_move_count(record), 2);
+        let written = unsafe { kifu_record_move(record, 1, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(&buffer[..written as usize], "△３４歩".as_bytes());
+        let written = unsafe { kifu_record_move(record, 2, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(written, KIFU_ERR_OUT_OF_RANGE);
+        let written = unsafe { kifu_record_move(record, 1, buffer.as_mut_ptr(), 3) };
+        assert_eq!(written, KIFU_ERR_BUFFER_TOO_SMALL);
+
+        unsafe { kifu_record_free(ptr) };
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut record = core::ptr::null_mut();
+        for text in [
+            &b"+7776FU\n"[..],
+            &b"PI\n+\n+5554FU\n"[..],
+            &b"N+\xff\n"[..],
+            // These used to panic in csa::parse, which would unwind across the C boundary.
+            "Pあ\n+\n".as_bytes(),
+            "P1あ\n+\n".as_bytes(),
+        ] {
+            let result = unsafe { kifu_record_parse_csa(text.as_ptr(), text.len(), &mut record) };
+            assert_eq!(result, KIFU_ERR_INVALID_RECORD);
+            assert!(record.is_null());
+        }
+        unsafe { kifu_record_free(record) };
+    }
+}
@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+use shogi_core::PartialPosition;
+
+use crate::{display_single_move, GameRecord};
+
+/// A view of a [`GameRecord`] that renders moves at any ply without replaying the whole game.
+///
+/// Positions are cached every `interval` plies as they are needed,
+/// so rendering a move replays at most `interval - 1` moves after the first access to its neighbourhood.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, RecordView};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut view = RecordView::new(&record, 2);
+/// assert_eq!(view.render_move_at(3), Some("△同銀".to_string()));
+/// assert_eq!(view.render_move_at(0), Some("▲７６歩".to_string()));
+/// assert_eq!(view.render_move_at(4), None);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RecordView<'a> {
+    record: &'a GameRecord,
+    interval: usize,
+    // checkpoints[i] is the position before the move at ply i * interval.
+    checkpoints: Vec<PartialPosition>,
+}
+
+impl<'a> RecordView<'a> {
+    /// Creates a [`RecordView`] that caches a position every `interval` plies.
+    ///
+    /// An `interval` of 0 is treated as 1.
+    pub fn new(record: &'a GameRecord, interval: usize) -> Self {
+        Self {
+            record,
+            interval: interval.max(1),
+            checkpoints: alloc::vec![record.initial().clone()],
+        }
+    }
+
+    /// Returns the underlying [`GameRecord`].
+    pub fn record(&self) -> &'a GameRecord {
+        self.record
+    }
+
+    /// Finds the string representation of the move at `ply`.
+    ///
+    /// `ply` counts from 0, so `render_move_at(0)` renders the first move.
+    /// Returns [`None`] if there is no move at `ply`, or the moves up to `ply` cannot be made.
+    pub fn render_move_at(&mut self, ply: usize) -> Option<alloc::string::String> {
+        let mv = self.record.move_at(ply)?;
+        let position = self.position_before(ply)?;
+        display_single_move(&position, mv)
+    }
+
+    /// Returns the position right before the move at `ply`.
+    ///
+    /// `position_before(record.len())` is the final position.
+    pub(crate) fn position_before(&mut self, ply: usize) -> Option<PartialPosition> {
+        let moves = self.record.compact_moves();
+        if ply > moves.len() {
+            return None;
+        }
+        let index = ply / self.interval;
+        while self.checkpoints.len() <= index {
+            let start = (self.checkpoints.len() - 1) * self.interval;
+            let mut position = self.checkpoints.last()?.clone();
+            for &mv in &moves[start..start + self.interval] {
+                position.make_move(mv.into())?;
+            }
+            self.checkpoints.push(position);
+        }
+        let mut position = self.checkpoints[index].clone();
+        for &mv in &moves[index * self.interval..ply] {
+            position.make_move(mv.into())?;
+        }
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn render_move_at_matches_replay() {
+        let pos = Position::from_usi(
+            "startpos moves 2g2f 8c8d 2f2e 8d8e 2e2d 2c2d 2h2d 8e8f 8g8f 8b8f 2d2c+ 3a3b",
+        )
+        .unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut expected = Vec::new();
+        let mut position = record.initial().clone();
+        for mv in record.moves() {
+            expected.push(display_single_move(&position, mv).unwrap());
+            position.make_move(mv).unwrap();
+        }
+        for interval in [0, 1, 3, 5, 100] {
+            let mut view = RecordView::new(&record, interval);
+            // Backwards, so that the first access fills every checkpoint.
+            for (ply, expected) in expected.iter().enumerate().rev() {
+                assert_eq!(view.render_move_at(ply).as_ref(), Some(expected));
+            }
+            for (ply, expected) in expected.iter().enumerate() {
+                assert_eq!(view.render_move_at(ply).as_ref(), Some(expected));
+            }
+            assert_eq!(view.render_move_at(expected.len()), None);
+            assert_eq!(view.position_before(expected.len()), Some(position.clone()));
+        }
+    }
+}
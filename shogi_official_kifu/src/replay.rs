@@ -0,0 +1,182 @@
+use alloc::vec::Vec;
+use shogi_core::{Move, PartialPosition, Piece};
+
+use crate::GameRecord;
+
+/// A cursor that steps through a [`GameRecord`] in both directions.
+///
+/// Stepping back undoes the last move, putting a captured piece back on the board,
+/// so it does not replay the game from the start.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, Replay};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut replay = Replay::new(&record);
+/// replay.seek(4).unwrap();
+/// assert_eq!(replay.position(), pos.inner());
+/// replay.back().unwrap();
+/// replay.back().unwrap();
+/// assert!(replay.position().piece_at(Square::SQ_2B).is_some());
+/// assert_eq!(replay.ply(), 2);
+/// assert_eq!(replay.position_before(1).map(|position| position.ply()), Some(2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Replay<'a> {
+    record: &'a GameRecord,
+    position: PartialPosition,
+    ply: usize,
+    // captured[i] is the piece captured by the move at ply i.
+    captured: Vec<Option<Piece>>,
+}
+
+impl<'a> Replay<'a> {
+    /// Creates a [`Replay`] at the initial position of `record`.
+    pub fn new(record: &'a GameRecord) -> Self {
+        Self {
+            record,
+            position: record.initial().clone(),
+            ply: 0,
+            captured: Vec::new(),
+        }
+    }
+
+    /// Returns the number of moves made so far.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Returns the current position, which is the one right before the move at [`Replay::ply`].
+    pub fn position(&self) -> &PartialPosition {
+        &self.position
+    }
+
+    /// Makes the next move, and returns it.
+    ///
+    /// Returns [`None`] without moving if there are no more moves or the next move cannot be made.
+    pub fn forward(&mut self) -> Option<Move> {
+        let mv = self.record.move_at(self.ply)?;
+        let captured = match mv {
+            Move::Normal { to, .. } => self.position.piece_at(to),
+            Move::Drop { .. } => None,
+        };
+        let mut next = self.position.clone();
+        next.make_move(mv)?;
+        self.position = next;
+        self.captured.push(captured);
+        self.ply += 1;
+        Some(mv)
+    }
+
+    /// Undoes the last move, and returns it.
+    ///
+    /// Returns [`None`] without moving if this is at the initial position.
+    pub fn back(&mut self) -> Option<Move> {
+        let ply = self.ply.checked_sub(1)?;
+        let mv = self.record.move_at(ply)?;
+        let captured = self.captured[ply];
+        let last_move = match ply.checked_sub(1) {
+            Some(previous) => self.record.move_at(previous),
+            None => self.record.initial().last_move(),
+        };
+        let mut previous = self.position.clone();
+        unmake_move(&mut previous, mv, captured, last_move)?;
+        self.position = previous;
+        self.captured.pop();
+        self.ply = ply;
+        Some(mv)
+    }
+
+    /// Moves forward or back until [`Replay::ply`] is `ply`.
+    ///
+    /// Returns [`None`] if `ply` is out of range or a move on the way cannot be made,
+    /// in which case this stops where the move failed.
+    pub fn seek(&mut self, ply: usize) -> Option<()> {
+        if ply > self.record.len() {
+            return None;
+        }
+        while self.ply < ply {
+            self.forward()?;
+        }
+        while self.ply > ply {
+            self.back()?;
+        }
+        Some(())
+    }
+
+    /// Returns the position right before the move at `ply`, moving this cursor there.
+    ///
+    /// `position_before(record.len())` is the final position.
+    pub fn position_before(&mut self, ply: usize) -> Option<&PartialPosition> {
+        self.seek(ply)?;
+        Some(&self.position)
+    }
+}
+
+/// Undoes `mv`, which captured `captured`, and sets the last move to `last_move`.
+fn unmake_move(
+    position: &mut PartialPosition,
+    mv: Move,
+    captured: Option<Piece>,
+    last_move: Option<Move>,
+) -> Option<()> {
+    let side = position.side_to_move().flip();
+    match mv {
+        Move::Normal { from, to, promote } => {
+            let moved = position.piece_at(to)?;
+            let original = if promote { moved.unpromote()? } else { moved };
+            if let Some(captured) = captured {
+                let piece_kind = captured.piece_kind();
+                let piece_kind = piece_kind.unpromote().unwrap_or(piece_kind);
+                let hand = position.hand_of_a_player_mut(side);
+                *hand = hand.removed(piece_kind)?;
+            }
+            position.piece_set(from, Some(original));
+            position.piece_set(to, captured);
+        }
+        Move::Drop { piece, to } => {
+            position.piece_set(to, None);
+            let hand = position.hand_of_a_player_mut(side);
+            *hand = hand.added(piece.piece_kind())?;
+        }
+    }
+    position.side_to_move_set(side);
+    position.ply_set(position.ply().checked_sub(1)?)?;
+    position.last_move_set(last_move);
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn back_restores_every_position() {
+        let pos = Position::from_usi(
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1 moves 7g7f 3c3d 8h2b+ 3a2b B*4e 2b3c 4e3d 3c3d",
+        )
+        .unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut expected = alloc::vec![record.initial().clone()];
+        let mut position = record.initial().clone();
+        for mv in record.moves() {
+            position.make_move(mv).unwrap();
+            expected.push(position.clone());
+        }
+        let mut replay = Replay::new(&record);
+        assert_eq!(replay.back(), None);
+        replay.seek(record.len()).unwrap();
+        assert_eq!(replay.forward(), None);
+        for ply in (0..record.len()).rev() {
+            assert_eq!(replay.back(), record.move_at(ply));
+            assert_eq!(replay.position(), &expected[ply]);
+        }
+        assert_eq!(replay.position_before(3), Some(&expected[3]));
+        assert_eq!(replay.position_before(record.len() + 1), None);
+    }
+}
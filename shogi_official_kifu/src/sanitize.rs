@@ -0,0 +1,99 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+/// The characters that [`write_kif_escaped`] escapes, with what it writes for them.
+///
+/// Besides `\`, these are the characters that end a line in some editor.
+const KIF_ESCAPES: [(char, &str); 8] = [
+    ('\\', "\\\\"),
+    ('\n', "\\n"),
+    ('\r', "\\r"),
+    ('\u{b}', "\\u{b}"),
+    ('\u{c}', "\\u{c}"),
+    ('\u{85}', "\\u{85}"),
+    ('\u{2028}', "\\u{2028}"),
+    ('\u{2029}', "\\u{2029}"),
+];
+
+/// Returns what [`write_kif_escaped`] writes for `c`, or [`None`] if `c` is written as it is.
+pub(crate) fn kif_escape(c: char) -> Option<&'static str> {
+    KIF_ESCAPES
+        .iter()
+        .find(|&&(escaped, _)| escaped == c)
+        .map(|&(_, escape)| escape)
+}
+
+/// Writes `s` so that it stays on one line of a KIF document, as described in [`KifFile`](crate::KifFile).
+pub(crate) fn write_kif_escaped<W: Write>(s: &str, w: &mut W) -> core::fmt::Result {
+    for c in s.chars() {
+        match kif_escape(c) {
+            Some(escape) => w.write_str(escape)?,
+            None => w.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Reverses [`write_kif_escaped`]. A `\` that does not start an escape is kept as it is.
+pub(crate) fn unescape_kif(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(index) = rest.find('\\') {
+        ret.push_str(&rest[..index]);
+        rest = &rest[index..];
+        match KIF_ESCAPES
+            .iter()
+            .find(|&&(_, escape)| rest.starts_with(escape))
+        {
+            Some(&(c, escape)) => {
+                ret.push(c);
+                rest = &rest[escape.len()..];
+            }
+            None => {
+                ret.push('\\');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// Returns whether `s` seems to contain an email address: `@` between a local part and a domain with a dot.
+pub(crate) fn contains_email(s: &str) -> bool {
+    s.match_indices('@').any(|(index, _)| {
+        let local = s[..index].chars().next_back();
+        let domain = s[index + 1..]
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or("");
+        local.map_or(false, |c| !c.is_whitespace())
+            && domain.split('.').filter(|label| !label.is_empty()).count() >= 2
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_email_works() {
+        assert!(contains_email("taro@example.com"));
+        assert!(contains_email("連絡先 taro@example.com まで"));
+        assert!(!contains_email("@example.com"));
+        assert!(!contains_email("taro@localhost"));
+        assert!(!contains_email("第1局"));
+    }
+
+    #[test]
+    fn kif_escape_round_trips() {
+        let text = "a\\b\nc\r\u{2028}\\n";
+        let mut escaped = String::new();
+        write_kif_escaped(text, &mut escaped).unwrap();
+        assert_eq!(escaped, "a\\\\b\\nc\\r\\u{2028}\\\\n");
+        assert!(!escaped.contains(['\n', '\r', '\u{2028}']));
+        assert_eq!(unescape_kif(&escaped), text);
+        // Backslashes written by other software are kept.
+        assert_eq!(unescape_kif("C:\\kifu\\"), "C:\\kifu\\");
+    }
+}
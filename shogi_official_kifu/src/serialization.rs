@@ -0,0 +1,254 @@
+use alloc::{format, string::String, vec::Vec};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use shogi_core::{Color, Move, PartialPosition, Piece, Square, ToUsi};
+use shogi_usi_parser::FromUsi;
+
+use crate::{
+    csa::{piece_kind_from_csa, piece_kind_to_csa},
+    GameRecord, KifuMove, Modifier,
+};
+
+// shogi_core has no serde support, so its types are written as the strings of USI and CSA.
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Side {
+    Black,
+    White,
+}
+
+/// A [`KifuMove`] as it is serialized, such as
+/// `{"side":"black","from":"6i","to":"5h","capture":false,"same":false,"piece":"GI","relative":"左","movement":null,"drop":false,"promotion":null}`.
+#[derive(Serialize, Deserialize)]
+struct KifuMoveRepr {
+    side: Side,
+    from: Option<String>,
+    to: String,
+    capture: bool,
+    same: bool,
+    piece: String,
+    relative: Option<char>,
+    movement: Option<char>,
+    drop: bool,
+    promotion: Option<bool>,
+}
+
+impl Serialize for KifuMove {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        KifuMoveRepr {
+            side: match self.side {
+                Color::Black => Side::Black,
+                Color::White => Side::White,
+            },
+            from: self.from.map(|from| from.to_usi_owned()),
+            to: self.to.to_usi_owned(),
+            capture: self.capture,
+            same: self.same,
+            piece: piece_kind_to_csa(self.piece_kind).into(),
+            relative: self.relative,
+            movement: self.movement,
+            drop: self.drop,
+            promotion: self.promotion,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for KifuMove {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = KifuMoveRepr::deserialize(deserializer)?;
+        let from = match repr.from {
+            Some(from) => Some(square(&from)?),
+            None => None,
+        };
+        let piece_kind = piece_kind_from_csa(&repr.piece)
+            .ok_or_else(|| D::Error::custom(format!("invalid piece: {}", repr.piece)))?;
+        if let Some(c) = repr.relative {
+            if !matches!(
+                Modifier::from_char(c),
+                Some(Modifier::Left | Modifier::Right | Modifier::Straight)
+            ) {
+                return Err(D::Error::custom(format!(
+                    "invalid relative position: {}",
+                    c
+                )));
+            }
+        }
+        if let Some(c) = repr.movement {
+            if !matches!(
+                Modifier::from_char(c),
+                Some(Modifier::Up | Modifier::Down | Modifier::Sideways)
+            ) {
+                return Err(D::Error::custom(format!("invalid movement: {}", c)));
+            }
+        }
+        if from.is_none() && (repr.promotion.is_some() || repr.capture || repr.same) {
+            return Err(D::Error::custom("a drop cannot promote or capture"));
+        }
+        if from.is_some() && repr.drop {
+            return Err(D::Error::custom("a normal move cannot have 打"));
+        }
+        Ok(KifuMove {
+            side: match repr.side {
+                Side::Black => Color::Black,
+                Side::White => Color::White,
+            },
+            from,
+            to: square(&repr.to)?,
+            capture: repr.capture,
+            same: repr.same,
+            piece_kind,
+            relative: repr.relative,
+            movement: repr.movement,
+            drop: repr.drop,
+            promotion: repr.promotion,
+        })
+    }
+}
+
+fn square<E: Error>(s: &str) -> Result<Square, E> {
+    Square::from_usi(s).map_err(|_| E::custom(format!("invalid square: {}", s)))
+}
+
+/// A [`GameRecord`] as it is serialized, such as `{"initial":"sfen lnsgkgsnl/... b - 1","moves":["7g7f","3c3d"]}`.
+///
+/// Positions in USI starting with `startpos` are also read.
+/// Bookmarks are written as `"bookmarks":[["中盤の急所",40]]` only if there are any.
+#[derive(Serialize, Deserialize)]
+struct GameRecordRepr {
+    initial: String,
+    moves: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    bookmarks: Vec<(String, usize)>,
+}
+
+impl Serialize for GameRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRecordRepr {
+            initial: self.initial().to_usi_owned(),
+            moves: self.moves().map(|mv| mv.to_usi_owned()).collect(),
+            bookmarks: self.bookmarks.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GameRecordRepr::deserialize(deserializer)?;
+        let initial = PartialPosition::from_usi(&repr.initial)
+            .map_err(|_| D::Error::custom(format!("invalid position: {}", repr.initial)))?;
+        let mut record = GameRecord::new(initial.clone());
+        let mut side = initial.side_to_move();
+        for (index, usi) in repr.moves.iter().enumerate() {
+            let mv = Move::from_usi(usi)
+                .map_err(|_| D::Error::custom(format!("invalid move {}: {}", index, usi)))?;
+            // USI does not tell whose piece is dropped.
+            record.push(match mv {
+                Move::Drop { piece, to } => Move::Drop {
+                    piece: Piece::new(piece.piece_kind(), side),
+                    to,
+                },
+                mv => mv,
+            });
+            side = side.flip();
+        }
+        record.bookmarks = repr.bookmarks;
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analyze_single_move, parse_kif, GameTree, KifRecord, SpecialMove};
+    use shogi_core::{LegalityChecker, Position};
+    use shogi_legality_lite::LiteLegalityChecker;
+
+    #[test]
+    fn kifu_move_round_trips() {
+        let sfens = [
+            "sfen 4k4/9/9/9/9/9/9/9/3S1S2K b - 1",
+            "sfen lnsgkgsnl/1r5+B1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL w B 4",
+        ];
+        for sfen in sfens {
+            let pos = PartialPosition::from_usi(sfen).unwrap();
+            for mv in LiteLegalityChecker.all_legal_moves_partial(&pos) {
+                let kifu_move = analyze_single_move(&pos, mv).unwrap();
+                let json = serde_json::to_string(&kifu_move).unwrap();
+                assert_eq!(serde_json::from_str::<KifuMove>(&json).unwrap(), kifu_move);
+            }
+        }
+        let pos = PartialPosition::from_usi(sfens[0]).unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_6I,
+            to: Square::SQ_5H,
+            promote: false,
+        };
+        assert_eq!(
+            serde_json::to_string(&analyze_single_move(&pos, mv).unwrap()).unwrap(),
+            r#"{"side":"black","from":"6i","to":"5h","capture":false,"same":false,"piece":"GI","relative":"左","movement":null,"drop":false,"promotion":null}"#,
+        );
+        let invalid = r#"{"side":"black","from":"6i","to":"5h","capture":false,"same":false,"piece":"GI","relative":"上","movement":null,"drop":false,"promotion":null}"#;
+        assert!(serde_json::from_str::<KifuMove>(invalid).is_err());
+    }
+
+    #[test]
+    fn game_record_round_trips() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.ends_with(r#"b - 1","moves":["7g7f","3c3d","8h2b+","3a2b","B*4e"]}"#));
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), record);
+        let mut record = record;
+        record.add_bookmark("中盤の急所", 5);
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(
+            json.ends_with(r#""bookmarks":[["中盤の急所",5]]}"#),
+            "{}",
+            json
+        );
+        assert_eq!(serde_json::from_str::<GameRecord>(&json).unwrap(), record);
+
+        let record: GameRecord = serde_json::from_str(
+            r#"{"initial":"startpos","moves":["7g7f","3c3d","8h2b+","3a2b","B*4e","B*6e"]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            record.move_at(5),
+            Some(Move::Drop {
+                piece: Piece::W_B,
+                to: Square::SQ_6E,
+            })
+        );
+        assert!(
+            serde_json::from_str::<GameRecord>(r#"{"initial":"startpos","moves":["7g7z"]}"#)
+                .is_err()
+        );
+
+        let tree = GameTree::new(record);
+        let json = serde_json::to_string(&tree).unwrap();
+        assert_eq!(serde_json::from_str::<GameTree>(&json).unwrap(), tree);
+    }
+
+    #[test]
+    fn kif_record_round_trips() {
+        let text = "先手：羽生善治
+手数----指手---------消費時間--
+   1 ７六歩(77)
+*角道を開ける
+   2 投了
+";
+        let kif = parse_kif(text).unwrap();
+        let json = serde_json::to_string(&kif).unwrap();
+        assert_eq!(serde_json::from_str::<KifRecord>(&json).unwrap(), kif);
+        assert_eq!(
+            serde_json::to_string(&SpecialMove::Resign).unwrap(),
+            r#""Resign""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Modifier>(r#""Left""#).unwrap(),
+            Modifier::Left
+        );
+    }
+}
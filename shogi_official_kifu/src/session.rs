@@ -0,0 +1,184 @@
+use alloc::{string::String, vec::Vec};
+use shogi_core::{LegalityChecker, Move, PartialPosition};
+use shogi_legality_lite::LiteLegalityChecker;
+
+use crate::{try_display_single_move_with_style, KifuFormatter, KifuWriterError};
+
+/// A game in progress whose moves can be taken back, together with their notation.
+///
+/// Every position is kept, so [`KifuSession::pop`] restores the ply and the previous move exactly,
+/// and a move pushed after it is written with `同` as if the taken back move had never been made.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{KifuSession, KifuWriterError};
+/// let mut session = KifuSession::new(PartialPosition::startpos());
+/// for (from, to, promote) in [
+///     (Square::SQ_7G, Square::SQ_7F, false),
+///     (Square::SQ_3C, Square::SQ_3D, false),
+///     (Square::SQ_8H, Square::SQ_2B, true),
+///     (Square::SQ_3A, Square::SQ_2B, false),
+/// ] {
+///     session.push(Move::Normal { from, to, promote }).unwrap();
+/// }
+/// assert_eq!(session.notation(3), Some("△同銀"));
+/// // Take back 3a2b. It is White's move again.
+/// session.pop();
+/// assert_eq!(session.ply(), 4);
+/// let mv = Move::Normal {
+///     from: Square::SQ_7F,
+///     to: Square::SQ_7E,
+///     promote: false,
+/// };
+/// assert!(matches!(session.push(mv), Err(KifuWriterError::Illegal(_))));
+/// let mv = Move::Normal {
+///     from: Square::SQ_3A,
+///     to: Square::SQ_2B,
+///     promote: false,
+/// };
+/// assert_eq!(session.push(mv), Ok("△同銀"));
+/// assert_eq!(session.len(), 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct KifuSession {
+    formatter: KifuFormatter,
+    // positions[i] is the position before moves[i], and the last one is the current position.
+    positions: Vec<PartialPosition>,
+    moves: Vec<Move>,
+    notations: Vec<String>,
+}
+
+impl KifuSession {
+    /// Creates a [`KifuSession`] that starts from `position` and writes moves in the official notation.
+    pub fn new(position: PartialPosition) -> Self {
+        Self {
+            formatter: KifuFormatter::new(),
+            positions: alloc::vec![position],
+            moves: Vec::new(),
+            notations: Vec::new(),
+        }
+    }
+
+    /// Sets the [`KifuFormatter`] that moves are written with.
+    ///
+    /// Moves already pushed keep the notation they were written in.
+    pub fn formatter(mut self, formatter: KifuFormatter) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Returns the current position.
+    pub fn position(&self) -> &PartialPosition {
+        // positions always has the initial position.
+        &self.positions[self.positions.len() - 1]
+    }
+
+    /// Returns the position the session started from.
+    pub fn initial(&self) -> &PartialPosition {
+        &self.positions[0]
+    }
+
+    /// Returns the ply of the next move.
+    pub fn ply(&self) -> u16 {
+        self.position().ply()
+    }
+
+    /// Returns the number of moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Returns whether there are no moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Returns the moves in the order they were made.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Returns the notation of the `index`-th move, counting from 0.
+    pub fn notation(&self, index: usize) -> Option<&str> {
+        self.notations.get(index).map(String::as_str)
+    }
+
+    /// Returns the notations of the moves in the order they were made.
+    pub fn notations(&self) -> impl DoubleEndedIterator<Item = &str> + ExactSizeIterator + '_ {
+        self.notations.iter().map(String::as_str)
+    }
+
+    /// Makes `mv` and returns its notation.
+    ///
+    /// # Errors
+    /// - [`KifuWriterError::Illegal`] if `mv` is not legal in the current position.
+    /// - [`KifuWriterError::Unwritable`] if `mv` cannot be written.
+    /// - [`KifuWriterError::PlyOverflow`] if the current position is already at ply [`u16::MAX`].
+    ///
+    /// The session is left as it is on errors. [`KifuWriterError::Write`] is never returned.
+    pub fn push(&mut self, mv: Move) -> Result<&str, KifuWriterError> {
+        let position = self.position();
+        if position.ply() == u16::MAX {
+            return Err(KifuWriterError::PlyOverflow);
+        }
+        LiteLegalityChecker
+            .is_legal_partial(position, mv)
+            .map_err(KifuWriterError::Illegal)?;
+        let notation = try_display_single_move_with_style(position, mv, self.formatter.style())
+            .map_err(KifuWriterError::Unwritable)?;
+        let mut next = position.clone();
+        // Legal moves can always be made.
+        next.make_move(mv);
+        self.positions.push(next);
+        self.moves.push(mv);
+        self.notations.push(notation);
+        Ok(&self.notations[self.notations.len() - 1])
+    }
+
+    /// Takes back the last move and returns it, or returns [`None`] if there are no moves.
+    pub fn pop(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.positions.pop();
+        self.notations.pop();
+        Some(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{KifuWriter, Style};
+    use shogi_core::Position;
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn pop_restores_context() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b 2g2f").unwrap();
+        let mut session = KifuSession::new(PartialPosition::startpos())
+            .formatter(KifuFormatter::with_style(Style::TRADITIONAL));
+        assert_eq!(session.pop(), None);
+        for &mv in pos.moves() {
+            session.push(mv).unwrap();
+        }
+        assert_eq!(session.pop(), Some(pos.moves()[4]));
+        assert_eq!(session.pop(), Some(pos.moves()[3]));
+        assert_eq!(session.ply(), 4);
+        assert_eq!(session.position().last_move(), Some(pos.moves()[2]));
+        session.push(pos.moves()[3]).unwrap();
+        session.push(pos.moves()[4]).unwrap();
+        assert_eq!(session.position(), pos.inner());
+
+        // Agrees with a writer that never took anything back.
+        let mut writer = KifuWriter::new(PartialPosition::startpos(), String::new())
+            .numbered(false)
+            .formatter(KifuFormatter::with_style(Style::TRADITIONAL));
+        for &mv in pos.moves() {
+            writer.push(mv).unwrap();
+        }
+        assert_eq!(
+            session.notations().collect::<Vec<_>>(),
+            writer.into_inner().lines().collect::<Vec<_>>()
+        );
+    }
+}
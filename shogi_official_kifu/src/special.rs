@@ -0,0 +1,101 @@
+/// A move that ends or suspends a game instead of moving a piece, which [`Move`](shogi_core::Move) cannot represent.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::{display_special_move, SpecialMove};
+/// assert_eq!(display_special_move(SpecialMove::Resign), "投了");
+/// assert_eq!(SpecialMove::Resign.to_csa(), "TORYO");
+/// assert_eq!(SpecialMove::from_csa("SENNICHITE"), Some(SpecialMove::Sennichite));
+/// ```
+/// Ref: <http://kakinoki.o.oo7.jp/kif_format.html>, <http://www2.computer-shogi.org/protocol/record_v22.html>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum SpecialMove {
+    /// `投了` (`%TORYO`): the side to move resigns.
+    Resign,
+    /// `中断` (`%CHUDAN`): the game is suspended.
+    Interrupt,
+    /// `千日手` (`%SENNICHITE`): a draw by repetition.
+    Sennichite,
+    /// `持将棋` (`%JISHOGI`): a draw by impasse.
+    Jishogi,
+    /// `切れ負け` (`%TIME_UP`): the side to move loses on time.
+    TimeUp,
+    /// `反則負け` (`%ILLEGAL_MOVE`): the side that made the last move loses by an illegal move.
+    IllegalMove,
+    /// `入玉勝ち` (`%KACHI`): the side to move declares a win by entering king (入玉宣言).
+    Declaration,
+    /// `詰み` (`%TSUMI`): the side to move is checkmated.
+    Checkmate,
+    /// `不詰` (`%FUZUMI`): the problem has no mate.
+    NoCheckmate,
+}
+
+/// The special moves with their names in KIF and in CSA.
+const NAMES: [(SpecialMove, &str, &str); 9] = [
+    (SpecialMove::Resign, "投了", "TORYO"),
+    (SpecialMove::Interrupt, "中断", "CHUDAN"),
+    (SpecialMove::Sennichite, "千日手", "SENNICHITE"),
+    (SpecialMove::Jishogi, "持将棋", "JISHOGI"),
+    (SpecialMove::TimeUp, "切れ負け", "TIME_UP"),
+    (SpecialMove::IllegalMove, "反則負け", "ILLEGAL_MOVE"),
+    (SpecialMove::Declaration, "入玉勝ち", "KACHI"),
+    (SpecialMove::Checkmate, "詰み", "TSUMI"),
+    (SpecialMove::NoCheckmate, "不詰", "FUZUMI"),
+];
+
+/// Returns how `special_move` is written in KIF and KI2, such as `投了`.
+pub fn display_special_move(special_move: SpecialMove) -> &'static str {
+    NAMES
+        .iter()
+        .find(|&&(s, _, _)| s == special_move)
+        .map(|&(_, kif, _)| kif)
+        .expect("every special move has a name")
+}
+
+impl SpecialMove {
+    /// Returns how `self` is written in CSA without `%`, such as `TORYO`.
+    pub fn to_csa(self) -> &'static str {
+        NAMES
+            .iter()
+            .find(|&&(s, _, _)| s == self)
+            .map(|&(_, _, csa)| csa)
+            .expect("every special move has a name")
+    }
+
+    /// Reads a special move written in CSA without `%`, such as `TORYO`.
+    pub fn from_csa(s: &str) -> Option<Self> {
+        NAMES
+            .iter()
+            .find(|&&(_, _, csa)| csa == s)
+            .map(|&(special_move, _, _)| special_move)
+    }
+
+    /// Reads a special move written in KIF or KI2, such as `投了`.
+    pub fn from_kif(s: &str) -> Option<Self> {
+        NAMES
+            .iter()
+            .find(|&&(_, kif, _)| kif == s)
+            .map(|&(special_move, _, _)| special_move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for &(special_move, _, _) in &NAMES {
+            let kif = display_special_move(special_move);
+            assert_eq!(SpecialMove::from_kif(kif), Some(special_move));
+            assert_eq!(
+                SpecialMove::from_csa(special_move.to_csa()),
+                Some(special_move)
+            );
+        }
+        assert_eq!(SpecialMove::from_csa("%TORYO"), None);
+        assert_eq!(SpecialMove::from_kif("まで"), None);
+    }
+}
@@ -0,0 +1,332 @@
+use shogi_core::{Color, PartialPosition, PieceKind};
+
+use crate::piece_kind_to_kanji;
+
+/// Options that control how moves are written.
+///
+/// [`Style::default()`] gives the official notation, which is what [`display_single_move`](crate::display_single_move) writes.
+///
+/// Examples:
+/// ```
+/// # use shogi_official_kifu::Style;
+/// let mut style = Style::default();
+/// style.write_forced_promotion = false;
+/// assert_ne!(style, Style::OFFICIAL);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Style {
+    /// Whether `成` is written when the promotion is forced (see [`is_promotion_forced`](crate::is_promotion_forced)).
+    ///
+    /// Some publications omit it, because a pawn, a lance or a knight there cannot stay unpromoted anyway.
+    /// Defaults to `true`.
+    pub write_forced_promotion: bool,
+    /// The notation moves are written in.
+    ///
+    /// [`Style::write_forced_promotion`] only affects [`Notation::Japanese`].
+    /// Defaults to [`Notation::Japanese`].
+    pub notation: Notation,
+    /// How the destination is written in [`Notation::Japanese`].
+    ///
+    /// Defaults to [`Coordinates::Arabic`].
+    pub coordinates: Coordinates,
+    /// The marks of the sides in [`Notation::Japanese`].
+    ///
+    /// Defaults to [`SideMarkers::Triangles`].
+    pub side_markers: SideMarkers,
+    /// Whether `同` is written instead of the destination when it is the destination of the last move, in [`Notation::Japanese`].
+    ///
+    /// Text-to-speech and datasets for machine learning may prefer the destination always written out.
+    /// Defaults to `true`.
+    pub write_same: bool,
+    /// Whether `同` is followed by a full-width space before a piece written in one character (`同　金`) in [`Notation::Japanese`].
+    ///
+    /// KIF pads `同` this way so that columns align, and strict KIF readers reject the form without the space.
+    /// Defaults to `false`.
+    pub pad_same: bool,
+    /// Whether `打` is written for every drop in [`Notation::Japanese`].
+    ///
+    /// The official notation writes `打` only if a piece on the board can also move to the destination,
+    /// whereas KIF records write it for every drop.
+    /// Defaults to `false`.
+    pub always_mark_drops: bool,
+    /// Whether every normal move is written with the suffixes that describe how the piece moves
+    /// (`左`, `右` or `直`, and `上`, `引` or `寄`) in [`Notation::Japanese`], even if the move is not ambiguous.
+    ///
+    /// This helps beginners and makes it easier to compare notations between tools.
+    /// Suffixes that the official notation needs are kept, so `▲５８金左` becomes `▲５８金左上`.
+    /// [`parse_single_move`](crate::parse_single_move) does not accept the added suffixes.
+    /// Defaults to `false`.
+    pub full_disambiguation: bool,
+    /// Whether a promoted rook is written in the old form `龍` instead of `竜`.
+    ///
+    /// Defaults to `false`.
+    pub old_form_dragon: bool,
+    /// Whether promoted silvers, knights and lances are written in one character (`全`, `圭`, `杏`)
+    /// instead of two (`成銀`, `成桂`, `成香`).
+    ///
+    /// Board diagrams use the single characters to keep columns aligned, and narrow displays may prefer them too.
+    /// Defaults to `false`.
+    pub single_char_promoted: bool,
+    /// Which kings are written `王` instead of `玉`.
+    ///
+    /// Defaults to [`KingGlyphs::Gyoku`].
+    pub kings: KingGlyphs,
+    /// Whether a capture or a drop is followed by the pieces that the moving side has in hand after it,
+    /// such as `▲２２角成 (持駒: 角)`, in [`Notation::Japanese`].
+    ///
+    /// Viewers without a piece stand can show the hands this way. Only writers that are given the position apply it,
+    /// such as [`display_single_move_with_style`](crate::display_single_move_with_style), [`NotationContext`](crate::NotationContext)
+    /// and the writers of whole games, which track the hands as they make the moves.
+    /// [`KifuMove::display`](crate::KifuMove::display) does not.
+    /// Defaults to `false`.
+    pub hand_changes: bool,
+}
+
+impl Style {
+    /// The official notation.
+    pub const OFFICIAL: Self = Self {
+        write_forced_promotion: true,
+        notation: Notation::Japanese,
+        coordinates: Coordinates::Arabic,
+        side_markers: SideMarkers::Triangles,
+        write_same: true,
+        pad_same: false,
+        always_mark_drops: false,
+        full_disambiguation: false,
+        old_form_dragon: false,
+        single_char_promoted: false,
+        kings: KingGlyphs::Gyoku,
+        hand_changes: false,
+    };
+
+    /// The traditional notation, with ranks in kansuji, which is what [`display_single_move_kansuji`](crate::display_single_move_kansuji) writes.
+    pub const TRADITIONAL: Self = Self {
+        coordinates: Coordinates::KansujiRank,
+        ..Self::OFFICIAL
+    };
+}
+
+impl Style {
+    /// Returns how a piece of `piece_kind` owned by `owner` is written when `side_to_move` is to move.
+    pub(crate) fn piece_name(
+        &self,
+        piece_kind: PieceKind,
+        owner: Color,
+        side_to_move: Color,
+    ) -> &'static str {
+        match piece_kind {
+            PieceKind::ProRook if self.old_form_dragon => "龍",
+            PieceKind::ProSilver if self.single_char_promoted => "全",
+            PieceKind::ProKnight if self.single_char_promoted => "圭",
+            PieceKind::ProLance if self.single_char_promoted => "杏",
+            PieceKind::King => self.kings.glyph(owner, side_to_move),
+            _ => piece_kind_to_kanji(piece_kind),
+        }
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self::OFFICIAL
+    }
+}
+
+/// The notation a [`Style`] writes moves in.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{display_single_move_with_style, Notation, Style};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// let mut style = Style::default();
+/// style.notation = Notation::Western;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("P-7f".to_string()));
+/// style.notation = Notation::Hosking;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("P-76".to_string()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Notation {
+    /// The Japanese notation, such as `▲７六歩`.
+    Japanese,
+    /// The western notation with ranks written as letters, such as `P-7f`.
+    ///
+    /// Same as [`display_single_move_western`](crate::display_single_move_western).
+    Western,
+    /// The convention of "The Art of Shogi" by Tony Hosking, with ranks written as digits, such as `P-76` or `G49-58`.
+    ///
+    /// Other than the ranks, moves are written as in [`Notation::Western`].
+    Hosking,
+    /// Readings in hiragana for text-to-speech, such as `ななろくふ` for `▲７６歩`.
+    ///
+    /// Moves are read as they are written in [`Notation::Japanese`], so [`Style::write_same`], [`Style::always_mark_drops`],
+    /// [`Style::full_disambiguation`], [`Style::write_forced_promotion`] and [`Style::kings`] apply.
+    /// Same as [`display_single_move_yomi`](crate::display_single_move_yomi) with the other options as in [`Style::OFFICIAL`].
+    Yomi,
+}
+
+/// How the destination of a move is written in [`Notation::Japanese`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{display_single_move_with_style, Coordinates, Style};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// let mut style = Style::default();
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲７６歩".to_string()));
+/// style.coordinates = Coordinates::KansujiRank;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲７六歩".to_string()));
+/// style.coordinates = Coordinates::Kansuji;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲七六歩".to_string()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Coordinates {
+    /// Both the file and the rank in full-width Arabic numerals, such as `７６`, as in the official notation.
+    Arabic,
+    /// The file in full-width Arabic numerals and the rank in kansuji, such as `７六`, as in the traditional notation.
+    KansujiRank,
+    /// Both the file and the rank in kansuji, such as `七六`, as in some books printed vertically.
+    Kansuji,
+}
+
+/// The marks of Black and White in [`Notation::Japanese`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{display_single_move_with_style, SideMarkers, Style};
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// let mut style = Style::default();
+/// style.side_markers = SideMarkers::ShogiPieces;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("☗７６歩".to_string()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SideMarkers {
+    /// `▲` and `△`, as in the official notation.
+    Triangles,
+    /// `☗` and `☖`, the shogi pieces in Unicode, preferred by modern publications.
+    ShogiPieces,
+    /// `▲` and `▽`.
+    DownwardTriangle,
+}
+
+impl SideMarkers {
+    /// Returns the marks of Black and White.
+    pub fn marks(self) -> (char, char) {
+        match self {
+            SideMarkers::Triangles => ('▲', '△'),
+            SideMarkers::ShogiPieces => ('☗', '☖'),
+            SideMarkers::DownwardTriangle => ('▲', '▽'),
+        }
+    }
+}
+
+/// Which kings a [`Style`] writes `王` instead of `玉`.
+///
+/// Sets of pieces have a `王将` for one player and a `玉将` for the other, and publications differ in how they write them.
+/// The king that moves always belongs to the side to move.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{display_single_move_with_style, KingGlyphs, Style};
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 1").unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_5I,
+///     to: Square::SQ_5H,
+///     promote: false,
+/// };
+/// let mut style = Style::default();
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲５８玉".to_string()));
+/// style.kings = KingGlyphs::SideToMoveOu;
+/// assert_eq!(display_single_move_with_style(&pos, mv, &style), Some("▲５８王".to_string()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KingGlyphs {
+    /// Both kings are written `玉`, as in the official notation.
+    Gyoku,
+    /// The king of the side to move is written `王`, and the other `玉`.
+    SideToMoveOu,
+    /// The king of the side to move is written `玉`, and the other `王`.
+    OpponentOu,
+    /// Black's king is written `王`, and White's `玉`.
+    BlackOu,
+    /// White's king is written `王`, and Black's `玉`.
+    ///
+    /// By custom the higher-rated player takes the `王将`, and plays White in handicap games.
+    WhiteOu,
+}
+
+impl KingGlyphs {
+    /// Returns how the king of `owner` is written when `side_to_move` is to move.
+    pub fn glyph(self, owner: Color, side_to_move: Color) -> &'static str {
+        let ou = match self {
+            KingGlyphs::Gyoku => false,
+            KingGlyphs::SideToMoveOu => owner == side_to_move,
+            KingGlyphs::OpponentOu => owner != side_to_move,
+            KingGlyphs::BlackOu => owner == Color::Black,
+            KingGlyphs::WhiteOu => owner == Color::White,
+        };
+        if ou {
+            "王"
+        } else {
+            "玉"
+        }
+    }
+}
+
+/// How the moves of a game are numbered when its initial position is not at ply 1,
+/// such as a position taken from a game in a database.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameLayout, GameRecord, Numbering};
+/// let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b G 31 moves G*5b").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let layout = GameLayout::new();
+/// assert_eq!(layout.render(&record).unwrap(), "31 ▲５二金\n");
+/// let layout = layout.numbering(Numbering::Restart);
+/// assert_eq!(layout.render(&record).unwrap(), "1 ▲５二金\n");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Numbering {
+    /// The first move is numbered 1, whatever the ply of the initial position is.
+    Restart,
+    /// The first move is numbered with the ply of the initial position, continuing the game it was taken from.
+    Continue,
+}
+
+impl Numbering {
+    /// Returns the number of the first move made from `initial`.
+    ///
+    /// A position at ply 0 is numbered as if it were at ply 1.
+    pub fn first_ply(self, initial: &PartialPosition) -> usize {
+        match self {
+            Numbering::Restart => 1,
+            Numbering::Continue => usize::from(initial.ply().max(1)),
+        }
+    }
+}
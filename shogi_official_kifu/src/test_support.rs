@@ -0,0 +1,264 @@
+use core::fmt::Write;
+use shogi_core::Position;
+
+use crate::csa::{self, CsaRecord};
+use crate::{display_single_move_with_style, parse_single_move, position_hash, GameRecord, Style};
+
+/// A [`Write`] that accepts at most `limit` bytes and fails afterwards.
+///
+/// A `write_str` call that would exceed the limit fails without writing anything,
+/// so the buffer never ends in the middle of a UTF-8 sequence.
+/// This is useful for checking how a caller behaves when a bounded sink runs out of space.
+///
+/// Examples:
+/// ```
+/// # use core::fmt::Write;
+/// # use shogi_official_kifu::test_support::FailingWriter;
+/// let mut w = FailingWriter::new(4);
+/// assert!(w.write_str("▲").is_ok());
+/// assert!(w.write_str("同").is_err());
+/// assert_eq!(w.as_str(), "▲");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FailingWriter {
+    buffer: alloc::string::String,
+    limit: usize,
+}
+
+impl FailingWriter {
+    /// Creates a [`FailingWriter`] that fails once more than `limit` bytes would have been written.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            buffer: alloc::string::String::new(),
+            limit,
+        }
+    }
+
+    /// Returns what has been successfully written so far.
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Consumes `self` and returns what has been successfully written so far.
+    pub fn into_inner(self) -> alloc::string::String {
+        self.buffer
+    }
+}
+
+impl Write for FailingWriter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        if self.buffer.len() + s.len() > self.limit {
+            return Err(core::fmt::Error);
+        }
+        self.buffer.push_str(s);
+        Ok(())
+    }
+}
+
+/// A format that moves can be written in and read back from, as checked by [`check_round_trip`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Dialect {
+    /// The official notation, written by [`display_single_move`](crate::display_single_move).
+    Official,
+    /// The traditional notation with ranks in kansuji, written with [`Style::TRADITIONAL`].
+    Traditional,
+    /// The CSA format, written by [`CsaRecord::render`].
+    Csa,
+    /// KIF, written by [`KifFile`](crate::KifFile) and read by [`parse_kif`](crate::parse_kif).
+    Kif,
+    /// USEN, written by [`usen::encode`](crate::usen::encode).
+    #[cfg(feature = "usen")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usen")))]
+    Usen,
+}
+
+impl Dialect {
+    /// Returns every dialect enabled by the features of this crate.
+    pub fn all() -> &'static [Dialect] {
+        &[
+            Dialect::Official,
+            Dialect::Traditional,
+            Dialect::Csa,
+            Dialect::Kif,
+            #[cfg(feature = "usen")]
+            Dialect::Usen,
+        ]
+    }
+
+    /// Returns whether the dialect keeps the ply of the initial position.
+    ///
+    /// The CSA format and KIF do not write it, so it is not compared after a round trip.
+    pub fn keeps_ply(self) -> bool {
+        !matches!(self, Dialect::Csa | Dialect::Kif)
+    }
+}
+
+/// The reason why [`check_round_trip`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RoundTripError {
+    /// The move at the index, counting from 0, could not be written or made.
+    Unwritable(usize),
+    /// The move at the index, counting from 0, was read back as a different move or could not be read.
+    MoveMismatch(usize),
+    /// The record as a whole could not be read back.
+    Unreadable,
+    /// The record read back has a different initial position or a different number of moves.
+    RecordMismatch,
+}
+
+impl core::fmt::Display for RoundTripError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RoundTripError::Unwritable(index) => write!(f, "move {} cannot be written", index),
+            RoundTripError::MoveMismatch(index) => {
+                write!(f, "move {} is read back differently", index)
+            }
+            RoundTripError::Unreadable => f.write_str("the record cannot be read back"),
+            RoundTripError::RecordMismatch => f.write_str("the record is read back differently"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for RoundTripError {}
+
+/// Checks that writing `record` in `dialect` and reading it back gives `record` again,
+/// as far as [`Dialect::keeps_ply`] says the dialect keeps it.
+///
+/// Only legal moves are guaranteed to survive, because [`parse_single_move`] only returns legal moves.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::GameRecord;
+/// # use shogi_official_kifu::test_support::{check_round_trip, Dialect};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// for &dialect in Dialect::all() {
+///     assert_eq!(check_round_trip(&record, dialect), Ok(()));
+/// }
+/// ```
+pub fn check_round_trip(record: &GameRecord, dialect: Dialect) -> Result<(), RoundTripError> {
+    let style = match dialect {
+        Dialect::Official => Style::OFFICIAL,
+        Dialect::Traditional => Style::TRADITIONAL,
+        Dialect::Csa => return check_csa_round_trip(record),
+        Dialect::Kif => return check_kif_round_trip(record),
+        #[cfg(feature = "usen")]
+        Dialect::Usen => {
+            let decoded = crate::usen::decode(&crate::usen::encode(record))
+                .map_err(|_| RoundTripError::Unreadable)?;
+            return compare_records(record, &decoded, true);
+        }
+    };
+    let mut position = record.initial().clone();
+    for (index, mv) in record.moves().enumerate() {
+        let rendered = display_single_move_with_style(&position, mv, &style)
+            .ok_or(RoundTripError::Unwritable(index))?;
+        if parse_single_move(&position, &rendered) != Ok(mv) {
+            return Err(RoundTripError::MoveMismatch(index));
+        }
+        position
+            .make_move(mv)
+            .ok_or(RoundTripError::Unwritable(index))?;
+    }
+    Ok(())
+}
+
+fn check_csa_round_trip(record: &GameRecord) -> Result<(), RoundTripError> {
+    let mut position = Position::arbitrary_position(record.initial().clone());
+    for (index, mv) in record.moves().enumerate() {
+        position
+            .make_move(mv)
+            .ok_or(RoundTripError::Unwritable(index))?;
+    }
+    let rendered = CsaRecord::new(position)
+        .render()
+        .ok_or(RoundTripError::Unreadable)?;
+    let parsed = csa::parse(&rendered).map_err(|_| RoundTripError::Unreadable)?;
+    let parsed = GameRecord::with_moves(
+        parsed.position.initial_position().clone(),
+        parsed.position.moves().iter().copied(),
+    );
+    compare_records(record, &parsed, Dialect::Csa.keeps_ply())
+}
+
+fn check_kif_round_trip(record: &GameRecord) -> Result<(), RoundTripError> {
+    let mut position = record.initial().clone();
+    for (index, mv) in record.moves().enumerate() {
+        position
+            .make_move(mv)
+            .ok_or(RoundTripError::Unwritable(index))?;
+    }
+    let rendered = crate::KifFile::new(record)
+        .render()
+        .ok_or(RoundTripError::Unreadable)?;
+    let parsed = crate::parse_kif(&rendered).map_err(|_| RoundTripError::Unreadable)?;
+    compare_records(record, &parsed.record, Dialect::Kif.keeps_ply())
+}
+
+fn compare_records(
+    expected: &GameRecord,
+    actual: &GameRecord,
+    keeps_ply: bool,
+) -> Result<(), RoundTripError> {
+    if position_hash(expected.initial()) != position_hash(actual.initial())
+        || keeps_ply && expected.initial().ply() != actual.initial().ply()
+        || expected.len() != actual.len()
+    {
+        return Err(RoundTripError::RecordMismatch);
+    }
+    match expected
+        .moves()
+        .zip(actual.moves())
+        .position(|(a, b)| a != b)
+    {
+        Some(index) => Err(RoundTripError::MoveMismatch(index)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{Move, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn check_round_trip_works() {
+        let usis = [
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e",
+            "sfen 8k/9/3GGG3/9/9/9/1+P4S1S/+P8/+P+P+P1K1SS1 b - 1 moves 6c5b 1a1b 5c5d",
+            "sfen 4k4/2s1s4/9/9/9/9/9/4+S1+S2/4K4 w rbgsnlp 24 moves 7b6c 5h5g N*5c",
+        ];
+        for usi in usis {
+            let pos = Position::from_usi(usi).unwrap();
+            let record =
+                GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+            for &dialect in Dialect::all() {
+                assert_eq!(
+                    check_round_trip(&record, dialect),
+                    Ok(()),
+                    "{} {:?}",
+                    usi,
+                    dialect
+                );
+            }
+        }
+
+        let mut record = GameRecord::new(shogi_core::PartialPosition::startpos());
+        record.push(Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_5E,
+            promote: false,
+        });
+        assert_eq!(
+            check_round_trip(&record, Dialect::Official),
+            Err(RoundTripError::Unwritable(0)),
+        );
+    }
+}
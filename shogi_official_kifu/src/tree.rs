@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+use shogi_core::{Move, PartialPosition};
+
+use crate::GameRecord;
+
+/// A game with variations: a main line and lines that fork from it, each of which may fork again.
+///
+/// A variation is stored with the index of the move it replaces, counting from 0,
+/// and is itself a [`GameTree`] whose initial position is the one before that move.
+/// [`KifFile::from_tree`](crate::KifFile::from_tree) writes the variations as `変化：N手` sections,
+/// and [`parse_kif`](crate::parse_kif) reads them back.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, Position, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{GameRecord, GameTree};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d 2g2f").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let mut tree = GameTree::new(record);
+/// // 2. ８四歩 instead of ３四歩
+/// let mv = Move::Normal {
+///     from: Square::SQ_8C,
+///     to: Square::SQ_8D,
+///     promote: false,
+/// };
+/// let variation = tree.add_variation(1, [mv]).unwrap();
+/// assert_eq!(variation.main_line().len(), 1);
+/// assert_eq!(tree.variations().len(), 1);
+/// assert_eq!(tree.variations()[0].0, 1);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameTree {
+    pub(crate) main_line: GameRecord,
+    pub(crate) variations: Vec<(usize, GameTree)>,
+}
+
+impl GameTree {
+    /// Creates a [`GameTree`] with `main_line` and no variations.
+    pub fn new(main_line: GameRecord) -> Self {
+        Self {
+            main_line,
+            variations: Vec::new(),
+        }
+    }
+
+    /// Returns the main line.
+    pub fn main_line(&self) -> &GameRecord {
+        &self.main_line
+    }
+
+    /// Returns the variations as pairs of the index of the move they replace and the line, in the order they were added.
+    pub fn variations(&self) -> &[(usize, GameTree)] {
+        &self.variations
+    }
+
+    /// Adds a variation of `moves` that replaces the move at `index`, counting from 0, and returns it for adding variations to it.
+    ///
+    /// Returns [`None`] without adding anything if the main line has no move at `index`
+    /// or a move before it cannot be made. The moves of the variation are not checked.
+    pub fn add_variation<I: IntoIterator<Item = Move>>(
+        &mut self,
+        index: usize,
+        moves: I,
+    ) -> Option<&mut GameTree> {
+        if index >= self.main_line.len() {
+            return None;
+        }
+        let initial = self.position_before(index)?;
+        self.variations
+            .push((index, GameTree::new(GameRecord::with_moves(initial, moves))));
+        self.variations.last_mut().map(|(_, tree)| tree)
+    }
+
+    /// Returns the position before the move at `index` in the main line.
+    pub(crate) fn position_before(&self, index: usize) -> Option<PartialPosition> {
+        let mut position = self.main_line.initial().clone();
+        for mv in self.main_line.moves().take(index) {
+            position.make_move(mv)?;
+        }
+        Some(position)
+    }
+}
+
+impl From<GameRecord> for GameTree {
+    fn from(main_line: GameRecord) -> Self {
+        Self::new(main_line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::{PartialPosition, Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn add_variation_checks_index() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+        let mut tree = GameTree::from(record);
+        let mv = Move::Normal {
+            from: Square::SQ_2G,
+            to: Square::SQ_2F,
+            promote: false,
+        };
+        assert!(tree.add_variation(2, [mv]).is_none());
+        let variation = tree.add_variation(0, [mv]).unwrap();
+        assert_eq!(
+            variation.main_line().initial(),
+            &PartialPosition::startpos()
+        );
+        let mv = Move::Normal {
+            from: Square::SQ_8C,
+            to: Square::SQ_8D,
+            promote: false,
+        };
+        let variation = tree.add_variation(1, [mv]).unwrap();
+        assert_eq!(
+            variation.main_line().initial().side_to_move(),
+            shogi_core::Color::White
+        );
+        assert_eq!(tree.variations().len(), 2);
+    }
+}
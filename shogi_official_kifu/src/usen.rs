@@ -0,0 +1,251 @@
+use alloc::string::String;
+use shogi_core::{Color, Move, PartialPosition, Piece, PieceKind, Square, ToUsi};
+use shogi_usi_parser::FromUsi;
+
+use crate::{record::is_startpos, GameRecord, ReadLimits};
+
+/// The pieces that can be dropped, in the order USEN numbers them.
+const DROP_PIECES: [PieceKind; 7] = [
+    PieceKind::Pawn,
+    PieceKind::Lance,
+    PieceKind::Knight,
+    PieceKind::Silver,
+    PieceKind::Gold,
+    PieceKind::Bishop,
+    PieceKind::Rook,
+];
+
+/// Encodes a [`GameRecord`] in USEN, the URL-safe encoding of games used by Shogi Playground.
+///
+/// A USEN string is the initial position, `.`, the moves and `.` followed by how the game ended.
+/// The initial position is `~0` for the initial position of shogi with Black to move at ply 1,
+/// and otherwise SFEN with `/` replaced by `_`, spaces by `.` and `+` by `z`.
+/// Every move takes 3 characters in base 36.
+/// [`GameRecord`] does not know how the game ended, so that part is left empty.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::Position;
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::{usen, GameRecord};
+/// let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+/// let record = GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+/// let encoded = usen::encode(&record);
+/// assert_eq!(encoded, "~0.72m31u.");
+/// assert_eq!(usen::decode(&encoded), Ok(record));
+/// ```
+/// Ref: <https://github.com/sunfish-shogi/tsshogi/blob/main/src/usen.ts>
+pub fn encode(record: &GameRecord) -> String {
+    let mut ret = String::new();
+    let initial = record.initial();
+    if is_startpos(initial) && initial.ply() == 1 {
+        ret.push_str("~0");
+    } else {
+        let sfen = initial.to_usi_owned();
+        let sfen = sfen.strip_prefix("sfen ").unwrap_or(&sfen);
+        ret.extend(sfen.chars().map(|c| match c {
+            '/' => '_',
+            ' ' => '.',
+            '+' => 'z',
+            c => c,
+        }));
+    }
+    ret.push('.');
+    for mv in record.moves() {
+        let code = encode_move(mv);
+        for shift in [36 * 36, 36, 1] {
+            let digit = (code / shift % 36) as u32;
+            ret.push(core::char::from_digit(digit, 36).expect("digit < 36"));
+        }
+    }
+    ret.push('.');
+    ret
+}
+
+/// Decodes a [`GameRecord`] from USEN, as written by [`encode`].
+///
+/// How the game ended, after the last `.`, is not checked.
+/// Moves are checked only as far as [`PartialPosition::make_move`] does.
+/// Strings with more moves than [`ReadLimits::DEFAULT`] allows are rejected with [`UsenError::TooLarge`] before the moves are decoded.
+///
+/// Ref: <https://github.com/sunfish-shogi/tsshogi/blob/main/src/usen.ts>
+pub fn decode(s: &str) -> Result<GameRecord, UsenError> {
+    decode_with_limits(s, &ReadLimits::DEFAULT)
+}
+
+/// Same as [`decode`], but rejects strings with more moves than `limits` allows instead of [`ReadLimits::DEFAULT`].
+///
+/// USEN has no headers, so only [`ReadLimits::max_moves`] is used.
+pub fn decode_with_limits(s: &str, limits: &ReadLimits) -> Result<GameRecord, UsenError> {
+    // The initial position may contain `.`, so split from the end.
+    let mut parts = s.rsplitn(3, '.');
+    let (_end, moves, initial) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(end), Some(moves), Some(initial)) => (end, moves, initial),
+        _ => return Err(UsenError::InvalidFormat),
+    };
+    let initial = if initial == "~0" {
+        PartialPosition::startpos()
+    } else {
+        let sfen: String = initial
+            .chars()
+            .map(|c| match c {
+                '_' => '/',
+                '.' => ' ',
+                'z' => '+',
+                c => c,
+            })
+            .collect();
+        PartialPosition::from_usi(&alloc::format!("sfen {}", sfen))
+            .map_err(|_| UsenError::InvalidPosition)?
+    };
+    if moves.len() / 3 > limits.max_moves {
+        return Err(UsenError::TooLarge);
+    }
+    if moves.len() % 3 != 0 || !moves.is_ascii() {
+        return Err(UsenError::InvalidMove(moves.len() / 3));
+    }
+    let mut record = GameRecord::new(initial.clone());
+    let mut position = initial;
+    for (index, chunk) in moves.as_bytes().chunks(3).enumerate() {
+        let mv =
+            decode_move(chunk, position.side_to_move()).ok_or(UsenError::InvalidMove(index))?;
+        position
+            .make_move(mv)
+            .ok_or(UsenError::InvalidMove(index))?;
+        record.push(mv);
+    }
+    Ok(record)
+}
+
+/// The reason why a USEN string could not be decoded.
+///
+/// Moves are numbered from 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UsenError {
+    /// The string does not have three parts separated by `.`.
+    InvalidFormat,
+    /// The initial position is not valid.
+    InvalidPosition,
+    /// The move is not valid or cannot be made.
+    InvalidMove(usize),
+    /// The string has more moves than the [`ReadLimits`] allow.
+    TooLarge,
+}
+
+impl core::fmt::Display for UsenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UsenError::InvalidFormat => f.write_str("not in the USEN format"),
+            UsenError::InvalidPosition => f.write_str("invalid initial position"),
+            UsenError::InvalidMove(index) => write!(f, "invalid move at index {}", index),
+            UsenError::TooLarge => f.write_str("too many moves"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for UsenError {}
+
+// Squares are numbered from 9a to 1a, then from 9b to 1b, and so on.
+fn square_to_index(square: Square) -> u16 {
+    (square.rank() as u16 - 1) * 9 + (9 - square.file() as u16)
+}
+
+fn index_to_square(index: u16) -> Option<Square> {
+    Square::new(9 - (index % 9) as u8, (index / 9) as u8 + 1)
+}
+
+fn encode_move(mv: Move) -> u16 {
+    let (from, to, promote) = match mv {
+        Move::Normal { from, to, promote } => (square_to_index(from), to, promote),
+        Move::Drop { piece, to } => {
+            let index = DROP_PIECES
+                .iter()
+                .position(|&piece_kind| piece_kind == piece.piece_kind())
+                .unwrap_or(0);
+            (81 + index as u16, to, false)
+        }
+    };
+    (from * 81 + square_to_index(to)) * 2 + promote as u16
+}
+
+fn decode_move(chunk: &[u8], side: Color) -> Option<Move> {
+    let mut code = 0u16;
+    for &c in chunk {
+        code = code * 36 + (c as char).to_digit(36)? as u16;
+    }
+    let promote = code % 2 == 1;
+    let to = index_to_square(code / 2 % 81)?;
+    let from = code / 2 / 81;
+    if from < 81 {
+        Some(Move::Normal {
+            from: index_to_square(from)?,
+            to,
+            promote,
+        })
+    } else if !promote {
+        let piece_kind = *DROP_PIECES.get(from as usize - 81)?;
+        Some(Move::Drop {
+            piece: Piece::new(piece_kind, side),
+            to,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+
+    #[test]
+    fn round_trip() {
+        let usis = [
+            "startpos moves 7g7f 3c3d 8h2b+ 3a2b B*4e",
+            "sfen lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL w - 1 moves 3c3d",
+            "sfen 4k4/9/9/9/9/9/9/9/+R3K4 b BGSNLPrbgsnlp 12 moves P*5b 5a4a B*4i R*4h",
+        ];
+        for usi in usis {
+            let pos = Position::from_usi(usi).unwrap();
+            let record =
+                GameRecord::with_moves(pos.initial_position().clone(), pos.moves().to_vec());
+            let encoded = encode(&record);
+            assert!(encoded.chars().all(|c| c.is_ascii_alphanumeric()
+                || c == '.'
+                || c == '_'
+                || c == '-'
+                || c == '~'));
+            assert_eq!(decode(&encoded), Ok(record), "{}", encoded);
+        }
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode("~0"), Err(UsenError::InvalidFormat));
+        assert_eq!(
+            decode("x8_9_9_9_9_9_9_9_9.b.-.1.."),
+            Err(UsenError::InvalidPosition)
+        );
+        assert_eq!(decode("~0.72m31.r"), Err(UsenError::InvalidMove(1)));
+        // 7g7f twice
+        assert_eq!(decode("~0.72m72m."), Err(UsenError::InvalidMove(1)));
+    }
+
+    #[test]
+    fn decode_with_limits_works() {
+        let mut limits = ReadLimits::DEFAULT;
+        limits.max_moves = 1;
+        assert_eq!(
+            decode_with_limits("~0.72m31u.", &limits),
+            Err(UsenError::TooLarge)
+        );
+        limits.max_moves = 2;
+        assert!(decode_with_limits("~0.72m31u.", &limits).is_ok());
+        // Rejected before the moves are looked at.
+        let long = alloc::format!("~0.{}.", "z".repeat(3 * ReadLimits::DEFAULT.max_moves + 3));
+        assert_eq!(decode(&long), Err(UsenError::TooLarge));
+    }
+}
@@ -0,0 +1,190 @@
+use alloc::string::String;
+use shogi_core::{Move, PartialPosition};
+use shogi_usi_parser::FromUsi;
+
+use crate::{try_display_single_move, KifuError};
+
+/// Finds the string representation of a move given in USI, such as `7g7f` or `P*5e`.
+///
+/// This is the same as parsing `usi` into a [`Move`] and calling [`try_display_single_move`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_official_kifu::{display_usi_move, KifuError, UsiMoveError};
+/// let pos = PartialPosition::startpos();
+/// assert_eq!(display_usi_move(&pos, "7g7f"), Ok("▲７６歩".to_string()));
+/// assert_eq!(display_usi_move(&pos, "7g7"), Err(UsiMoveError::InvalidUsi));
+/// assert_eq!(
+///     display_usi_move(&pos, "5e5d"),
+///     Err(UsiMoveError::Undisplayable(KifuError::NoPieceAtOrigin)),
+/// );
+/// ```
+pub fn display_usi_move(position: &PartialPosition, usi: &str) -> Result<String, UsiMoveError> {
+    let mv = Move::from_usi(usi).map_err(|_| UsiMoveError::InvalidUsi)?;
+    try_display_single_move(position, mv).map_err(UsiMoveError::Undisplayable)
+}
+
+/// Writes a principal variation given in USI, such as `7g7f 3c3d 8h2b+`, as one line of official notation.
+///
+/// The moves are made one after another from `position`, so `同` is written wherever a move
+/// captures on the square the previous one moved to. Moves are separated by whitespace.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_official_kifu::{display_pv, KifuError, PvError, UsiMoveError};
+/// let pos = PartialPosition::startpos();
+/// assert_eq!(
+///     display_pv(&pos, "7g7f 3c3d 8h2b+ 3a2b"),
+///     Ok("▲７６歩△３４歩▲２２角成△同銀".to_string()),
+/// );
+/// assert_eq!(
+///     display_pv(&pos, "7g7f 8h2b+"),
+///     Err(PvError {
+///         index: 1,
+///         error: UsiMoveError::Undisplayable(KifuError::WrongSide),
+///     }),
+/// );
+/// ```
+pub fn display_pv(position: &PartialPosition, pv: &str) -> Result<String, PvError> {
+    let mut ret = String::new();
+    match write_pv(position, pv, &mut ret) {
+        Some(error) => Err(error),
+        None => Ok(ret),
+    }
+}
+
+/// Writes the moves of a principal variation given in USI up to the first one that cannot be written or made.
+///
+/// This is useful for variations from engines that may be cut off or stale.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::PartialPosition;
+/// # use shogi_official_kifu::display_pv_prefix;
+/// let pos = PartialPosition::startpos();
+/// assert_eq!(display_pv_prefix(&pos, "7g7f 3c3d 2b8h+ 8h2b+"), "▲７６歩△３４歩");
+/// ```
+pub fn display_pv_prefix(position: &PartialPosition, pv: &str) -> String {
+    let mut ret = String::new();
+    write_pv(position, pv, &mut ret);
+    ret
+}
+
+// Returns the first error, if any. The moves before it are written to `buffer`.
+fn write_pv(position: &PartialPosition, pv: &str, buffer: &mut String) -> Option<PvError> {
+    let mut position = position.clone();
+    for (index, usi) in pv.split_ascii_whitespace().enumerate() {
+        let error = |error| PvError { index, error };
+        let mv = match Move::from_usi(usi) {
+            Ok(mv) => mv,
+            Err(_) => return Some(error(UsiMoveError::InvalidUsi)),
+        };
+        match try_display_single_move(&position, mv) {
+            Ok(written) => buffer.push_str(&written),
+            Err(e) => return Some(error(UsiMoveError::Undisplayable(e))),
+        }
+        if position.make_move(mv).is_none() {
+            return Some(error(UsiMoveError::Undisplayable(KifuError::Unreachable)));
+        }
+    }
+    None
+}
+
+/// The reason why [`display_usi_move`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UsiMoveError {
+    /// The input is not a move in USI.
+    InvalidUsi,
+    /// The move cannot be written in the position.
+    Undisplayable(KifuError),
+}
+
+impl core::fmt::Display for UsiMoveError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UsiMoveError::InvalidUsi => f.write_str("not a move in USI"),
+            UsiMoveError::Undisplayable(e) => write!(f, "the move cannot be written: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for UsiMoveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UsiMoveError::InvalidUsi => None,
+            UsiMoveError::Undisplayable(e) => Some(e),
+        }
+    }
+}
+
+/// The reason why [`display_pv`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PvError {
+    /// The index of the move that failed, counting from 0.
+    pub index: usize,
+    /// Why the move failed.
+    pub error: UsiMoveError,
+}
+
+impl core::fmt::Display for PvError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "move at index {}: {}", self.index, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for PvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shogi_core::Position;
+
+    #[test]
+    fn display_usi_move_works() {
+        // USI does not write the color of a dropped piece.
+        let pos = Position::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w p 1").unwrap();
+        assert_eq!(display_usi_move(pos.inner(), "P*5e"), Ok("△５５歩".into()));
+        assert_eq!(
+            display_usi_move(pos.inner(), "p*5e"),
+            Err(UsiMoveError::InvalidUsi)
+        );
+        assert_eq!(
+            display_usi_move(pos.inner(), "5i5h"),
+            Err(UsiMoveError::Undisplayable(KifuError::WrongSide))
+        );
+    }
+
+    #[test]
+    fn display_pv_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        assert_eq!(display_pv(pos.inner(), ""), Ok(String::new()));
+        // The previous move is taken from the position.
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        assert_eq!(
+            display_pv(pos.inner(), "  3a2b\tB*4e\n"),
+            Ok("△同銀▲４５角".into())
+        );
+        assert_eq!(
+            display_pv(pos.inner(), "3a2b B*4e 7f"),
+            Err(PvError {
+                index: 2,
+                error: UsiMoveError::InvalidUsi,
+            })
+        );
+        assert_eq!(
+            display_pv_prefix(pos.inner(), "3a2b B*4e 7f"),
+            "△同銀▲４５角"
+        );
+    }
+}
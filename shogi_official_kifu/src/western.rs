@@ -0,0 +1,176 @@
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition, PieceKind, Square};
+
+use crate::kifu_move::{self, KifuMove};
+
+/// Finds the western representation of a [`Move`], such as `P-7f`, `Bx2b+` or `S*5e`.
+///
+/// A move is written as the piece, `-`, `x` for a capture or `*` for a drop, and the destination with the rank as a letter.
+/// `+` is appended to a promotion and `=` to a move that could promote but does not.
+/// Sides are not written.
+/// When the official notation needs `左`, `右`, `直`, `上`, `引` or `寄`, the origin is written after the piece instead (`G4b-5b`).
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Piece, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_single_move_western;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(display_single_move_western(&pos, mv), Some("P-7f".to_string()));
+///
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b S 1").unwrap();
+/// let mv = Move::Drop {
+///     piece: Piece::B_S,
+///     to: Square::SQ_5E,
+/// };
+/// assert_eq!(display_single_move_western(&pos, mv), Some("S*5e".to_string()));
+/// ```
+/// Ref: <https://en.wikipedia.org/wiki/Shogi_notation#Western_notation>
+pub fn display_single_move_western(
+    position: &PartialPosition,
+    mv: Move,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_western(position, mv, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the western representation of a [`Move`] and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`](crate::display_single_move_write).
+///
+/// Ref: <https://en.wikipedia.org/wiki/Shogi_notation#Western_notation>
+pub fn display_single_move_write_western<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let kifu_move = if let Ok(kifu_move) = kifu_move::analyze(position, mv) {
+        kifu_move
+    } else {
+        return Ok(None);
+    };
+    write_western(&kifu_move, b'a', w)?;
+    Ok(Some(()))
+}
+
+/// Writes `kifu_move` in the western notation, writing rank 1 as `first_rank`.
+///
+/// `b'a'` gives the ranks as letters, and `b'1'` as digits in the Hosking convention.
+pub(crate) fn write_western<W: Write>(
+    kifu_move: &KifuMove,
+    first_rank: u8,
+    w: &mut W,
+) -> core::fmt::Result {
+    w.write_str(piece_kind_to_western(kifu_move.piece_kind))?;
+    match kifu_move.from {
+        Some(from) => {
+            if kifu_move.relative.is_some() || kifu_move.movement.is_some() {
+                write_square(from, first_rank, w)?;
+            }
+            w.write_char(if kifu_move.capture { 'x' } else { '-' })?;
+        }
+        None => w.write_char('*')?,
+    }
+    write_square(kifu_move.to, first_rank, w)?;
+    match kifu_move.promotion {
+        Some(true) => w.write_char('+'),
+        Some(false) => w.write_char('='),
+        None => Ok(()),
+    }
+}
+
+fn write_square<W: Write>(square: Square, first_rank: u8, w: &mut W) -> core::fmt::Result {
+    w.write_char((b'0' + square.file()) as char)?;
+    w.write_char((first_rank + square.rank() - 1) as char)
+}
+
+fn piece_kind_to_western(piece_kind: PieceKind) -> &'static str {
+    match piece_kind {
+        PieceKind::King => "K",
+        PieceKind::Rook => "R",
+        PieceKind::Bishop => "B",
+        PieceKind::Gold => "G",
+        PieceKind::Silver => "S",
+        PieceKind::Knight => "N",
+        PieceKind::Lance => "L",
+        PieceKind::Pawn => "P",
+        PieceKind::ProRook => "+R",
+        PieceKind::ProBishop => "+B",
+        PieceKind::ProSilver => "+S",
+        PieceKind::ProKnight => "+N",
+        PieceKind::ProLance => "+L",
+        PieceKind::ProPawn => "+P",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{display_single_move_with_style, Notation, Style};
+    use shogi_core::{Piece, Position};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn western_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let pos = pos.inner();
+        let mv = Move::Normal {
+            from: Square::SQ_8H,
+            to: Square::SQ_2B,
+            promote: true,
+        };
+        assert_eq!(display_single_move_western(pos, mv).unwrap(), "Bx2b+");
+        let mv = Move::Normal {
+            from: Square::SQ_8H,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_western(pos, mv).unwrap(), "Bx2b=");
+
+        // Two golds can move to 5B: ５二金右 and ５二金左
+        let pos = PartialPosition::from_usi("sfen 4k4/3G1G3/9/9/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_4B,
+            to: Square::SQ_5B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_western(&pos, mv).unwrap(), "G4b-5b");
+        let style = Style {
+            notation: Notation::Hosking,
+            ..Style::default()
+        };
+        assert_eq!(
+            display_single_move_with_style(&pos, mv, &style).unwrap(),
+            "G42-52",
+        );
+        let mv = Move::Normal {
+            from: Square::SQ_4B,
+            to: Square::SQ_4A,
+            promote: false,
+        };
+        assert_eq!(display_single_move_western(&pos, mv).unwrap(), "G-4a");
+
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 w p 2").unwrap();
+        let mv = Move::Drop {
+            piece: Piece::W_P,
+            to: Square::SQ_5E,
+        };
+        assert_eq!(display_single_move_western(&pos, mv).unwrap(), "P*5e");
+
+        // No piece at the origin
+        let mv = Move::Normal {
+            from: Square::SQ_1A,
+            to: Square::SQ_1B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_western(&pos, mv), None);
+    }
+}
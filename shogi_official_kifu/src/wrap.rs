@@ -0,0 +1,122 @@
+use core::fmt::Write;
+
+/// Counts non-ASCII characters as two columns, which holds for everything the notation uses.
+pub(crate) fn display_width(s: &str) -> usize {
+    s.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+}
+
+/// Writes words into lines of at most `width` columns.
+///
+/// Words are never split, so a line may exceed `width` if a single word does not fit.
+/// Every line starts with `prefix`, which counts towards the width.
+pub(crate) struct Wrapper<'a> {
+    width: usize,
+    prefix: &'a str,
+    // The width of the current line, or `None` if nothing has been written on it.
+    column: Option<usize>,
+}
+
+impl<'a> Wrapper<'a> {
+    pub(crate) fn new(width: usize, prefix: &'a str) -> Self {
+        Self {
+            width,
+            prefix,
+            column: None,
+        }
+    }
+
+    /// Writes `word`, preceded by `separator` if it stays on the current line.
+    pub(crate) fn write_word<W: Write>(
+        &mut self,
+        word: &str,
+        separator: &str,
+        w: &mut W,
+    ) -> core::fmt::Result {
+        let word_width = display_width(word);
+        let column = match self.column {
+            Some(column) if column + display_width(separator) + word_width <= self.width => {
+                w.write_str(separator)?;
+                column + display_width(separator)
+            }
+            Some(_) => {
+                w.write_char('\n')?;
+                self.start_line(w)?
+            }
+            None => self.start_line(w)?,
+        };
+        w.write_str(word)?;
+        self.column = Some(column + word_width);
+        Ok(())
+    }
+
+    /// Ends the current line, writing `prefix` alone if nothing has been written on it.
+    pub(crate) fn end_line<W: Write>(&mut self, w: &mut W) -> core::fmt::Result {
+        if self.column.is_none() {
+            self.start_line(w)?;
+        }
+        w.write_char('\n')?;
+        self.column = None;
+        Ok(())
+    }
+
+    /// Ends the current line if something has been written on it.
+    pub(crate) fn finish<W: Write>(&mut self, w: &mut W) -> core::fmt::Result {
+        if self.column.is_some() {
+            self.end_line(w)?;
+        }
+        Ok(())
+    }
+
+    fn start_line<W: Write>(&mut self, w: &mut W) -> Result<usize, core::fmt::Error> {
+        w.write_str(self.prefix)?;
+        Ok(display_width(self.prefix))
+    }
+}
+
+/// Writes `text` with every line starting with `prefix`, breaking lines longer than `width` columns between characters.
+///
+/// Every line feed in `text` starts a new line, so an empty `text` or one ending with a line feed still has its last line,
+/// written as `prefix` alone. A character for which `escape` returns a string, such as `\r`, is written as that string,
+/// which is never split.
+pub(crate) fn write_wrapped<W: Write>(
+    text: &str,
+    prefix: &str,
+    width: usize,
+    escape: fn(char) -> Option<&'static str>,
+    w: &mut W,
+) -> core::fmt::Result {
+    let mut wrapper = Wrapper::new(width, prefix);
+    let mut buffer = [0; 4];
+    for line in text.split('\n') {
+        for c in line.chars() {
+            let word = match escape(c) {
+                Some(escaped) => escaped,
+                None => c.encode_utf8(&mut buffer),
+            };
+            wrapper.write_word(word, "", w)?;
+        }
+        wrapper.end_line(w)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_wrapped_works() {
+        let mut result = alloc::string::String::new();
+        write_wrapped("ab角換わり\n\nc", "*", 7, |_| None, &mut result).unwrap();
+        assert_eq!(result, "*ab角換\n*わり\n*\n*c\n");
+    }
+
+    #[test]
+    fn write_wrapped_keeps_empty_lines() {
+        for (text, expected) in [("", "*\n"), ("a\n", "*a\n*\n"), ("a\r\nb", "*a\\r\n*b\n")] {
+            let mut result = alloc::string::String::new();
+            write_wrapped(text, "*", 80, crate::sanitize::kif_escape, &mut result).unwrap();
+            assert_eq!(result, expected);
+        }
+    }
+}
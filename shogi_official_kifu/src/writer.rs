@@ -0,0 +1,305 @@
+use core::fmt::Write;
+use shogi_core::{IllegalMoveKind, LegalityChecker, Move, PartialPosition};
+use shogi_legality_lite::LiteLegalityChecker;
+
+use crate::{kifu_move, KifuError, KifuFormatter};
+
+/// Writes the moves of a game as they are played, one on a line, keeping track of the position.
+///
+/// [`KifuWriter::push`] checks that a move is legal, writes it with its ply number and makes it,
+/// so `同` is written whenever a move captures on the square the previous one moved to.
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{IllegalMoveKind, Move, PartialPosition, Square};
+/// # use shogi_official_kifu::{KifuWriter, KifuWriterError};
+/// let mut writer = KifuWriter::new(PartialPosition::startpos(), String::new());
+/// writer
+///     .push(Move::Normal {
+///         from: Square::SQ_7G,
+///         to: Square::SQ_7F,
+///         promote: false,
+///     })
+///     .unwrap();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7F,
+///     to: Square::SQ_7E,
+///     promote: false,
+/// };
+/// assert!(matches!(writer.push(mv), Err(KifuWriterError::Illegal(_))));
+/// writer
+///     .push(Move::Normal {
+///         from: Square::SQ_3C,
+///         to: Square::SQ_3D,
+///         promote: false,
+///     })
+///     .unwrap();
+/// assert_eq!(writer.ply(), 3);
+/// assert_eq!(writer.into_inner(), "1 ▲７６歩\n2 △３４歩\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct KifuWriter<W> {
+    position: PartialPosition,
+    w: W,
+    formatter: KifuFormatter,
+    numbered: bool,
+    #[cfg(feature = "log")]
+    log_moves: bool,
+}
+
+impl<W: Write> KifuWriter<W> {
+    /// Creates a [`KifuWriter`] that writes numbered moves in the official notation to `w`, starting from `position`.
+    pub fn new(position: PartialPosition, w: W) -> Self {
+        Self {
+            position,
+            w,
+            formatter: KifuFormatter::new(),
+            numbered: true,
+            #[cfg(feature = "log")]
+            log_moves: false,
+        }
+    }
+
+    /// Sets the [`KifuFormatter`] that moves are written with.
+    pub fn formatter(mut self, formatter: KifuFormatter) -> Self {
+        self.formatter = formatter;
+        self
+    }
+
+    /// Sets whether moves are prefixed with their ply numbers.
+    pub fn numbered(mut self, numbered: bool) -> Self {
+        self.numbered = numbered;
+        self
+    }
+
+    /// Sets whether every move that is made is also logged at [`log::Level::Info`].
+    ///
+    /// The message is the line written for the move, without the line feed,
+    /// followed by the [`position_hash`](crate::position_hash) of the position after the move in hexadecimal,
+    /// such as `1 ▲７６歩 hash=0123456789abcdef`. Moves that fail to be pushed are not logged.
+    #[cfg(feature = "log")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "log")))]
+    pub fn log_moves(mut self, log_moves: bool) -> Self {
+        self.log_moves = log_moves;
+        self
+    }
+
+    /// Returns the current position.
+    pub fn position(&self) -> &PartialPosition {
+        &self.position
+    }
+
+    /// Returns the ply of the next move.
+    pub fn ply(&self) -> u16 {
+        self.position.ply()
+    }
+
+    /// Returns a reference to the underlying [`Write`].
+    pub fn get_ref(&self) -> &W {
+        &self.w
+    }
+
+    /// Unwraps this [`KifuWriter`], returning the underlying [`Write`].
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+
+    /// Writes `mv` on a line of its own and makes it.
+    ///
+    /// The line is `{ply} {move}`, or only the move if [`numbered`](KifuWriter::numbered) is unset.
+    /// Nothing is written and the position is left as it is if `mv` is illegal or cannot be written.
+    ///
+    /// # Errors
+    /// - [`KifuWriterError::Illegal`] if `mv` is not legal in the current position.
+    /// - [`KifuWriterError::Unwritable`] if `mv` cannot be written.
+    /// - [`KifuWriterError::PlyOverflow`] if the current position is already at ply [`u16::MAX`].
+    /// - [`KifuWriterError::Write`] if the underlying [`Write`] fails.
+    ///   A part of the line may have been written, but the position is left as it is.
+    pub fn push(&mut self, mv: Move) -> Result<(), KifuWriterError> {
+        if self.position.ply() == u16::MAX {
+            return Err(KifuWriterError::PlyOverflow);
+        }
+        LiteLegalityChecker
+            .is_legal_partial(&self.position, mv)
+            .map_err(KifuWriterError::Illegal)?;
+        kifu_move::analyze(&self.position, mv).map_err(KifuWriterError::Unwritable)?;
+        let mut line = alloc::string::String::new();
+        if self.numbered {
+            write!(line, "{} ", self.position.ply()).map_err(|_| KifuWriterError::Write)?;
+        }
+        self.formatter
+            .format(&self.position, mv, &mut line)
+            .map_err(|_| KifuWriterError::Write)?;
+        line.push('\n');
+        self.w
+            .write_str(&line)
+            .map_err(|_| KifuWriterError::Write)?;
+        // Legal moves can always be made.
+        self.position.make_move(mv);
+        #[cfg(feature = "log")]
+        if self.log_moves {
+            log::info!(
+                "{} hash={:016x}",
+                line.trim_end(),
+                crate::position_hash(&self.position)
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The reason why [`KifuWriter::push`] failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum KifuWriterError {
+    /// The move is not legal.
+    Illegal(IllegalMoveKind),
+    /// The move cannot be written.
+    Unwritable(KifuError),
+    /// The position is at ply [`u16::MAX`], the largest that [`PartialPosition`] can count,
+    /// so no more moves can be made.
+    PlyOverflow,
+    /// The underlying [`Write`] failed.
+    Write,
+}
+
+impl core::fmt::Display for KifuWriterError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            KifuWriterError::Illegal(kind) => write!(f, "illegal move: {:?}", kind),
+            KifuWriterError::Unwritable(e) => write!(f, "the move cannot be written: {}", e),
+            KifuWriterError::PlyOverflow => f.write_str("too many moves"),
+            KifuWriterError::Write => f.write_str("failed to write"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl std::error::Error for KifuWriterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KifuWriterError::Unwritable(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support::FailingWriter, Style};
+    use shogi_core::{Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn kifu_writer_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+ 3a2b").unwrap();
+        let (opening, rest) = pos.moves().split_at(2);
+        let mut start = PartialPosition::startpos();
+        for &mv in opening {
+            start.make_move(mv).unwrap();
+        }
+        let mut writer = KifuWriter::new(start, String::new())
+            .formatter(KifuFormatter::with_style(Style::TRADITIONAL));
+        for &mv in rest {
+            writer.push(mv).unwrap();
+        }
+        assert_eq!(writer.get_ref(), "3 ▲２二角成\n4 △同銀\n");
+
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/4r4/4KG3 b - 1").unwrap();
+        let mut writer = KifuWriter::new(pos, String::new()).numbered(false);
+        // Leaves the king in check.
+        let mv = Move::Normal {
+            from: Square::SQ_4I,
+            to: Square::SQ_4H,
+            promote: false,
+        };
+        assert_eq!(
+            writer.push(mv),
+            Err(KifuWriterError::Illegal(IllegalMoveKind::IgnoredCheck))
+        );
+        assert_eq!(writer.ply(), 1);
+        assert_eq!(writer.get_ref(), "");
+        let mv = Move::Normal {
+            from: Square::SQ_4I,
+            to: Square::SQ_5H,
+            promote: false,
+        };
+        writer.push(mv).unwrap();
+        assert_eq!(writer.into_inner(), "▲５８金\n");
+    }
+
+    #[test]
+    fn kifu_writer_keeps_position_on_write_errors() {
+        let mut writer = KifuWriter::new(PartialPosition::startpos(), FailingWriter::new(3));
+        let mv = Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        };
+        assert_eq!(writer.push(mv), Err(KifuWriterError::Write));
+        assert_eq!(writer.ply(), 1);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn kifu_writer_logs_moves() {
+        use std::sync::Mutex;
+
+        struct Logger(Mutex<Vec<String>>);
+
+        impl log::Log for Logger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                let message = alloc::format!("{} {}", record.level(), record.args());
+                self.0.lock().unwrap().push(message);
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: Logger = Logger(Mutex::new(Vec::new()));
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Info);
+
+        let mut writer =
+            KifuWriter::new(PartialPosition::startpos(), String::new()).log_moves(true);
+        let mv = Move::Normal {
+            from: Square::SQ_7G,
+            to: Square::SQ_7F,
+            promote: false,
+        };
+        writer.push(mv).unwrap();
+        assert!(writer.push(mv).is_err());
+        let hash = crate::position_hash(writer.position());
+        assert_eq!(
+            *LOGGER.0.lock().unwrap(),
+            [alloc::format!("INFO 1 ▲７６歩 hash={:016x}", hash)]
+        );
+    }
+
+    #[test]
+    fn kifu_writer_stops_at_the_last_ply() {
+        let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/9/4K4 b - 65534").unwrap();
+        let mut writer = KifuWriter::new(pos, String::new());
+        let mv = Move::Normal {
+            from: Square::SQ_5I,
+            to: Square::SQ_5H,
+            promote: false,
+        };
+        writer.push(mv).unwrap();
+        assert_eq!(writer.ply(), u16::MAX);
+        let mv = Move::Normal {
+            from: Square::SQ_5A,
+            to: Square::SQ_5B,
+            promote: false,
+        };
+        assert_eq!(writer.push(mv), Err(KifuWriterError::PlyOverflow));
+        assert_eq!(writer.ply(), u16::MAX);
+        assert_eq!(writer.into_inner(), "65534 ▲５８玉\n");
+    }
+}
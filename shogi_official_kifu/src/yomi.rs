@@ -0,0 +1,209 @@
+use core::fmt::Write;
+use shogi_core::{Move, PartialPosition};
+
+use crate::{display_single_move_write_with_style, Notation, Style};
+
+/// Finds the reading of a [`Move`] in hiragana, such as `ななろくふ` for `▲７６歩`.
+///
+/// The reading follows the official notation: `同` is read `どう`, and `打`, `成`, `不成` and the suffixes
+/// `左`, `右`, `直`, `上`, `引` and `寄` are read as they are spoken (`うつ`, `なり`, `ならず`, `ひだり`, …).
+/// Sides are not read. This is meant for text-to-speech, so nothing separates the words.
+///
+/// Same as [`display_single_move_with_style`](crate::display_single_move_with_style) with [`Notation::Yomi`].
+///
+/// Examples:
+/// ```
+/// # use shogi_core::{Move, PartialPosition, Square};
+/// # use shogi_usi_parser::FromUsi;
+/// # use shogi_official_kifu::display_single_move_yomi;
+/// let pos = PartialPosition::startpos();
+/// let mv = Move::Normal {
+///     from: Square::SQ_7G,
+///     to: Square::SQ_7F,
+///     promote: false,
+/// };
+/// assert_eq!(display_single_move_yomi(&pos, mv), Some("ななろくふ".to_string()));
+///
+/// // ▲２二角打: the bishop on 8H can also move to 2B.
+/// let pos = PartialPosition::from_usi("sfen 4k4/9/9/9/9/9/9/1B7/4K4 b B 1").unwrap();
+/// let mv = Move::Drop {
+///     piece: shogi_core::Piece::B_B,
+///     to: Square::SQ_2B,
+/// };
+/// assert_eq!(display_single_move_yomi(&pos, mv), Some("ににかくうつ".to_string()));
+/// ```
+pub fn display_single_move_yomi(
+    position: &PartialPosition,
+    mv: Move,
+) -> Option<alloc::string::String> {
+    let mut ret = alloc::string::String::new();
+    display_single_move_write_yomi(position, mv, &mut ret)
+        .expect("fmt::Write for String cannot return an error")?;
+    Some(ret)
+}
+
+/// Finds the reading of a [`Move`] in hiragana and write it to a [`Write`].
+///
+/// # Errors
+/// Same as [`display_single_move_write`](crate::display_single_move_write).
+pub fn display_single_move_write_yomi<W: Write>(
+    position: &PartialPosition,
+    mv: Move,
+    w: &mut W,
+) -> Result<Option<()>, core::fmt::Error> {
+    let style = Style {
+        notation: Notation::Yomi,
+        ..Style::OFFICIAL
+    };
+    display_single_move_write_with_style(position, mv, &style, w)
+}
+
+/// A [`Write`] that writes the reading of the Japanese notation written to it.
+///
+/// Side markers and spaces are dropped, and `不` is held until the `成` that follows it.
+pub(crate) struct Reading<'a, W> {
+    inner: &'a mut W,
+    negated: bool,
+}
+
+impl<'a, W: Write> Reading<'a, W> {
+    pub(crate) fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            negated: false,
+        }
+    }
+}
+
+impl<W: Write> Write for Reading<'_, W> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for c in s.chars() {
+            self.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> core::fmt::Result {
+        if c == '不' {
+            self.negated = true;
+            return Ok(());
+        }
+        let reading = if c == '成' && self.negated {
+            self.negated = false;
+            "ならず"
+        } else {
+            reading(c)
+        };
+        self.inner.write_str(reading)
+    }
+}
+
+fn reading(c: char) -> &'static str {
+    match c {
+        '１' | '一' => "いち",
+        '２' | '二' => "に",
+        '３' | '三' => "さん",
+        '４' | '四' => "よん",
+        '５' | '五' => "ご",
+        '６' | '六' => "ろく",
+        '７' | '七' => "なな",
+        '８' | '八' => "はち",
+        '９' | '九' => "きゅう",
+        '同' => "どう",
+        '歩' => "ふ",
+        '香' => "きょう",
+        '桂' => "けい",
+        '銀' => "ぎん",
+        '金' => "きん",
+        '角' => "かく",
+        '飛' => "ひしゃ",
+        '玉' => "ぎょく",
+        '王' => "おう",
+        'と' => "と",
+        '杏' => "なりきょう",
+        '圭' => "なりけい",
+        '全' => "なりぎん",
+        '馬' => "うま",
+        '竜' | '龍' => "りゅう",
+        '成' => "なり",
+        '打' => "うつ",
+        '左' => "ひだり",
+        '右' => "みぎ",
+        '直' => "すぐ",
+        '上' => "あがる",
+        '引' => "ひく",
+        '寄' => "よる",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{display_single_move_with_style, KingGlyphs};
+    use shogi_core::{Position, Square};
+    use shogi_usi_parser::FromUsi;
+
+    #[test]
+    fn yomi_works() {
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_8H,
+            to: Square::SQ_2B,
+            promote: true,
+        };
+        assert_eq!(
+            display_single_move_yomi(pos.inner(), mv).unwrap(),
+            "ににかくなり"
+        );
+        let mv = Move::Normal {
+            from: Square::SQ_8H,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_yomi(pos.inner(), mv).unwrap(),
+            "ににかくならず"
+        );
+
+        let pos = Position::from_usi("startpos moves 7g7f 3c3d 8h2b+").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_3A,
+            to: Square::SQ_2B,
+            promote: false,
+        };
+        assert_eq!(
+            display_single_move_yomi(pos.inner(), mv).unwrap(),
+            "どうぎん"
+        );
+        let mut style = Style {
+            notation: Notation::Yomi,
+            ..Style::OFFICIAL
+        };
+        style.write_same = false;
+        assert_eq!(
+            display_single_move_with_style(pos.inner(), mv, &style).unwrap(),
+            "ににぎん"
+        );
+
+        // Two golds can move to 5B: ５二金右 and ５二金左
+        let pos = PartialPosition::from_usi("sfen 4k4/3G1G3/9/9/9/9/9/9/4K4 b - 1").unwrap();
+        let mv = Move::Normal {
+            from: Square::SQ_4B,
+            to: Square::SQ_5B,
+            promote: false,
+        };
+        assert_eq!(display_single_move_yomi(&pos, mv).unwrap(), "ごにきんみぎ");
+        let mv = Move::Normal {
+            from: Square::SQ_5I,
+            to: Square::SQ_5H,
+            promote: false,
+        };
+        assert_eq!(display_single_move_yomi(&pos, mv).unwrap(), "ごはちぎょく");
+        style.kings = KingGlyphs::SideToMoveOu;
+        assert_eq!(
+            display_single_move_with_style(&pos, mv, &style).unwrap(),
+            "ごはちおう"
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Regression tests registered from data files in `tests/regressions/`.
+//!
+//! Each file holds one case per line in the form `position | move | expected`, where
+//! - `position` is a position in USI format (`sfen ...` or `startpos`, optionally followed by `moves ...`),
+//! - `move` is the move to display in USI format,
+//! - `expected` is the expected output of `display_single_move`, or `-` if it should return `None`.
+//!
+//! Empty lines and lines starting with `#` are ignored.
+//! To register a new file, add a `regression!` line with the file's stem.
+
+use shogi_core::{Move, Piece, Position};
+use shogi_official_kifu::display_single_move;
+use shogi_usi_parser::FromUsi;
+
+fn run(name: &str, data: &str) {
+    for (index, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let location = format!("{}:{}", name, index + 1);
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        assert_eq!(fields.len(), 3, "{}: malformed line", location);
+        let position = Position::from_usi(fields[0])
+            .unwrap_or_else(|e| panic!("{}: invalid position: {:?}", location, e));
+        let position = position.inner();
+        let mut mv = Move::from_usi(fields[1])
+            .unwrap_or_else(|e| panic!("{}: invalid move: {:?}", location, e));
+        if let Move::Drop { piece, to } = mv {
+            let piece = Piece::new(piece.piece_kind(), position.side_to_move());
+            mv = Move::Drop { piece, to };
+        }
+        let expected = match fields[2] {
+            "-" => None,
+            expected => Some(expected.to_string()),
+        };
+        assert_eq!(display_single_move(position, mv), expected, "{}", location);
+    }
+}
+
+macro_rules! regression {
+    ($name:ident) => {
+        #[test]
+        fn $name() {
+            run(
+                concat!(stringify!($name), ".txt"),
+                include_str!(concat!("regressions/", stringify!($name), ".txt")),
+            );
+        }
+    };
+}
+
+regression!(pinned_pieces);
+regression!(promotion_zone);
+regression!(same_square);